@@ -1,7 +1,13 @@
 package com.clevertree.md2jsx
 
 import android.util.Log
+import org.json.JSONArray
 
+/**
+ * Native failures surface as exceptions rather than crashes: malformed
+ * options throw `IllegalArgumentException`, anything else
+ * `RuntimeException`.
+ */
 object MarkdownParser {
     init {
         try {
@@ -12,9 +18,59 @@ object MarkdownParser {
     }
 
     fun parse(markdown: String, allowedTags: List<String> = emptyList()): String {
-        val allowedTagsJson = "[\"" + allowedTags.joinToString("\",\"") + "\"]"
+        val allowedTagsJson = JSONArray(allowedTags).toString()
         return nativeParse(markdown, allowedTagsJson)
     }
 
+    /**
+     * Parses with the full options, given as `TranspileOptions` JSON with the
+     * Rust field names (`{"allowed_tags": ["Card"], "soft_break": "Br"}`).
+     *
+     * @throws IllegalArgumentException if the options are malformed.
+     */
+    fun parseWithOptions(markdown: String, optionsJson: String): String {
+        return nativeParseWithOptions(markdown, optionsJson)
+    }
+
+    /** Same AST in the compact encoding: one-letter keys, bare text strings. */
+    fun parseCompact(markdown: String, allowedTags: List<String> = emptyList()): String {
+        val allowedTagsJson = JSONArray(allowedTags).toString()
+        return nativeParseCompact(markdown, allowedTagsJson)
+    }
+
+    /**
+     * HTML in the subset `HtmlCompat.fromHtml` understands, for showing
+     * markdown in a plain `TextView` without a WebView or Compose.
+     */
+    fun parseHtml(markdown: String, allowedTags: List<String> = emptyList()): String {
+        val allowedTagsJson = JSONArray(allowedTags).toString()
+        return nativeParseHtml(markdown, allowedTagsJson)
+    }
+
+    /**
+     * Text with span, paragraph and link ranges (UTF-16 offsets) for
+     * building a Compose `AnnotatedString`.
+     */
+    fun parseCompose(markdown: String, allowedTags: List<String> = emptyList()): String {
+        val allowedTagsJson = JSONArray(allowedTags).toString()
+        return nativeParseCompose(markdown, allowedTagsJson)
+    }
+
+    /** Same AST as MessagePack bytes; requires the `msgpack` feature. */
+    fun parseMsgpack(markdown: String, allowedTags: List<String> = emptyList()): ByteArray {
+        val allowedTagsJson = JSONArray(allowedTags).toString()
+        return nativeParseMsgpack(markdown, allowedTagsJson)
+    }
+
     private external fun nativeParse(markdown: String, allowedTagsJson: String): String
+
+    private external fun nativeParseWithOptions(markdown: String, optionsJson: String): String
+
+    private external fun nativeParseCompact(markdown: String, allowedTagsJson: String): String
+
+    private external fun nativeParseCompose(markdown: String, allowedTagsJson: String): String
+
+    private external fun nativeParseHtml(markdown: String, allowedTagsJson: String): String
+
+    private external fun nativeParseMsgpack(markdown: String, allowedTagsJson: String): ByteArray
 }
@@ -0,0 +1,6 @@
+//! Prints the TypeScript declarations for the AST:
+//! `cargo run --example typescript --features typescript > pkg/ast.d.ts`
+
+fn main() {
+    print!("{}", md2jsx::typescript_definitions());
+}
@@ -0,0 +1,70 @@
+/* Generated with cbindgen --config cbindgen.toml --output include/md2jsx.h */
+
+#ifndef MD2JSX_H
+#define MD2JSX_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Outcome of the last `md2jsx_parse` call on the calling thread.
+ */
+typedef enum Md2jsxStatus {
+  MD2JSX_STATUS_OK = 0,
+  /**
+   * `input` was null.
+   */
+  MD2JSX_STATUS_NULL_ARGUMENT = 1,
+  /**
+   * `input` or `options_json` was not UTF-8.
+   */
+  MD2JSX_STATUS_INVALID_UTF8 = 2,
+  /**
+   * `options_json` did not deserialize to `TranspileOptions`.
+   */
+  MD2JSX_STATUS_INVALID_OPTIONS = 3,
+  /**
+   * The parser panicked.
+   */
+  MD2JSX_STATUS_PANIC = 4,
+} Md2jsxStatus;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Parses the NUL-terminated UTF-8 `input` with `options_json` (a
+ * `TranspileOptions` object, or null for the defaults) and returns the
+ * AST as a JSON string, to be released with `md2jsx_free`. Returns null
+ * on error; `md2jsx_last_status` tells why.
+ *
+ * # Safety
+ *
+ * `input` and `options_json`, if not null, must point to NUL-terminated
+ * strings valid for the duration of the call.
+ */
+char *md2jsx_parse(const char *input, const char *options_json);
+
+/**
+ * Status of the last `md2jsx_parse` call on the calling thread.
+ */
+Md2jsxStatus md2jsx_last_status(void);
+
+/**
+ * Releases a string returned by `md2jsx_parse`. Null is ignored.
+ *
+ * # Safety
+ *
+ * `json` must be null or a pointer returned by `md2jsx_parse` that has
+ * not been freed.
+ */
+void md2jsx_free(char *json);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* MD2JSX_H */
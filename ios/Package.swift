@@ -0,0 +1,16 @@
+// swift-tools-version:5.7
+import PackageDescription
+
+// Run scripts/build-ios.sh first to build Md2jsxFFI.xcframework and
+// Sources/Md2jsx/Md2jsx.swift.
+let package = Package(
+    name: "Md2jsx",
+    platforms: [.iOS(.v13)],
+    products: [
+        .library(name: "Md2jsx", targets: ["Md2jsx"]),
+    ],
+    targets: [
+        .binaryTarget(name: "Md2jsxFFI", path: "Md2jsxFFI.xcframework"),
+        .target(name: "Md2jsx", dependencies: ["Md2jsxFFI"]),
+    ]
+)
@@ -0,0 +1,9 @@
+// The entry point matching the Android `MarkdownParser`, over the UniFFI
+// bindings generated into Md2jsx.swift.
+
+public typealias Node = MarkdownNode
+
+/// Parses `markdown` into the md2jsx AST.
+public func parse(markdown: String, options: MarkdownOptions = MarkdownOptions()) throws -> [Node] {
+    try parseMarkdown(markdown: markdown, options: options)
+}
@@ -0,0 +1,20 @@
+// Web Worker entry point: parses off the main thread and transfers the
+// encoded AST back without a structured clone.
+//
+//   const worker = new Worker(new URL("@clevertree/md2jsx/js/worker.js", import.meta.url), { type: "module" });
+//   worker.postMessage({ id, markdown, options, format });
+//   worker.onmessage = ({ data: { id, buffer, error } }) => ...;
+
+import init, { transpile_buffer } from "../pkg/md2jsx.js";
+
+const ready = init();
+
+self.onmessage = async ({ data: { id, markdown, options, format } }) => {
+    try {
+        await ready;
+        const buffer = transpile_buffer(markdown, options, format);
+        self.postMessage({ id, format: format ?? "json", buffer }, [buffer]);
+    } catch (error) {
+        self.postMessage({ id, error: String(error) });
+    }
+};
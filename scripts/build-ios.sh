@@ -0,0 +1,39 @@
+#!/bin/bash
+set -e
+
+# Builds ios/Md2jsxFFI.xcframework and the generated Swift bindings for the
+# Swift package in ios/. Run on macOS with Xcode and the iOS targets:
+#   rustup target add aarch64-apple-ios aarch64-apple-ios-sim x86_64-apple-ios
+
+TARGETS=("aarch64-apple-ios" "aarch64-apple-ios-sim" "x86_64-apple-ios")
+IOS_DIR="ios"
+HEADERS_DIR="target/ios/headers"
+
+for TARGET in "${TARGETS[@]}"; do
+    echo "Building for $TARGET..."
+    cargo build --release --target $TARGET --features uniffi
+done
+
+# The Swift sources and C header come from the host build's metadata.
+scripts/generate-bindings.sh target/ios/bindings
+cp target/ios/bindings/swift/Md2jsx.swift "$IOS_DIR/Sources/Md2jsx/Md2jsx.swift"
+
+rm -rf "$HEADERS_DIR"
+mkdir -p "$HEADERS_DIR"
+cp target/ios/bindings/swift/Md2jsxFFI.h "$HEADERS_DIR/"
+cp target/ios/bindings/swift/Md2jsxFFI.modulemap "$HEADERS_DIR/module.modulemap"
+
+# One slice per platform: the two simulator architectures share one.
+mkdir -p target/ios-simulator/release
+lipo -create \
+    target/aarch64-apple-ios-sim/release/libmd2jsx.a \
+    target/x86_64-apple-ios/release/libmd2jsx.a \
+    -output target/ios-simulator/release/libmd2jsx.a
+
+rm -rf "$IOS_DIR/Md2jsxFFI.xcframework"
+xcodebuild -create-xcframework \
+    -library target/aarch64-apple-ios/release/libmd2jsx.a -headers "$HEADERS_DIR" \
+    -library target/ios-simulator/release/libmd2jsx.a -headers "$HEADERS_DIR" \
+    -output "$IOS_DIR/Md2jsxFFI.xcframework"
+
+echo "iOS build complete. The Swift package is in $IOS_DIR"
@@ -0,0 +1,15 @@
+#!/bin/bash
+set -e
+
+# Generates the UniFFI Swift and Kotlin bindings from the compiled library.
+# Uses the debug build: the release profile strips the UniFFI metadata.
+OUT_DIR="${1:-bindings}"
+
+cargo build --features uniffi
+LIB=$(ls target/debug/libmd2jsx.so target/debug/libmd2jsx.dylib 2>/dev/null | head -n 1)
+cargo run --features uniffi-cli --bin uniffi-bindgen -- generate \
+    --library "$LIB" --language swift --out-dir "$OUT_DIR/swift"
+cargo run --features uniffi-cli --bin uniffi-bindgen -- generate \
+    --library "$LIB" --language kotlin --out-dir "$OUT_DIR/kotlin"
+
+echo "Bindings are in $OUT_DIR"
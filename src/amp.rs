@@ -0,0 +1,136 @@
+//! AMP HTML output, for publishers serving AMP pages.
+
+use indexmap::IndexMap;
+use serde::Serialize;
+use serde_json::Value;
+use crate::elements::html_attribute;
+use crate::render_html::render_html;
+use crate::{Node, SchemeAction, UrlSchemes};
+
+/// Elements AMP forbids or only allows through an extension component.
+const DISALLOWED_TAGS: &[&str] = &[
+    "script", "style", "iframe", "frame", "frameset", "object", "embed", "param", "applet", "form", "input", "textarea",
+    "select", "option", "video", "audio", "base", "link", "meta",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AmpIssueKind {
+    /// An image without numeric `width` and `height`, which `amp-img`
+    /// requires.
+    ImageWithoutSize,
+    /// An element AMP does not allow, or a custom component.
+    DisallowedElement,
+    /// Raw HTML, which cannot be checked.
+    RawHtml,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AmpIssue {
+    /// Index path of the offending node, as in `Patch`.
+    pub path: Vec<usize>,
+    pub kind: AmpIssueKind,
+    /// Tag of the offending element; `None` for raw nodes.
+    pub tag: Option<String>,
+}
+
+/// Renders `nodes` as AMP-valid HTML: `img` becomes
+/// `<amp-img layout="responsive">`, event handlers, inline styles and
+/// links with disallowed URL schemes are stripped, and task list
+/// checkboxes become `☑`/`☐`. Images without a size, elements AMP does not
+/// allow (scripts, forms, media, custom components) and raw HTML are not
+/// rewritten; they are reported instead, and nothing is rendered.
+pub fn render_amp_html(nodes: &[Node]) -> Result<String, Vec<AmpIssue>> {
+    let mut issues = Vec::new();
+    let converted = convert_all(nodes, &mut Vec::new(), &mut issues);
+    match issues.is_empty() {
+        true => Ok(render_html(&converted)),
+        false => Err(issues),
+    }
+}
+
+fn convert_all(nodes: &[Node], path: &mut Vec<usize>, issues: &mut Vec<AmpIssue>) -> Vec<Node> {
+    let mut converted = Vec::with_capacity(nodes.len());
+    for (i, node) in nodes.iter().enumerate() {
+        path.push(i);
+        if let Some(node) = convert(node, path, issues) {
+            converted.push(node);
+        }
+        path.pop();
+    }
+    converted
+}
+
+fn convert(node: &Node, path: &mut Vec<usize>, issues: &mut Vec<AmpIssue>) -> Option<Node> {
+    let issue = |kind, tag: Option<&str>, path: &[usize]| AmpIssue { path: path.to_vec(), kind, tag: tag.map(String::from) };
+    let (tag, props, children) = match node {
+        Node::Element { tag, props, children, .. } => (tag.as_str(), props, children),
+        Node::Fragment { children } => return Some(Node::Fragment { children: convert_all(children, path, issues) }),
+        Node::Raw { format, .. } if format == "html" => {
+            issues.push(issue(AmpIssueKind::RawHtml, None, path));
+            return None;
+        }
+        Node::Comment { .. } => return None,
+        node => return Some(node.clone()),
+    };
+    if tag == "input" && props.get("type").and_then(Value::as_str) == Some("checkbox") {
+        let checked = props.get("checked").and_then(Value::as_bool).unwrap_or(false);
+        return Some(Node::text(if checked { "\u{2611} " } else { "\u{2610} " }));
+    }
+    if DISALLOWED_TAGS.contains(&tag) || tag.starts_with(|c: char| c.is_ascii_uppercase()) {
+        issues.push(issue(AmpIssueKind::DisallowedElement, Some(tag), path));
+        return None;
+    }
+    let mut props = amp_props(props);
+    if tag != "img" {
+        let children = convert_all(children, path, issues);
+        return Some(Node::Element { tag: tag.to_string(), props, children, span: None, content_id: None });
+    }
+    let size = |key: &str| props.get(key).is_some_and(|value| match value {
+        Value::Number(_) => true,
+        Value::String(value) => value.parse::<u32>().is_ok(),
+        _ => false,
+    });
+    if !size("width") || !size("height") {
+        issues.push(issue(AmpIssueKind::ImageWithoutSize, Some(tag), path));
+    }
+    props.insert("layout".to_string(), "responsive".into());
+    let image = Node::Element { tag: "amp-img".to_string(), props, children: Vec::new(), span: None, content_id: None };
+    // What an unclosed `<img>` in allowed HTML holds follows it.
+    let children = convert_all(children, path, issues);
+    Some(Node::Fragment { children: std::iter::once(image).chain(children).collect() })
+}
+
+/// Props under their HTML names, without those AMP rejects.
+fn amp_props(props: &IndexMap<String, Value>) -> IndexMap<String, Value> {
+    let schemes = UrlSchemes::default();
+    props.iter()
+        .map(|(key, value)| (html_attribute(key), value))
+        .filter(|(name, value)| {
+            let lower = name.to_ascii_lowercase();
+            let url = matches!(lower.as_str(), "href" | "src") && value.as_str().is_some_and(|url| schemes.action(url) != SchemeAction::Allow);
+            !(lower.starts_with("on") || lower == "style" || lower == "xmlns" || url)
+        })
+        .map(|(name, value)| (name.to_string(), value.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_render_amp_html() {
+        let markdown = "<img src=\"/a.png\" width=\"400\" height=\"300\" alt=\"A\" onclick=\"f()\" style=\"x\">\n\n- [ ] todo";
+        let options = TranspileOptions::builder().allowed_tags(["img"]).build();
+        assert_eq!(render_amp_html(&parse(markdown, &options)).unwrap(),
+            "<amp-img src=\"/a.png\" width=\"400\" height=\"300\" alt=\"A\" layout=\"responsive\"></amp-img>\n<ul><li>\u{2610} todo</li></ul>");
+
+        let markdown = "![b](/b.png)\n\n<iframe src=\"/x\"></iframe>";
+        let options = TranspileOptions::builder().allowed_tags(["iframe"]).build();
+        let issues = render_amp_html(&parse(markdown, &options)).unwrap_err();
+        let kinds: Vec<_> = issues.iter().map(|issue| (issue.kind, issue.path.clone())).collect();
+        assert_eq!(kinds, [(AmpIssueKind::ImageWithoutSize, vec![0, 0]), (AmpIssueKind::DisallowedElement, vec![1])]);
+    }
+}
@@ -0,0 +1,179 @@
+//! The HTML subset Android's `Html.fromHtml`/`HtmlCompat.fromHtml`
+//! understands, for showing parsed markdown in a plain `TextView`.
+
+use indexmap::IndexMap;
+use serde_json::Value;
+use crate::markdown::BLOCK_TAGS;
+use crate::render_html::render_html;
+use crate::{text_content, Node};
+
+/// Elements `Html.fromHtml` renders, kept as they are.
+const KEPT_TAGS: &[&str] = &[
+    "p", "div", "br", "strong", "b", "em", "i", "u", "del", "s", "sup", "sub", "big", "small", "tt", "h1", "h2", "h3",
+    "h4", "h5", "h6", "a", "img", "blockquote", "span",
+];
+
+/// Indentation per list nesting level.
+const INDENT: &str = "\u{a0}\u{a0}\u{a0}\u{a0}";
+
+/// Renders `nodes` as HTML for `HtmlCompat.fromHtml`: lists become
+/// `•`/numbered lines (nested lists indented with non-breaking spaces),
+/// code becomes `tt` with line breaks kept, tables become lines of
+/// ` | `-separated cells with a bold header, and thematic breaks a line of
+/// dashes. Other unsupported elements are unwrapped; only `href`, `src`
+/// and `span` styles are kept, and comments and raw HTML are dropped.
+pub fn render_android_html(nodes: &[Node]) -> String {
+    render_html(&convert_all(nodes))
+}
+
+fn convert_all(nodes: &[Node]) -> Vec<Node> {
+    let mut converted = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        convert(node, &mut converted);
+    }
+    converted
+}
+
+fn convert(node: &Node, out: &mut Vec<Node>) {
+    let Node::Element { tag, props, children, .. } = node else {
+        match node {
+            Node::Text { content, .. } => out.push(Node::text(content.clone())),
+            Node::Fragment { children } => out.extend(convert_all(children)),
+            _ => {}
+        }
+        return;
+    };
+    match tag.as_str() {
+        "code" => out.push(Node::element("tt").child(Node::text(text_content(children)))),
+        "pre" => out.push(Node::element("p").child(Node::element("tt").with_children(code_lines(&text_content(children))))),
+        "ul" | "ol" => {
+            let mut lines = Vec::new();
+            list_lines(node, 0, &mut lines);
+            out.push(Node::element("p").with_children(join_lines(lines)));
+        }
+        "table" => {
+            let mut rows = Vec::new();
+            table_rows(children, false, &mut rows);
+            out.push(Node::element("p").with_children(join_lines(rows)));
+        }
+        "hr" => out.push(Node::element("p").child(Node::text("\u{2014}".repeat(12)))),
+        "input" => {
+            if props.get("type").and_then(Value::as_str) == Some("checkbox") {
+                let checked = props.get("checked").and_then(Value::as_bool).unwrap_or(false);
+                out.push(Node::text(if checked { "\u{2611} " } else { "\u{2610} " }));
+            }
+        }
+        tag if KEPT_TAGS.contains(&tag) => out.push(Node::Element {
+            tag: tag.to_string(),
+            props: android_props(tag, props),
+            children: convert_all(children),
+            span: None,
+            content_id: None,
+        }),
+        _ => out.extend(convert_all(children)),
+    }
+}
+
+fn android_props(tag: &str, props: &IndexMap<String, Value>) -> IndexMap<String, Value> {
+    let kept = match tag {
+        "a" => "href",
+        "img" => "src",
+        "span" => "style",
+        _ => return IndexMap::new(),
+    };
+    props.iter().filter(|(key, _)| *key == kept).map(|(key, value)| (key.clone(), value.clone())).collect()
+}
+
+/// Code as text and `br`s, with leading spaces made non-breaking so
+/// indentation survives.
+fn code_lines(code: &str) -> Vec<Node> {
+    let lines = code.strip_suffix('\n').unwrap_or(code).split('\n').map(|line| {
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        vec![Node::text(format!("{}{}", "\u{a0}".repeat(indent), &line[indent..]))]
+    });
+    join_lines(lines.collect())
+}
+
+fn join_lines(lines: Vec<Vec<Node>>) -> Vec<Node> {
+    let mut joined = Vec::new();
+    for (i, line) in lines.into_iter().enumerate() {
+        if i > 0 {
+            joined.push(Node::element("br"));
+        }
+        joined.extend(line);
+    }
+    joined
+}
+
+/// One line per list item, prefixed with its bullet or number; an item's
+/// paragraphs are joined by spaces and nested lists follow, indented.
+fn list_lines(list: &Node, depth: usize, lines: &mut Vec<Vec<Node>>) {
+    let Node::Element { tag, props, children, .. } = list else { return };
+    let start = props.get("start").and_then(Value::as_u64).unwrap_or(1);
+    for (number, item) in (start..).zip(children.iter().filter(|item| item.tag() == Some("li"))) {
+        let marker = match tag.as_str() {
+            "ol" => format!("{}. ", number),
+            _ => "\u{2022} ".to_string(),
+        };
+        let mut line = vec![Node::text(format!("{}{}", INDENT.repeat(depth), marker))];
+        let mut nested = Vec::new();
+        for child in item.children().map_or(&[][..], Vec::as_slice) {
+            match child {
+                Node::Element { tag, .. } if tag == "ul" || tag == "ol" => nested.push(child),
+                Node::Element { tag, children, .. } if BLOCK_TAGS.contains(&tag.as_str()) => {
+                    if line.len() > 1 {
+                        line.push(Node::text(" "));
+                    }
+                    line.extend(convert_all(children));
+                }
+                child => convert(child, &mut line),
+            }
+        }
+        lines.push(line);
+        for list in nested {
+            list_lines(list, depth + 1, lines);
+        }
+    }
+}
+
+/// One line per row with cells separated by ` | `; header rows are bold.
+fn table_rows(nodes: &[Node], head: bool, rows: &mut Vec<Vec<Node>>) {
+    for node in nodes {
+        match node {
+            Node::Element { tag, children, .. } if tag == "tr" => {
+                let header = head || children.iter().any(|cell| cell.tag() == Some("th"));
+                let mut row = Vec::new();
+                for cell in children.iter().filter(|cell| matches!(cell.tag(), Some("td" | "th"))) {
+                    if !row.is_empty() {
+                        row.push(Node::text(" | "));
+                    }
+                    row.extend(convert_all(cell.children().map_or(&[][..], Vec::as_slice)));
+                }
+                rows.push(match header {
+                    true => vec![Node::element("b").with_children(row)],
+                    false => row,
+                });
+            }
+            node => {
+                if let Some(children) = node.children() {
+                    table_rows(children, head || node.tag() == Some("thead"), rows);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_render_android_html() {
+        let markdown = "A `b` [c](/c)\n\n1. one\n   - two\n\n```\nif x:\n  y\n```\n\n| a | b |\n| - | - |\n| 1 | 2 |";
+        assert_eq!(render_android_html(&parse(markdown, &TranspileOptions::default())), "<p>A <tt>b</tt> <a href=\"/c\">c</a></p>\n\
+            <p>1. one<br>\u{a0}\u{a0}\u{a0}\u{a0}\u{2022} two</p>\n\
+            <p><tt>if x:<br>\u{a0}\u{a0}y</tt></p>\n\
+            <p><b>a | b</b><br>1 | 2</p>");
+    }
+}
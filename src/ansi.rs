@@ -0,0 +1,415 @@
+//! Terminal rendering with ANSI escapes, for CLI tools and TUIs, and the
+//! block layout it shares with `to_formatted_text`.
+
+use std::borrow::Cow;
+use serde_json::Value;
+use crate::markdown::BLOCK_TAGS;
+use crate::{text_content, Node};
+
+const BOLD: (&str, &str) = ("\x1b[1m", "\x1b[22m");
+const DIM: (&str, &str) = ("\x1b[2m", "\x1b[22m");
+const ITALIC: (&str, &str) = ("\x1b[3m", "\x1b[23m");
+const UNDERLINE: (&str, &str) = ("\x1b[4m", "\x1b[24m");
+const STRIKE: (&str, &str) = ("\x1b[9m", "\x1b[29m");
+const CODE: (&str, &str) = ("\x1b[36m", "\x1b[39m");
+const LINK: (&str, &str) = ("\x1b[4;34m", "\x1b[24;39m");
+const CODE_BLOCK: (&str, &str) = ("\x1b[32m", "\x1b[39m");
+const HEADING: (&str, &str) = ("\x1b[1;35m", "\x1b[22;39m");
+
+/// Renders `nodes` for a terminal: headings in bold color, inline
+/// formatting as bold/italic/underline/strikethrough escapes, links
+/// followed by their URL, lists with `•` bullets or numbers and indented
+/// continuation lines, quotes behind a `│` bar, code blocks indented in
+/// green, and tables drawn with box characters. Text is not wrapped;
+/// comments and raw content are dropped.
+pub fn render_ansi(nodes: &[Node]) -> String {
+    Layout { ansi: true, width: None, references: None }.render(nodes)
+}
+
+fn is_block(node: &Node) -> bool {
+    match node {
+        Node::Element { tag, children, .. } => BLOCK_TAGS.contains(&tag.as_str()) || children.iter().any(is_block),
+        Node::Fragment { children } => children.iter().any(is_block),
+        _ => false,
+    }
+}
+
+/// The number of columns `text` takes, not counting escapes.
+fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut escape = false;
+    for c in text.chars() {
+        match c {
+            '\x1b' => escape = true,
+            'm' if escape => escape = false,
+            _ if escape => {}
+            _ => width += 1,
+        }
+    }
+    width
+}
+
+/// `text` with control characters other than line breaks and tabs (ESC,
+/// BEL, C1 codes) replaced by U+FFFD, so content can't send escapes of
+/// its own to the terminal.
+fn printable(text: &str) -> Cow<'_, str> {
+    let unsafe_char = |c: char| c.is_control() && c != '\n' && c != '\t';
+    match text.contains(unsafe_char) {
+        true => text.replace(unsafe_char, "\u{fffd}").into(),
+        false => text.into(),
+    }
+}
+
+/// `text` broken into lines of at most `width` columns at spaces; longer
+/// words get a line of their own.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph_line in text.split('\n') {
+        let mut line = String::new();
+        for word in paragraph_line.split(' ').filter(|word| !word.is_empty()) {
+            if !line.is_empty() && visible_width(&line) + 1 + visible_width(word) > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Lays out blocks as lines of text, styled with ANSI escapes or plain.
+pub(crate) struct Layout {
+    pub ansi: bool,
+    /// Column to wrap text at.
+    pub width: Option<usize>,
+    /// URLs of links numbered so far, when links are written as `[n]`
+    /// references instead of inline.
+    pub references: Option<Vec<String>>,
+}
+
+impl Layout {
+    pub fn render(&mut self, nodes: &[Node]) -> String {
+        let mut lines = self.blocks(nodes, false, self.width);
+        if let Some(references) = self.references.as_ref().filter(|references| !references.is_empty()) {
+            lines.push(String::new());
+            lines.extend(references.iter().enumerate().map(|(i, url)| format!("[{}] {}", i + 1, url)));
+        }
+        lines.join("\n")
+    }
+
+    fn styled(&self, (on, off): (&str, &str), text: &str) -> String {
+        match self.ansi {
+            true => format!("{}{}{}", on, text, off),
+            false => text.to_string(),
+        }
+    }
+
+    /// The lines of block content within `width` columns; blocks are
+    /// separated by a blank line unless `tight`.
+    fn blocks(&mut self, nodes: &[Node], tight: bool, width: Option<usize>) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut run = String::new();
+        let push = |block: Vec<String>, lines: &mut Vec<String>| {
+            if block.is_empty() {
+                return;
+            }
+            if !lines.is_empty() && !tight {
+                lines.push(String::new());
+            }
+            lines.extend(block);
+        };
+        for node in nodes {
+            if is_block(node) {
+                push(self.text_lines(&std::mem::take(&mut run), width), &mut lines);
+                let block = self.block(node, width);
+                push(block, &mut lines);
+            } else {
+                self.inline(node, &mut run);
+            }
+        }
+        push(self.text_lines(&run, width), &mut lines);
+        lines
+    }
+
+    fn text_lines(&self, text: &str, width: Option<usize>) -> Vec<String> {
+        match (text.trim(), width) {
+            ("", _) => Vec::new(),
+            (text, Some(width)) => wrap(text, width),
+            (text, None) => text.split('\n').map(|line| line.trim_end().to_string()).collect(),
+        }
+    }
+
+    fn block(&mut self, node: &Node, width: Option<usize>) -> Vec<String> {
+        let Node::Element { tag, props, children, .. } = node else {
+            return match node {
+                Node::Fragment { children } => self.blocks(children, false, width),
+                _ => Vec::new(),
+            };
+        };
+        let narrower = |by: usize| width.map(|width| width.saturating_sub(by).max(1));
+        match tag.as_str() {
+            "p" => {
+                let text = self.inlines(children);
+                self.text_lines(&text, width)
+            }
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let text = self.inlines(children);
+                let mut lines: Vec<String> = self.text_lines(&text, width).iter().map(|line| self.styled(HEADING, line)).collect();
+                // Plain headings are underlined setext-style.
+                let rule = match tag.as_str() {
+                    "h1" => "=",
+                    "h2" => "-",
+                    _ => "",
+                };
+                if !self.ansi && !rule.is_empty() {
+                    let length = lines.iter().map(|line| visible_width(line)).max().unwrap_or(0);
+                    lines.push(rule.repeat(length));
+                }
+                lines
+            }
+            "blockquote" => {
+                let bar = match self.ansi {
+                    true => self.styled(DIM, "\u{2502}"),
+                    false => ">".to_string(),
+                };
+                let lines = self.blocks(children, false, narrower(2));
+                lines.iter().map(|line| format!("{} {}", bar, line).trim_end().to_string()).collect()
+            }
+            "ul" | "ol" => {
+                let start = props.get("start").and_then(Value::as_u64).unwrap_or(1);
+                let loose = children.iter().any(|item| item.children().is_some_and(|c| c.iter().any(|n| n.tag() == Some("p"))));
+                let mut lines = Vec::new();
+                for (number, item) in (start..).zip(children.iter().filter(|item| item.tag() == Some("li"))) {
+                    let marker = match tag.as_str() {
+                        "ol" => format!("{}. ", number),
+                        _ => "\u{2022} ".to_string(),
+                    };
+                    if loose && !lines.is_empty() {
+                        lines.push(String::new());
+                    }
+                    let indent = " ".repeat(marker.chars().count());
+                    let item_lines = self.blocks(item.children().map_or(&[][..], Vec::as_slice), !loose, narrower(indent.len()));
+                    for (i, line) in item_lines.iter().enumerate() {
+                        let prefix = if i == 0 { &marker } else { &indent };
+                        lines.push(if line.is_empty() { String::new() } else { format!("{}{}", prefix, line) });
+                    }
+                }
+                lines
+            }
+            "pre" => {
+                let code = text_content(children);
+                let code = printable(&code);
+                code.strip_suffix('\n').unwrap_or(&code).split('\n').map(|line| match line {
+                    "" => String::new(),
+                    line => format!("  {}", self.styled(CODE_BLOCK, line)),
+                }).collect()
+            }
+            "hr" => match self.ansi {
+                true => vec![self.styled(DIM, &"\u{2500}".repeat(40))],
+                false => vec!["-".repeat(width.unwrap_or(40).min(40))],
+            },
+            "table" => self.table(children),
+            _ => self.blocks(children, false, width),
+        }
+    }
+
+    /// A table: box-drawn with bold headers in ANSI, with columns aligned
+    /// by spaces and a dashed rule under the header in plain text.
+    fn table(&mut self, children: &[Node]) -> Vec<String> {
+        let mut rows: Vec<(bool, Vec<String>)> = Vec::new();
+        self.collect_rows(children, false, &mut rows);
+        let columns = rows.iter().map(|(_, cells)| cells.len()).max().unwrap_or(0);
+        let mut widths = vec![0; columns];
+        for (_, cells) in &rows {
+            for (width, cell) in widths.iter_mut().zip(cells) {
+                *width = (*width).max(visible_width(cell));
+            }
+        }
+        let pad = |cell: &str, width: usize| " ".repeat(width - visible_width(cell));
+        if !self.ansi {
+            let mut lines = Vec::new();
+            for (i, (header, cells)) in rows.iter().enumerate() {
+                let cells: Vec<String> = widths.iter().enumerate().map(|(column, &width)| {
+                    let cell = cells.get(column).map_or("", String::as_str);
+                    format!("{}{}", cell, pad(cell, width))
+                }).collect();
+                lines.push(cells.join("  ").trim_end().to_string());
+                if *header && rows.get(i + 1).is_some_and(|(header, _)| !header) {
+                    lines.push(widths.iter().map(|&width| "-".repeat(width)).collect::<Vec<_>>().join("  "));
+                }
+            }
+            return lines;
+        }
+        let rule = |left: &str, middle: &str, right: &str| {
+            let segments: Vec<String> = widths.iter().map(|width| "\u{2500}".repeat(width + 2)).collect();
+            format!("{}{}{}", left, segments.join(middle), right)
+        };
+        let mut lines = vec![rule("\u{250c}", "\u{252c}", "\u{2510}")];
+        for (i, (header, cells)) in rows.iter().enumerate() {
+            let cells: Vec<String> = widths.iter().enumerate().map(|(column, &width)| {
+                let cell = cells.get(column).map_or("", String::as_str);
+                match header {
+                    true => format!(" {}{} ", self.styled(BOLD, cell), pad(cell, width)),
+                    false => format!(" {}{} ", cell, pad(cell, width)),
+                }
+            }).collect();
+            lines.push(format!("\u{2502}{}\u{2502}", cells.join("\u{2502}")));
+            if *header && rows.get(i + 1).is_some_and(|(header, _)| !header) {
+                lines.push(rule("\u{251c}", "\u{253c}", "\u{2524}"));
+            }
+        }
+        lines.push(rule("\u{2514}", "\u{2534}", "\u{2518}"));
+        lines
+    }
+
+    /// Rows with their cells' text; rows in a `thead` or of `th`s are
+    /// headers.
+    fn collect_rows(&mut self, nodes: &[Node], head: bool, rows: &mut Vec<(bool, Vec<String>)>) {
+        for node in nodes {
+            match node {
+                Node::Element { tag, children, .. } if tag == "tr" => {
+                    let header = head || children.iter().any(|cell| cell.tag() == Some("th"));
+                    let cells = children.iter()
+                        .filter(|cell| matches!(cell.tag(), Some("td" | "th")))
+                        .map(|cell| self.inlines(cell.children().map_or(&[][..], Vec::as_slice)).replace('\n', " ").trim().to_string());
+                    let cells = cells.collect();
+                    rows.push((header, cells));
+                }
+                node => {
+                    if let Some(children) = node.children() {
+                        self.collect_rows(children, head || node.tag() == Some("thead"), rows);
+                    }
+                }
+            }
+        }
+    }
+
+    fn inlines(&mut self, nodes: &[Node]) -> String {
+        let mut out = String::new();
+        for node in nodes {
+            self.inline(node, &mut out);
+        }
+        out
+    }
+
+    fn inline(&mut self, node: &Node, out: &mut String) {
+        match node {
+            // Soft breaks are spaces; lines end at `br`.
+            Node::Text { content, .. } => out.push_str(&printable(content).replace('\n', " ")),
+            Node::Element { tag, props, children, .. } => {
+                let string = |key: &str| props.get(key).and_then(Value::as_str).unwrap_or_default();
+                match tag.as_str() {
+                    "strong" | "b" => {
+                        let text = self.inlines(children);
+                        out.push_str(&self.styled(BOLD, &text));
+                    }
+                    "em" | "i" => {
+                        let text = self.inlines(children);
+                        out.push_str(&self.styled(ITALIC, &text));
+                    }
+                    "del" | "s" => {
+                        let text = self.inlines(children);
+                        out.push_str(&self.styled(STRIKE, &text));
+                    }
+                    "u" => {
+                        let text = self.inlines(children);
+                        out.push_str(&self.styled(UNDERLINE, &text));
+                    }
+                    "code" => out.push_str(&self.styled(CODE, &printable(&text_content(children)))),
+                    "a" => {
+                        let text = self.inlines(children);
+                        out.push_str(&self.styled(LINK, &text));
+                        let href = printable(string("href"));
+                        let href = href.as_ref();
+                        if href.is_empty() || href.starts_with('#') || href == text || href.strip_prefix("mailto:") == Some(&text) {
+                            return;
+                        }
+                        match &mut self.references {
+                            Some(references) => {
+                                let number = match references.iter().position(|url| url == href) {
+                                    Some(index) => index + 1,
+                                    None => {
+                                        references.push(href.to_string());
+                                        references.len()
+                                    }
+                                };
+                                out.push_str(&format!("[{}]", number));
+                            }
+                            None => out.push_str(&self.styled(DIM, &format!(" ({})", href))),
+                        }
+                    }
+                    "sup" => {
+                        out.push('[');
+                        out.push_str(&self.inlines(children));
+                        out.push(']');
+                    }
+                    "img" => out.push_str(&self.styled(DIM, &format!("[image: {}]", printable(string("alt"))))),
+                    "br" => out.push('\n'),
+                    "input" if string("type") == "checkbox" => {
+                        let checked = props.get("checked").and_then(Value::as_bool).unwrap_or(false);
+                        out.push_str(match (self.ansi, checked) {
+                            (true, true) => "\u{2611} ",
+                            (true, false) => "\u{2610} ",
+                            (false, true) => "[x] ",
+                            (false, false) => "[ ] ",
+                        });
+                    }
+                    _ => {
+                        for child in children {
+                            self.inline(child, out);
+                        }
+                    }
+                }
+            }
+            Node::Fragment { children } => {
+                for child in children {
+                    self.inline(child, out);
+                }
+            }
+            Node::Comment { .. } | Node::Raw { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_render_ansi() {
+        let markdown = "# Hi\n\nA **b** [c](https://c.io)\n\n> q\n\n1. one\n   - two\n\n| a | bb |\n| - | - |\n| 1 | 2 |";
+        assert_eq!(render_ansi(&parse(markdown, &TranspileOptions::default())), [
+            "\x1b[1;35mHi\x1b[22;39m",
+            "",
+            "A \x1b[1mb\x1b[22m \x1b[4;34mc\x1b[24;39m\x1b[2m (https://c.io)\x1b[22m",
+            "",
+            "\x1b[2m\u{2502}\x1b[22m q",
+            "",
+            "1. one",
+            "   \u{2022} two",
+            "",
+            "\u{250c}\u{2500}\u{2500}\u{2500}\u{252c}\u{2500}\u{2500}\u{2500}\u{2500}\u{2510}",
+            "\u{2502} \x1b[1ma\x1b[22m \u{2502} \x1b[1mbb\x1b[22m \u{2502}",
+            "\u{251c}\u{2500}\u{2500}\u{2500}\u{253c}\u{2500}\u{2500}\u{2500}\u{2500}\u{2524}",
+            "\u{2502} 1 \u{2502} 2  \u{2502}",
+            "\u{2514}\u{2500}\u{2500}\u{2500}\u{2534}\u{2500}\u{2500}\u{2500}\u{2500}\u{2518}",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn test_render_ansi_control_characters() {
+        let mut nodes = parse("a\x1b]8;;https://evil.io\x07b\n\n`\u{9b}c`", &TranspileOptions::default());
+        nodes.push(Node::element("a").prop("href", "https://x.io/\x1b[2J").child(Node::text("x")));
+        assert_eq!(render_ansi(&nodes), [
+            "a\u{fffd}]8;;https://evil.io\u{fffd}b",
+            "",
+            "\x1b[36m\u{fffd}c\x1b[39m",
+            "",
+            "\x1b[4;34mx\x1b[24;39m\x1b[2m (https://x.io/\u{fffd}[2J)\x1b[22m",
+        ].join("\n"));
+    }
+}
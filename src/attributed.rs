@@ -0,0 +1,133 @@
+//! Runs for building an `NSAttributedString` or `AttributedString` on
+//! Apple platforms.
+
+use serde_json::{json, Map, Value};
+use crate::styled_text::{styled_text, InlineStyle};
+use crate::Node;
+
+/// `UIFont.TextStyle` names for `h1`..`h6`.
+const HEADING_STYLES: [&str; 6] = ["title1", "title2", "title3", "headline", "subheadline", "footnote"];
+
+/// Indent per list or blockquote level, in points.
+const INDENT: u32 = 16;
+
+/// Converts `nodes` to `{"runs", "paragraphs"}`. Appending each run's
+/// `text` gives the whole string, blocks separated by `\n` and list
+/// markers included. A run lists only the attributes it has: `traits`
+/// (`bold`, `italic`, `monospace`), `textStyle` (`UIFont.TextStyle` for
+/// headings), `link`, `underline`, `strikethrough` and `baselineOffset`
+/// (`1` superscript, `-1` subscript). `paragraphs` gives `location`/
+/// `length` ranges in UTF-16 code units, as `NSRange` takes them, with
+/// `NSParagraphStyle`'s `headIndent` and `firstLineHeadIndent` for nested
+/// lists and quotes.
+pub fn to_attributed_runs(nodes: &[Node]) -> Value {
+    let styled = styled_text(nodes);
+    let units: Vec<u16> = styled.text.encode_utf16().collect();
+    let mut bounds = vec![0, units.len()];
+    for range in &styled.spans {
+        bounds.extend([range.start, range.end]);
+    }
+    for paragraph in &styled.paragraphs {
+        bounds.extend([paragraph.start, paragraph.end]);
+    }
+    bounds.sort_unstable();
+    bounds.dedup();
+
+    let mut runs: Vec<(Map<String, Value>, String)> = Vec::new();
+    for pair in bounds.windows(2) {
+        let (start, end) = (pair[0], pair[1]);
+        let mut attributes = Map::new();
+        let mut traits = Vec::new();
+        let mut trait_ = |name: &str| {
+            if !traits.contains(&name.to_string()) {
+                traits.push(name.to_string());
+            }
+        };
+        for paragraph in styled.paragraphs.iter().filter(|p| p.start <= start && end <= p.end) {
+            if let Some(level) = paragraph.heading {
+                trait_("bold");
+                attributes.insert("textStyle".to_string(), json!(HEADING_STYLES[usize::from(level.clamp(1, 6)) - 1]));
+            }
+            if paragraph.code {
+                trait_("monospace");
+            }
+        }
+        for range in styled.spans.iter().filter(|r| r.start <= start && end <= r.end) {
+            match &range.style {
+                InlineStyle::Bold => trait_("bold"),
+                InlineStyle::Italic => trait_("italic"),
+                InlineStyle::Code => trait_("monospace"),
+                InlineStyle::Strikethrough => {
+                    attributes.insert("strikethrough".to_string(), json!(true));
+                }
+                InlineStyle::Underline => {
+                    attributes.insert("underline".to_string(), json!(true));
+                }
+                InlineStyle::Superscript => {
+                    attributes.insert("baselineOffset".to_string(), json!(1));
+                }
+                InlineStyle::Subscript => {
+                    attributes.insert("baselineOffset".to_string(), json!(-1));
+                }
+                InlineStyle::Link(url) => {
+                    attributes.insert("link".to_string(), json!(url));
+                }
+            }
+        }
+        if !traits.is_empty() {
+            attributes.insert("traits".to_string(), json!(traits));
+        }
+        let text = String::from_utf16_lossy(&units[start..end]);
+        match runs.last_mut() {
+            Some((last, run)) if *last == attributes => run.push_str(&text),
+            _ => runs.push((attributes, text)),
+        }
+    }
+
+    let runs: Vec<Value> = runs
+        .into_iter()
+        .map(|(mut attributes, text)| {
+            attributes.insert("text".to_string(), json!(text));
+            Value::Object(attributes)
+        })
+        .collect();
+    let paragraphs: Vec<Value> = styled
+        .paragraphs
+        .iter()
+        .filter(|paragraph| paragraph.quote + paragraph.list > 0)
+        .map(|paragraph| {
+            let indent = (paragraph.quote + paragraph.list) as u32 * INDENT;
+            json!({
+                "location": paragraph.start,
+                "length": paragraph.end - paragraph.start,
+                "headIndent": indent,
+                "firstLineHeadIndent": indent,
+            })
+        })
+        .collect();
+    json!({ "runs": runs, "paragraphs": paragraphs })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_to_attributed_runs() {
+        let ast = parse("# Hi\n\n- **a** [`b`](https://x.org) c", &TranspileOptions::default());
+        assert_eq!(to_attributed_runs(&ast), json!({
+            "runs": [
+                { "text": "Hi", "textStyle": "title1", "traits": ["bold"] },
+                { "text": "\n\u{2022} " },
+                { "text": "a", "traits": ["bold"] },
+                { "text": " " },
+                { "text": "b", "link": "https://x.org", "traits": ["monospace"] },
+                { "text": " c" },
+            ],
+            "paragraphs": [
+                { "location": 3, "length": 7, "headIndent": 16, "firstLineHeadIndent": 16 },
+            ],
+        }));
+    }
+}
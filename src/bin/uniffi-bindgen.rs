@@ -0,0 +1,6 @@
+//! Generates the Swift and Kotlin UniFFI bindings; see
+//! `scripts/generate-bindings.sh`.
+
+fn main() {
+    uniffi::uniffi_bindgen_main()
+}
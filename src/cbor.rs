@@ -0,0 +1,36 @@
+//! CBOR encoding of the AST, for consumers that standardize on CBOR
+//! payloads.
+//!
+//! Nodes are written as maps with the same field names as the JSON form.
+
+use crate::Node;
+
+pub type CborError = ciborium::de::Error<std::io::Error>;
+
+/// Encodes `nodes` as CBOR.
+pub fn to_cbor(nodes: &[Node]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    ciborium::into_writer(nodes, &mut bytes).expect("AST serialization is infallible");
+    bytes
+}
+
+/// Decodes nodes written by `to_cbor`.
+pub fn from_cbor(bytes: &[u8]) -> Result<Vec<Node>, CborError> {
+    ciborium::from_reader(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_cbor_round_trip() {
+        let options = TranspileOptions { source_spans: true, ..Default::default() };
+        let ast = parse("# Hi\n\n1. [x] done <!-- c -->\n\n| a |\n| - |\n| 1 |", &options);
+        let bytes = to_cbor(&ast);
+
+        assert_eq!(from_cbor(&bytes).unwrap(), ast);
+        assert!(from_cbor(&bytes[..bytes.len() / 2]).is_err());
+    }
+}
@@ -0,0 +1,143 @@
+//! Routing of fenced code blocks by language, from
+//! `TranspileOptions::code_handlers`.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use crate::visitor::{walk, Flow, VisitorMut};
+use crate::{text_content, Node};
+
+/// Turns code into highlighted nodes (typically `span`s with classes),
+/// which replace the text inside the block's `code`. Any
+/// `Fn(&str, &str) -> Vec<Node>` closure taking the code and language is
+/// a highlighter.
+pub trait Highlighter: Send + Sync {
+    fn highlight(&self, code: &str, language: &str) -> Vec<Node>;
+}
+
+impl<F> Highlighter for F
+where
+    F: Fn(&str, &str) -> Vec<Node> + Send + Sync,
+{
+    fn highlight(&self, code: &str, language: &str) -> Vec<Node> {
+        self(code, language)
+    }
+}
+
+impl fmt::Debug for dyn Highlighter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Highlighter")
+    }
+}
+
+/// What a fenced code block of a given language becomes, in place of
+/// `pre`/`code`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+pub enum CodeHandler {
+    /// An element `tag` with these props plus `language`, and the code as
+    /// its text child (`chart` to `<Chart language="chart">...</Chart>`).
+    Component {
+        tag: String,
+        #[serde(default)]
+        props: IndexMap<String, serde_json::Value>,
+    },
+    /// A `raw` node with the language as its `format`, for content the
+    /// renderer handles verbatim (`math`, `mermaid`).
+    Raw,
+    /// The usual `pre`/`code`, with the code run through a highlighter.
+    /// Cannot be given in JSON or TOML.
+    #[serde(skip)]
+    #[cfg_attr(feature = "typescript", ts(skip))]
+    Highlight(Arc<dyn Highlighter>),
+}
+
+impl CodeHandler {
+    pub fn component(tag: impl Into<String>) -> Self {
+        CodeHandler::Component { tag: tag.into(), props: IndexMap::new() }
+    }
+
+    pub fn highlight(highlighter: impl Highlighter + 'static) -> Self {
+        CodeHandler::Highlight(Arc::new(highlighter))
+    }
+}
+
+struct CodeRouter<'h>(&'h HashMap<String, CodeHandler>);
+
+impl VisitorMut for CodeRouter<'_> {
+    fn enter(&mut self, node: &mut Node) -> Flow {
+        let Node::Element { tag, children, span, .. } = node else {
+            return Flow::Continue;
+        };
+        if tag != "pre" {
+            return Flow::Continue;
+        }
+        let Some(language) = language(children) else {
+            return Flow::SkipChildren;
+        };
+        let Some(handler) = self.0.get(&language) else {
+            return Flow::SkipChildren;
+        };
+        let code = text_content(children);
+        match handler {
+            CodeHandler::Component { tag, props } => {
+                let mut props = props.clone();
+                props.insert("language".to_string(), language.into());
+                *node = Node::Element { tag: tag.clone(), props, children: vec![Node::text(code)], span: *span, content_id: None };
+            }
+            CodeHandler::Raw => *node = Node::Raw { content: code, format: language, span: *span },
+            CodeHandler::Highlight(highlighter) => {
+                if let Some(Node::Element { children, .. }) = children.first_mut() {
+                    *children = highlighter.highlight(&code, &language);
+                }
+            }
+        }
+        Flow::SkipChildren
+    }
+}
+
+/// The language of a `pre` holding a single `code.language-*`.
+fn language(children: &[Node]) -> Option<String> {
+    let [Node::Element { tag, props, .. }] = children else { return None };
+    if tag != "code" {
+        return None;
+    }
+    let class = props.get("className").or_else(|| props.get("class"))?.as_str()?;
+    class.split_whitespace().find_map(|class| class.strip_prefix("language-")).map(str::to_string)
+}
+
+pub(crate) fn route_code(nodes: &mut [Node], handlers: &HashMap<String, CodeHandler>) {
+    if !handlers.is_empty() {
+        walk(nodes, &mut CodeRouter(handlers));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_code_handlers() {
+        let markdown = "```chart\nbar: 1\n```\n\n```math\nx^2\n```\n\n```rust\nlet x;\n```\n\n```js\nx\n```";
+        let options = TranspileOptions::builder()
+            .code_handler("chart", CodeHandler::component("Chart"))
+            .code_handler("math", CodeHandler::Raw)
+            .code_handler("rust", CodeHandler::highlight(|code: &str, _: &str| {
+                code.split_inclusive(' ').map(|word| Node::element("span").prop("className", "tok").child(Node::text(word))).collect()
+            }))
+            .build();
+        let ast = parse(markdown, &options);
+        assert_eq!(ast[0], Node::element("Chart").prop("language", "chart").child(Node::text("bar: 1\n")));
+        assert_eq!(ast[1], Node::Raw { content: "x^2\n".to_string(), format: "math".to_string(), span: None });
+        assert_eq!(ast[2], Node::element("pre").child(Node::element("code").prop("className", "language-rust").with_children([
+            Node::element("span").prop("className", "tok").child(Node::text("let ")),
+            Node::element("span").prop("className", "tok").child(Node::text("x;\n")),
+        ])));
+        let options = TranspileOptions::from_json(r#"{"code_handlers": {"chart": {"Component": {"tag": "Chart"}}, "math": "Raw"}}"#).unwrap();
+        assert_eq!(parse(markdown, &options)[..2], ast[..2]);
+        assert_eq!(ast[3], Node::element("pre").child(Node::element("code").prop("className", "language-js").child(Node::text("x\n"))));
+    }
+}
@@ -0,0 +1,106 @@
+//! Compact JSON encoding of the AST for size-sensitive consumers such as
+//! the Android bridge.
+//!
+//! Field names are shortened to one letter and empty `props`/`children`
+//! are omitted:
+//!
+//! | Node       | Encoding                                   |
+//! |------------|--------------------------------------------|
+//! | `element`  | `{"t": tag, "p": props?, "c": children?}`  |
+//! | `text`     | `{"x": content}`, or `content` if bare     |
+//! | `comment`  | `{"m": content}`                           |
+//! | `raw`      | `{"r": content, "f": format}`              |
+//! | `fragment` | `{"c": children}`                          |
+//!
+//! Spans, when kept, become `"s": [start, end, line, column]`, and content
+//! ids become `"i"`.
+
+use serde_json::{json, Map, Value};
+use crate::{Node, Span};
+
+#[derive(Debug, Clone, Default)]
+pub struct CompactOptions {
+    /// Write text nodes as bare JSON strings. Their spans are dropped.
+    pub bare_text: bool,
+    /// Keep source spans.
+    pub spans: bool,
+}
+
+/// Encodes `nodes` in the compact format.
+pub fn to_compact_value(nodes: &[Node], options: &CompactOptions) -> Value {
+    Value::Array(nodes.iter().map(|node| compact_node(node, options)).collect())
+}
+
+/// Encodes `nodes` in the compact format as a JSON string.
+pub fn to_compact_json(nodes: &[Node], options: &CompactOptions) -> String {
+    to_compact_value(nodes, options).to_string()
+}
+
+fn compact_node(node: &Node, options: &CompactOptions) -> Value {
+    let mut out = Map::new();
+    let span = match node {
+        Node::Element { tag, props, children, span, content_id } => {
+            out.insert("t".to_string(), Value::String(tag.clone()));
+            if let Some(id) = content_id {
+                out.insert("i".to_string(), Value::String(id.clone()));
+            }
+            if !props.is_empty() {
+                let props = props.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+                out.insert("p".to_string(), Value::Object(props));
+            }
+            if !children.is_empty() {
+                out.insert("c".to_string(), to_compact_value(children, options));
+            }
+            span
+        }
+        Node::Text { content, .. } if options.bare_text => return Value::String(content.clone()),
+        Node::Text { content, span } => {
+            out.insert("x".to_string(), Value::String(content.clone()));
+            span
+        }
+        Node::Comment { content, span } => {
+            out.insert("m".to_string(), Value::String(content.clone()));
+            span
+        }
+        Node::Raw { content, format, span } => {
+            out.insert("r".to_string(), Value::String(content.clone()));
+            out.insert("f".to_string(), Value::String(format.clone()));
+            span
+        }
+        Node::Fragment { children } => {
+            out.insert("c".to_string(), to_compact_value(children, options));
+            &None
+        }
+    };
+    if let (true, Some(Span { start, end, line, column })) = (options.spans, span) {
+        out.insert("s".to_string(), json!([start, end, line, column]));
+    }
+    Value::Object(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_compact_json() {
+        let ast = parse("# Hi\n\n[a](/x) <!-- c -->", &TranspileOptions::default());
+
+        let json = to_compact_json(&ast, &CompactOptions::default());
+        assert_eq!(json, r#"[{"t":"h1","c":[{"x":"Hi"}]},{"t":"p","c":[{"t":"a","p":{"href":"/x"},"c":[{"x":"a"}]},{"x":" "},{"m":" c "}]}]"#);
+
+        let options = CompactOptions { bare_text: true, ..Default::default() };
+        let json = to_compact_json(&ast[..1], &options);
+        assert_eq!(json, r#"[{"t":"h1","c":["Hi"]}]"#);
+        assert!(json.len() < serde_json::to_string(&ast[..1]).unwrap().len() / 2);
+    }
+
+    #[test]
+    fn test_compact_spans() {
+        let ast = parse("Hi", &TranspileOptions { source_spans: true, ..Default::default() });
+        let options = CompactOptions { spans: true, ..Default::default() };
+
+        assert_eq!(to_compact_json(&ast, &options), r#"[{"t":"p","c":[{"x":"Hi","s":[0,2,1,1]}],"s":[0,2,1,1]}]"#);
+    }
+}
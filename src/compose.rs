@@ -0,0 +1,88 @@
+//! Jetpack Compose `AnnotatedString` spec: the text with span, paragraph
+//! and link ranges as JSON.
+
+use serde_json::{json, Map, Value};
+use crate::styled_text::{styled_text, InlineStyle};
+use crate::Node;
+
+/// Font sizes of `h1`..`h6`, in `em`.
+const HEADING_SIZES: [f64; 6] = [2.0, 1.5, 1.25, 1.1, 1.0, 0.9];
+
+/// Indent per list or blockquote level, in `sp`.
+const INDENT: u32 = 16;
+
+/// Converts `nodes` to `{"text", "spanStyles", "paragraphStyles",
+/// "links"}`. Ranges are `start`/`end` offsets in UTF-16 code units, as
+/// `AnnotatedString` takes them; styles use `SpanStyle` and
+/// `ParagraphStyle` property names (`fontWeight` as a number, `fontSize`
+/// in `em`, `textIndent` in `sp`). Headings are a bold, sized span.
+pub fn to_compose_spec(nodes: &[Node]) -> Value {
+    let styled = styled_text(nodes);
+    let mut spans = Vec::new();
+    let mut links = Vec::new();
+    for range in &styled.spans {
+        let style = match &range.style {
+            InlineStyle::Bold => json!({ "fontWeight": 700 }),
+            InlineStyle::Italic => json!({ "fontStyle": "italic" }),
+            InlineStyle::Strikethrough => json!({ "textDecoration": "lineThrough" }),
+            InlineStyle::Underline => json!({ "textDecoration": "underline" }),
+            InlineStyle::Code => json!({ "fontFamily": "monospace" }),
+            InlineStyle::Superscript => json!({ "baselineShift": 0.5, "fontSize": 0.75 }),
+            InlineStyle::Subscript => json!({ "baselineShift": -0.5, "fontSize": 0.75 }),
+            InlineStyle::Link(url) => {
+                links.push(json!({ "start": range.start, "end": range.end, "url": url }));
+                json!({ "textDecoration": "underline" })
+            }
+        };
+        spans.push(json!({ "start": range.start, "end": range.end, "style": style }));
+    }
+
+    let mut paragraphs = Vec::new();
+    for paragraph in &styled.paragraphs {
+        if let Some(level) = paragraph.heading {
+            let size = HEADING_SIZES[usize::from(level.clamp(1, 6)) - 1];
+            let style = json!({ "fontWeight": 700, "fontSize": size });
+            spans.push(json!({ "start": paragraph.start, "end": paragraph.end, "style": style }));
+        }
+        if paragraph.code {
+            let style = json!({ "fontFamily": "monospace" });
+            spans.push(json!({ "start": paragraph.start, "end": paragraph.end, "style": style }));
+        }
+        let indent = (paragraph.quote + paragraph.list) as u32 * INDENT;
+        if indent > 0 {
+            let mut style = Map::new();
+            style.insert("textIndent".to_string(), json!({ "firstLine": indent, "restLine": indent }));
+            paragraphs.push(json!({ "start": paragraph.start, "end": paragraph.end, "style": style }));
+        }
+    }
+
+    json!({
+        "text": styled.text,
+        "spanStyles": spans,
+        "paragraphStyles": paragraphs,
+        "links": links,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_to_compose_spec() {
+        let ast = parse("## Hi\n\n> *a* [b](https://x.org)", &TranspileOptions::default());
+        assert_eq!(to_compose_spec(&ast), json!({
+            "text": "Hi\na b",
+            "spanStyles": [
+                { "start": 3, "end": 4, "style": { "fontStyle": "italic" } },
+                { "start": 5, "end": 6, "style": { "textDecoration": "underline" } },
+                { "start": 0, "end": 2, "style": { "fontWeight": 700, "fontSize": 1.5 } },
+            ],
+            "paragraphStyles": [
+                { "start": 3, "end": 6, "style": { "textIndent": { "firstLine": 16, "restLine": 16 } } },
+            ],
+            "links": [{ "start": 5, "end": 6, "url": "https://x.org" }],
+        }));
+    }
+}
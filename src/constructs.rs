@@ -0,0 +1,103 @@
+//! Markdown constructs that `TranspileOptions::disabled_constructs` can
+//! switch off, leaving their syntax as literal text.
+
+use std::collections::HashSet;
+use pulldown_cmark::{Event, LinkType, Options, Tag};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+pub enum Construct {
+    Headings,
+    Images,
+    /// Inline and reference links, but not autolinks.
+    Links,
+    /// `<https://...>` and `<user@example.com>`.
+    Autolinks,
+    /// Inline HTML and HTML blocks, which then render as their source.
+    Html,
+    Tables,
+    CodeBlocks,
+    CodeSpans,
+    BlockQuotes,
+    Lists,
+    Emphasis,
+    Strong,
+    Strikethrough,
+    ThematicBreaks,
+    Footnotes,
+    TaskLists,
+}
+
+impl Construct {
+    /// The construct `event` starts, for those handled by the tree builder.
+    /// Extensions are switched off in the parser instead.
+    pub(crate) fn of(event: &Event) -> Option<Construct> {
+        Some(match event {
+            Event::Start(Tag::Heading { .. }) => Construct::Headings,
+            Event::Start(Tag::Image { .. }) => Construct::Images,
+            Event::Start(Tag::Link { link_type: LinkType::Autolink | LinkType::Email, .. }) => Construct::Autolinks,
+            Event::Start(Tag::Link { .. }) => Construct::Links,
+            Event::Start(Tag::HtmlBlock) | Event::Html(_) | Event::InlineHtml(_) => Construct::Html,
+            Event::Start(Tag::CodeBlock(_)) => Construct::CodeBlocks,
+            Event::Code(_) => Construct::CodeSpans,
+            Event::Start(Tag::BlockQuote(_)) => Construct::BlockQuotes,
+            Event::Start(Tag::List(_)) => Construct::Lists,
+            Event::Start(Tag::Emphasis) => Construct::Emphasis,
+            Event::Start(Tag::Strong) => Construct::Strong,
+            Event::Rule => Construct::ThematicBreaks,
+            _ => return None,
+        })
+    }
+
+    /// Whether the literal source of this construct is a block of its own,
+    /// to be wrapped in a paragraph.
+    pub(crate) fn is_block(self) -> bool {
+        matches!(
+            self,
+            Construct::Headings | Construct::CodeBlocks | Construct::BlockQuotes | Construct::Lists | Construct::ThematicBreaks
+        )
+    }
+}
+
+/// `options` without the extensions in `disabled`.
+pub(crate) fn without_disabled(mut options: Options, disabled: &HashSet<Construct>) -> Options {
+    for (construct, option) in [
+        (Construct::Tables, Options::ENABLE_TABLES),
+        (Construct::Strikethrough, Options::ENABLE_STRIKETHROUGH),
+        (Construct::Footnotes, Options::ENABLE_FOOTNOTES),
+        (Construct::TaskLists, Options::ENABLE_TASKLISTS),
+    ] {
+        if disabled.contains(&construct) {
+            options.remove(option);
+        }
+    }
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, Construct, Node, TranspileOptions};
+
+    #[test]
+    fn test_disabled_constructs() {
+        let markdown = "# Title\n\n![cat](/cat.png) *hi* <b>x</b> ~~no~~ <https://a.io>\n\n> quote\n\n| a |\n| - |\n\n<div>\nhey\n</div>";
+        let options = TranspileOptions::builder()
+            .allowed_tags(["b"])
+            .normalize(true)
+            .disabled_constructs([Construct::Headings, Construct::Images, Construct::Html, Construct::Strikethrough, Construct::Tables])
+            .build();
+        assert_eq!(parse(markdown, &options), [
+            Node::element("p").child(Node::text("# Title")),
+            Node::element("p").with_children([
+                Node::text("![cat](/cat.png) "),
+                Node::element("em").child(Node::text("hi")),
+                Node::text(" <b>x</b> ~~no~~ "),
+                Node::element("a").prop("href", "https://a.io").child(Node::text("https://a.io")),
+            ]),
+            Node::element("blockquote").child(Node::element("p").child(Node::text("quote"))),
+            Node::element("p").child(Node::text("| a |\n| - |")),
+            Node::element("p").child(Node::text("<div>\nhey\n</div>")),
+        ]);
+    }
+}
@@ -0,0 +1,120 @@
+//! Stable content-hash ids for elements.
+//!
+//! An element's id hashes its tag, its props and, in order, the text and
+//! ids of its children, so it only changes when something it renders
+//! changes. Positions (spans and `data-sourcepos`), `key` props and
+//! comments are left out. The hash is 64-bit FNV-1a, written as 16 hex digits, which
+//! stays the same across builds and platforms.
+
+use crate::Node;
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// Sets `content_id` on every element in `nodes`, replacing ids from
+/// before any edits.
+pub fn assign_content_ids(nodes: &mut [Node]) {
+    for_each_hash(nodes, &mut |node, hash| {
+        if let Node::Element { content_id, .. } = node {
+            *content_id = Some(hash.to_string());
+        }
+    });
+}
+
+/// Hashes every element in `nodes`, children first, passing each element
+/// and its hash (as 16 hex digits) to `f`.
+pub(crate) fn for_each_hash(nodes: &mut [Node], f: &mut impl FnMut(&mut Node, &str)) {
+    for node in nodes {
+        match node {
+            Node::Element { .. } => {
+                let hash = element_hash(node, f);
+                f(node, &hash);
+            }
+            Node::Fragment { children } => for_each_hash(children, f),
+            Node::Text { .. } | Node::Comment { .. } | Node::Raw { .. } => {}
+        }
+    }
+}
+
+fn element_hash(node: &mut Node, f: &mut impl FnMut(&mut Node, &str)) -> String {
+    let mut hash = Fnv(FNV_OFFSET);
+    if let Node::Element { tag, props, children, .. } = node {
+        hash.write(tag.as_bytes());
+        for (key, value) in props.iter().filter(|(key, _)| !matches!(key.as_str(), "data-sourcepos" | "key")) {
+            hash.write(key.as_bytes());
+            hash.write(value.to_string().as_bytes());
+        }
+        hash_children(children, &mut hash, f);
+    }
+    format!("{:016x}", hash.0)
+}
+
+fn hash_children(children: &mut [Node], hash: &mut Fnv, f: &mut impl FnMut(&mut Node, &str)) {
+    for child in children {
+        match child {
+            Node::Element { .. } => {
+                let id = element_hash(child, f);
+                f(child, &id);
+                hash.write(b"e");
+                hash.write(id.as_bytes());
+            }
+            Node::Text { content, .. } => {
+                hash.write(b"t");
+                hash.write(content.as_bytes());
+            }
+            Node::Raw { content, format, .. } => {
+                hash.write(b"r");
+                hash.write(format.as_bytes());
+                hash.write(content.as_bytes());
+            }
+            Node::Fragment { children } => hash_children(children, hash, f),
+            Node::Comment { .. } => {}
+        }
+    }
+}
+
+struct Fnv(u64);
+
+impl Fnv {
+    /// Hashes `bytes` followed by a separator, so `("ab", "c")` and
+    /// `("a", "bc")` differ.
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes.iter().chain(&[0xff]) {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    fn ids(nodes: &[Node]) -> Vec<&str> {
+        nodes.iter().filter_map(|node| match node {
+            Node::Element { content_id, .. } => content_id.as_deref(),
+            _ => None,
+        }).collect()
+    }
+
+    #[test]
+    fn test_content_ids() {
+        let options = TranspileOptions { content_ids: true, sourcepos: true, ..Default::default() };
+        let ast = parse("# Title\n\nSame *text*\n\nSame *text*\n\nSame *texts*", &options);
+        let before = ids(&ast);
+
+        assert_eq!(before.len(), 4);
+        assert_eq!(before[1], before[2]);
+        assert_ne!(before[1], before[3]);
+        // Pinned, so a change to the hashing is noticed.
+        assert_eq!(before[0], "31237c8e8e503d71");
+
+        // Moving a block keeps its id.
+        let moved = parse("Intro\n\n# Title", &options);
+        assert_eq!(ids(&moved)[1], before[0]);
+        let json = serde_json::to_string(&moved[1]).unwrap();
+        assert!(json.ends_with(r#""__id":"31237c8e8e503d71"}"#), "{}", json);
+        assert!(!serde_json::to_string(&parse("x", &TranspileOptions::default())).unwrap().contains("__id"));
+    }
+}
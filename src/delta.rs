@@ -0,0 +1,247 @@
+//! Quill Delta output.
+
+use serde_json::{json, Map, Value};
+use crate::markdown::BLOCK_TAGS;
+use crate::{text_content, Node};
+
+/// Converts `nodes` to a Quill Delta (`{"ops": [...]}`) of `insert`
+/// operations. Inline formatting becomes attributes on the inserted text
+/// (`bold`, `italic`, `strike`, `underline`, `code`, `link`, `script`) and
+/// line formats go on the newline ending each line (`header`,
+/// `blockquote`, `list`, `indent`, `code-block`). Images are `image`
+/// embeds with their `alt`; table rows become lines of tab-separated
+/// cells, and thematic breaks, comments and raw content are dropped.
+pub fn to_delta(nodes: &[Node]) -> Value {
+    let mut writer = Writer { ops: Vec::new() };
+    writer.blocks(nodes, &Map::new(), 0);
+    if !writer.ops.last().and_then(|op| op["insert"].as_str()).is_some_and(|text| text.ends_with('\n')) {
+        writer.newline(&Map::new());
+    }
+    json!({ "ops": writer.ops })
+}
+
+fn is_block(node: &Node) -> bool {
+    match node {
+        Node::Element { tag, children, .. } => BLOCK_TAGS.contains(&tag.as_str()) || children.iter().any(is_block),
+        Node::Fragment { children } => children.iter().any(is_block),
+        _ => false,
+    }
+}
+
+fn attributes<const N: usize>(pairs: [(&str, Value); N]) -> Map<String, Value> {
+    pairs.into_iter().map(|(key, value)| (key.to_string(), value)).collect()
+}
+
+struct Writer {
+    ops: Vec<Value>,
+}
+
+impl Writer {
+    /// Appends an insert, merged into the previous one if both are text
+    /// with the same attributes.
+    fn insert(&mut self, insert: Value, attributes: &Map<String, Value>) {
+        if let (Some(text), Some(last)) = (insert.as_str(), self.ops.last_mut()) {
+            let same = last.get("attributes").and_then(Value::as_object).map_or(attributes.is_empty(), |a| a == attributes);
+            if let (Some(previous), true) = (last["insert"].as_str(), same) {
+                last["insert"] = format!("{}{}", previous, text).into();
+                return;
+            }
+        }
+        let mut op = json!({ "insert": insert });
+        if !attributes.is_empty() {
+            op["attributes"] = attributes.clone().into();
+        }
+        self.ops.push(op);
+    }
+
+    fn newline(&mut self, line: &Map<String, Value>) {
+        self.insert("\n".into(), line);
+    }
+
+    /// Writes block content; `line` holds the line formats of the enclosing
+    /// block, applied to lines not formatted otherwise.
+    fn blocks(&mut self, nodes: &[Node], line: &Map<String, Value>, indent: u64) {
+        let mut pending = false;
+        for node in nodes {
+            if !is_block(node) {
+                pending |= self.inline(node, &Map::new(), line);
+                continue;
+            }
+            if pending {
+                self.newline(line);
+                pending = false;
+            }
+            self.block(node, line, indent);
+        }
+        if pending {
+            self.newline(line);
+        }
+    }
+
+    fn block(&mut self, node: &Node, line: &Map<String, Value>, indent: u64) {
+        let Node::Element { tag, children, .. } = node else {
+            if let Node::Fragment { children } = node {
+                self.blocks(children, line, indent);
+            }
+            return;
+        };
+        match tag.as_str() {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.inlines(children, line);
+                self.newline(&attributes([("header", tag[1..].parse::<u8>().unwrap_or(1).into())]));
+            }
+            "blockquote" => self.blocks(children, &attributes([("blockquote", true.into())]), indent),
+            "ul" | "ol" => {
+                for item in children {
+                    let Node::Element { tag: item_tag, children, .. } = item else { continue };
+                    if item_tag != "li" {
+                        continue;
+                    }
+                    let checked = children.iter().chain(children.iter().filter_map(Node::children).flatten()).find_map(|node| match node {
+                        Node::Element { tag, props, .. } if tag == "input" => Some(props.get("checked").and_then(Value::as_bool).unwrap_or(false)),
+                        _ => None,
+                    });
+                    let list = match (checked, tag.as_str()) {
+                        (Some(true), _) => "checked",
+                        (Some(false), _) => "unchecked",
+                        (None, "ol") => "ordered",
+                        (None, _) => "bullet",
+                    };
+                    let mut item_line = attributes([("list", list.into())]);
+                    if indent > 0 {
+                        item_line.insert("indent".into(), indent.into());
+                    }
+                    let (lists, content): (Vec<&Node>, Vec<&Node>) =
+                        children.iter().partition(|node| matches!(node, Node::Element { tag, .. } if tag == "ul" || tag == "ol"));
+                    let content: Vec<Node> = content.into_iter().cloned().collect();
+                    self.blocks(&content, &item_line, indent);
+                    for nested in lists {
+                        self.block(nested, line, indent + 1);
+                    }
+                }
+            }
+            "pre" => {
+                let language = match children.as_slice() {
+                    [Node::Element { tag, props, .. }] if tag == "code" => {
+                        let class = props.get("className").or_else(|| props.get("class")).and_then(Value::as_str).unwrap_or_default();
+                        class.split_whitespace().find_map(|class| class.strip_prefix("language-")).map(Value::from)
+                    }
+                    _ => None,
+                };
+                let code_line = attributes([("code-block", language.unwrap_or(true.into()))]);
+                let code = text_content(children);
+                for code in code.strip_suffix('\n').unwrap_or(&code).split('\n') {
+                    if !code.is_empty() {
+                        self.insert(code.into(), &Map::new());
+                    }
+                    self.newline(&code_line);
+                }
+            }
+            "tr" => {
+                let cells = children.iter().filter(|cell| matches!(cell, Node::Element { tag, .. } if tag == "td" || tag == "th"));
+                for (i, cell) in cells.enumerate() {
+                    if i > 0 {
+                        self.insert("\t".into(), &Map::new());
+                    }
+                    self.inlines(cell.children().map_or(&[][..], Vec::as_slice), line);
+                }
+                self.newline(line);
+            }
+            "hr" => {}
+            "p" => {
+                self.inlines(children, line);
+                self.newline(line);
+            }
+            _ => self.blocks(children, line, indent),
+        }
+    }
+
+    fn inlines(&mut self, nodes: &[Node], line: &Map<String, Value>) {
+        for node in nodes {
+            self.inline(node, &Map::new(), line);
+        }
+    }
+
+    /// Writes inline content, returning whether anything was written.
+    fn inline(&mut self, node: &Node, format: &Map<String, Value>, line: &Map<String, Value>) -> bool {
+        match node {
+            Node::Text { content, .. } if !content.trim().is_empty() || !format.is_empty() || !self.at_line_start() => {
+                // A newline would end the line in Quill.
+                self.insert(content.replace('\n', " ").into(), format);
+                true
+            }
+            Node::Element { tag, props, children, .. } => {
+                let mut format = format.clone();
+                match tag.as_str() {
+                    "br" => {
+                        self.newline(line);
+                        return false;
+                    }
+                    "img" => {
+                        let src = props.get("src").cloned().unwrap_or_default();
+                        let mut embed = format.clone();
+                        if let Some(alt) = props.get("alt") {
+                            embed.insert("alt".into(), alt.clone());
+                        }
+                        self.insert(json!({ "image": src }), &embed);
+                        return true;
+                    }
+                    "input" => return false,
+                    "strong" | "b" => _ = format.insert("bold".into(), true.into()),
+                    "em" | "i" => _ = format.insert("italic".into(), true.into()),
+                    "del" | "s" => _ = format.insert("strike".into(), true.into()),
+                    "u" => _ = format.insert("underline".into(), true.into()),
+                    "code" => _ = format.insert("code".into(), true.into()),
+                    "sub" => _ = format.insert("script".into(), "sub".into()),
+                    "sup" => _ = format.insert("script".into(), "super".into()),
+                    "a" => _ = format.insert("link".into(), props.get("href").cloned().unwrap_or_default()),
+                    _ => {}
+                }
+                let mut written = false;
+                for child in children {
+                    written |= self.inline(child, &format, line);
+                }
+                written
+            }
+            Node::Fragment { children } => {
+                let mut written = false;
+                for child in children {
+                    written |= self.inline(child, format, line);
+                }
+                written
+            }
+            _ => false,
+        }
+    }
+
+    fn at_line_start(&self) -> bool {
+        self.ops.last().and_then(|op| op["insert"].as_str()).is_none_or(|text| text.ends_with('\n'))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_to_delta() {
+        let markdown = "# Hi\n\nA **b** [c](/x) ![d](/d.png)\n\n- one\n  - two\n\n```js\nx\n```";
+        assert_eq!(to_delta(&parse(markdown, &TranspileOptions::default())), json!({ "ops": [
+            { "insert": "Hi" },
+            { "insert": "\n", "attributes": { "header": 1 } },
+            { "insert": "A " },
+            { "insert": "b", "attributes": { "bold": true } },
+            { "insert": " " },
+            { "insert": "c", "attributes": { "link": "/x" } },
+            { "insert": " " },
+            { "insert": { "image": "/d.png" }, "attributes": { "alt": "d" } },
+            { "insert": "\none" },
+            { "insert": "\n", "attributes": { "list": "bullet" } },
+            { "insert": "two" },
+            { "insert": "\n", "attributes": { "list": "bullet", "indent": 1 } },
+            { "insert": "x" },
+            { "insert": "\n", "attributes": { "code-block": "js" } },
+        ] }));
+    }
+}
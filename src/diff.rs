@@ -0,0 +1,299 @@
+//! Structural diffing between two versions of a document.
+//!
+//! Patches address nodes by index path (child positions from the root) and
+//! are meant to be applied in order (`apply` does so): paths in later
+//! patches account for earlier removals and insertions. Source spans are
+//! ignored, since they shift on every edit without changing what is
+//! rendered, and so are content ids.
+
+use indexmap::IndexMap;
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+use crate::Node;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum Patch {
+    /// Insert `node` so that it ends up at `path`.
+    Insert { path: Vec<usize>, node: Node },
+    Remove { path: Vec<usize> },
+    Replace { path: Vec<usize>, node: Node },
+    /// Update props of the element at `path`, keeping its children. Keys
+    /// in `set` replace values in place or are appended; keys in both
+    /// `remove` and `set` move to the end, so props keep the new order.
+    Props {
+        path: Vec<usize>,
+        set: IndexMap<String, serde_json::Value>,
+        remove: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PatchError {
+    #[error("patch {index}: no node at path {path:?}")]
+    InvalidPath { index: usize, path: Vec<usize> },
+    #[error("patch {index}: node at path {path:?} is not an element")]
+    NotAnElement { index: usize, path: Vec<usize> },
+}
+
+/// Computes the patches that turn `old` into `new`.
+pub fn diff(old: &[Node], new: &[Node]) -> Vec<Patch> {
+    let mut patches = Vec::new();
+    diff_children(old, new, &mut Vec::new(), &mut patches);
+    patches
+}
+
+fn diff_children(old: &[Node], new: &[Node], path: &mut Vec<usize>, patches: &mut Vec<Patch>) {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| same(a, b)).count();
+    let suffix = old[prefix..].iter().rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| same(a, b))
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+    let paired = old_mid.len().min(new_mid.len());
+
+    for i in 0..paired {
+        path.push(prefix + i);
+        diff_node(&old_mid[i], &new_mid[i], path, patches);
+        path.pop();
+    }
+    for i in (paired..old_mid.len()).rev() {
+        path.push(prefix + i);
+        patches.push(Patch::Remove { path: path.clone() });
+        path.pop();
+    }
+    for (i, node) in new_mid.iter().enumerate().skip(paired) {
+        path.push(prefix + i);
+        patches.push(Patch::Insert { path: path.clone(), node: node.clone() });
+        path.pop();
+    }
+}
+
+fn diff_node(old: &Node, new: &Node, path: &mut Vec<usize>, patches: &mut Vec<Patch>) {
+    match (old, new) {
+        (
+            Node::Element { tag: old_tag, props: old_props, children: old_children, .. },
+            Node::Element { tag: new_tag, props: new_props, children: new_children, .. },
+        ) if old_tag == new_tag => {
+            // The longest run of new keys that is already in order in the
+            // old props stays in place; the keys after it move to the end.
+            let mut old_keys = old_props.keys();
+            let in_order = new_props.keys().take_while(|key| old_keys.any(|old| old == *key)).count();
+            let set: IndexMap<_, _> = new_props.iter()
+                .enumerate()
+                .filter(|(i, (key, value))| *i >= in_order || old_props.get(*key) != Some(*value))
+                .map(|(_, (key, value))| (key.clone(), value.clone()))
+                .collect();
+            let mut remove: Vec<String> = old_props.keys()
+                .filter(|key| new_props.get_index_of(*key).is_none_or(|i| i >= in_order))
+                .cloned()
+                .collect();
+            remove.sort();
+            if !set.is_empty() || !remove.is_empty() {
+                patches.push(Patch::Props { path: path.clone(), set, remove });
+            }
+            diff_children(old_children, new_children, path, patches);
+        }
+        (Node::Fragment { children: old_children }, Node::Fragment { children: new_children }) => {
+            diff_children(old_children, new_children, path, patches);
+        }
+        _ if same(old, new) => {}
+        _ => patches.push(Patch::Replace { path: path.clone(), node: new.clone() }),
+    }
+}
+
+/// Applies `patches` from `diff` in order. If any patch does not fit the
+/// tree, `nodes` is left as it was.
+pub fn apply(nodes: &mut Vec<Node>, patches: &[Patch]) -> Result<(), PatchError> {
+    let mut patched = nodes.clone();
+    for (index, patch) in patches.iter().enumerate() {
+        apply_one(&mut patched, patch).map_err(|invalid| match invalid {
+            Invalid::Path(path) => PatchError::InvalidPath { index, path },
+            Invalid::NotAnElement(path) => PatchError::NotAnElement { index, path },
+        })?;
+    }
+    *nodes = patched;
+    Ok(())
+}
+
+enum Invalid {
+    Path(Vec<usize>),
+    NotAnElement(Vec<usize>),
+}
+
+fn apply_one(nodes: &mut Vec<Node>, patch: &Patch) -> Result<(), Invalid> {
+    let path = match patch {
+        Patch::Insert { path, .. } | Patch::Remove { path } | Patch::Replace { path, .. } | Patch::Props { path, .. } => path,
+    };
+    let invalid = || Invalid::Path(path.clone());
+    let (&index, parent_path) = path.split_last().ok_or_else(invalid)?;
+    let siblings = children_at(nodes, parent_path).ok_or_else(invalid)?;
+    match patch {
+        Patch::Insert { node, .. } if index <= siblings.len() => siblings.insert(index, node.clone()),
+        Patch::Remove { .. } if index < siblings.len() => {
+            siblings.remove(index);
+        }
+        Patch::Replace { node, .. } if index < siblings.len() => siblings[index] = node.clone(),
+        Patch::Props { set, remove, .. } if index < siblings.len() => {
+            let Node::Element { props, .. } = &mut siblings[index] else {
+                return Err(Invalid::NotAnElement(path.clone()));
+            };
+            for key in remove {
+                props.shift_remove(key);
+            }
+            props.extend(set.iter().map(|(key, value)| (key.clone(), value.clone())));
+        }
+        _ => return Err(invalid()),
+    }
+    Ok(())
+}
+
+/// The child list at `path`: `nodes` itself for an empty path.
+fn children_at<'a>(nodes: &'a mut Vec<Node>, path: &[usize]) -> Option<&'a mut Vec<Node>> {
+    match path.split_first() {
+        None => Some(nodes),
+        Some((&first, rest)) => children_at(nodes.get_mut(first)?.children_mut()?, rest),
+    }
+}
+
+/// Equality that ignores source spans.
+fn same(a: &Node, b: &Node) -> bool {
+    match (a, b) {
+        (
+            Node::Element { tag: a_tag, props: a_props, children: a_children, .. },
+            Node::Element { tag: b_tag, props: b_props, children: b_children, .. },
+        ) => {
+            a_tag == b_tag
+                && a_props.iter().eq(b_props)
+                && a_children.len() == b_children.len()
+                && a_children.iter().zip(b_children).all(|(a, b)| same(a, b))
+        }
+        (Node::Text { content: a, .. }, Node::Text { content: b, .. })
+        | (Node::Comment { content: a, .. }, Node::Comment { content: b, .. }) => a == b,
+        (
+            Node::Raw { content: a, format: a_format, .. },
+            Node::Raw { content: b, format: b_format, .. },
+        ) => a == b && a_format == b_format,
+        (Node::Fragment { children: a }, Node::Fragment { children: b }) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same(a, b))
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_diff_identical() {
+        let options = TranspileOptions { source_spans: true, ..Default::default() };
+        let old = parse("# A\n\nB", &options);
+        let new = parse("# A\n\n\nB", &options);
+
+        assert!(diff(&old, &new).is_empty());
+    }
+
+    #[test]
+    fn test_diff_insert_remove_replace() {
+        let options = TranspileOptions::default();
+        let old = parse("# Title\n\nOne\n\nTwo", &options);
+        let new = parse("# Title\n\nOne\n\nInserted\n\nTwo", &options);
+        let patches = diff(&old, &new);
+        assert_eq!(patches.len(), 1);
+        assert!(matches!(&patches[0], Patch::Insert { path, .. } if path == &[2]));
+
+        let patches = diff(&new, &old);
+        assert_eq!(patches, vec![Patch::Remove { path: vec![2] }]);
+
+        let edited = parse("# Title\n\nOne\n\nTwo!", &options);
+        let patches = diff(&old, &edited);
+        assert_eq!(patches.len(), 1);
+        assert!(matches!(&patches[0], Patch::Replace { path, .. } if path == &[2, 0]));
+    }
+
+    #[test]
+    fn test_apply() {
+        let options = TranspileOptions::default();
+        let versions = [
+            "# Title\n\nOne\n\nTwo",
+            "# Title\n\nOne\n\nInserted [link](/a)\n\nTwo",
+            "# New title\n\nInserted [link](/b \"t\")\n\n- a\n- b",
+            "- b\n- a\n- c",
+            "",
+        ];
+        for old in versions {
+            for new in versions {
+                let (mut nodes, new) = (parse(old, &options), parse(new, &options));
+                let patches = diff(&nodes, &new);
+                apply(&mut nodes, &patches).unwrap();
+                assert_eq!(nodes, new);
+            }
+        }
+    }
+
+    #[test]
+    fn test_apply_invalid() {
+        let mut nodes = parse("Text", &TranspileOptions::default());
+        let original = nodes.clone();
+        let patches = [
+            Patch::Remove { path: vec![0, 0] },
+            Patch::Insert { path: vec![0, 2], node: Node::text("x") },
+        ];
+        let err = apply(&mut nodes, &patches).unwrap_err();
+        assert_eq!(err, PatchError::InvalidPath { index: 1, path: vec![0, 2] });
+        assert_eq!(nodes, original);
+
+        let props = Patch::Props { path: vec![0, 0], set: IndexMap::new(), remove: Vec::new() };
+        assert!(matches!(apply(&mut nodes, &[props]), Err(PatchError::NotAnElement { .. })));
+        assert!(matches!(apply(&mut nodes, &[Patch::Remove { path: vec![] }]), Err(PatchError::InvalidPath { .. })));
+    }
+
+    #[test]
+    fn test_diff_props() {
+        let options = TranspileOptions::default();
+        let old = parse("[link](/a)", &options);
+        let new = parse("[link](/b)", &options);
+        let patches = diff(&old, &new);
+
+        assert_eq!(patches.len(), 1);
+        match &patches[0] {
+            Patch::Props { path, set, remove } => {
+                assert_eq!(path, &[0, 0]);
+                assert_eq!(set.get("href").unwrap(), "/b");
+                assert!(remove.is_empty());
+            }
+            other => panic!("Expected props patch, got {:?}", other),
+        }
+        let json = serde_json::to_string(&patches[0]).unwrap();
+        assert!(json.starts_with("{\"op\":\"props\""));
+    }
+
+    #[test]
+    fn test_apply_props_order() {
+        let link = |props: &[(&str, &str)]| {
+            props.iter().fold(Node::element("a"), |node, &(key, value)| node.prop(key, value))
+        };
+        let versions = [
+            link(&[("href", "/a"), ("title", "t"), ("id", "x")]),
+            link(&[("id", "x"), ("href", "/b"), ("title", "t")]),
+            link(&[("href", "/a"), ("class", "c"), ("title", "t")]),
+            link(&[("title", "t"), ("href", "/a")]),
+        ];
+        for old in &versions {
+            for new in &versions {
+                let mut nodes = vec![old.clone()];
+                let patches = diff(&nodes, std::slice::from_ref(new));
+                apply(&mut nodes, &patches).unwrap();
+                assert_eq!(serde_json::to_string(&nodes).unwrap(), serde_json::to_string(&[new]).unwrap());
+            }
+        }
+        assert_eq!(diff(&[versions[3].clone()], &[link(&[("href", "/a"), ("title", "t")])]), [Patch::Props {
+            path: vec![0],
+            set: IndexMap::from([("title".to_string(), "t".into())]),
+            remove: vec!["title".to_string()],
+        }]);
+    }
+}
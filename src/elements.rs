@@ -0,0 +1,367 @@
+//! Per-element rewrites configured on `TranspileOptions`, applied once
+//! transforms have run so they still see standard HTML tag names.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::ops::Deref;
+use indexmap::IndexMap;
+use serde_json::Value;
+use crate::links::{LinkKind, SchemeAction, SCHEME_RE};
+use crate::{Node, PropStyle, Span, TranspileOptions, UrlSchemes};
+
+/// HTML attribute names and their React equivalents, where they differ by
+/// more than `aria-*`/`data-*` (which React keeps as is).
+const REACT_PROPS: &[(&str, &str)] = &[
+    ("accesskey", "accessKey"),
+    ("allowfullscreen", "allowFullScreen"),
+    ("autocomplete", "autoComplete"),
+    ("autofocus", "autoFocus"),
+    ("autoplay", "autoPlay"),
+    ("cellpadding", "cellPadding"),
+    ("cellspacing", "cellSpacing"),
+    ("charset", "charSet"),
+    ("class", "className"),
+    ("colspan", "colSpan"),
+    ("contenteditable", "contentEditable"),
+    ("crossorigin", "crossOrigin"),
+    ("datetime", "dateTime"),
+    ("enctype", "encType"),
+    ("for", "htmlFor"),
+    ("formaction", "formAction"),
+    ("frameborder", "frameBorder"),
+    ("hreflang", "hrefLang"),
+    ("http-equiv", "httpEquiv"),
+    ("inputmode", "inputMode"),
+    ("itemprop", "itemProp"),
+    ("maxlength", "maxLength"),
+    ("minlength", "minLength"),
+    ("novalidate", "noValidate"),
+    ("playsinline", "playsInline"),
+    ("readonly", "readOnly"),
+    ("referrerpolicy", "referrerPolicy"),
+    ("rowspan", "rowSpan"),
+    ("spellcheck", "spellCheck"),
+    ("srcdoc", "srcDoc"),
+    ("srclang", "srcLang"),
+    ("srcset", "srcSet"),
+    ("tabindex", "tabIndex"),
+    ("usemap", "useMap"),
+];
+
+lazy_static! {
+    /// The path of a link to a markdown file, before any query or fragment.
+    static ref MD_PATH_RE: Regex = Regex::new(r"^([^?#]*)\.md([?#].*)?$").unwrap();
+}
+
+/// Applies `prop_style`, `id_prefix`, the URL options and `url_schemes`,
+/// `class_map` and then `tag_map` to every element in `nodes`.
+pub(crate) fn rewrite_elements(nodes: &mut Vec<Node>, options: &TranspileOptions) {
+    if options.prop_style == PropStyle::Preserve
+        && options.url_schemes.schemes.is_empty()
+        && options.url_schemes.other == SchemeAction::Allow
+        && options.tag_map.is_empty()
+        && options.class_map.is_empty()
+        && options.base_url.is_none()
+        && options.link_rewriter.is_none()
+        && options.image_rewriter.is_none()
+        && !options.md_links_as_routes
+        && options.id_prefix.is_none()
+    {
+        return;
+    }
+    nodes.retain_mut(|node| {
+        match node {
+            Node::Element { tag, props, children, span, .. } => {
+                if options.prop_style != PropStyle::Preserve {
+                    restyle_props(props, options.prop_style);
+                }
+                let (class_key, src_set_key) = match options.prop_style {
+                    PropStyle::Html | PropStyle::Preact => ("class", "srcset"),
+                    PropStyle::React | PropStyle::Preserve => ("className", "srcSet"),
+                };
+                if let Some(prefix) = &options.id_prefix {
+                    if let Some(id) = props.get("id").and_then(|v| v.as_str()) {
+                        let id = format!("{}{}", prefix, id);
+                        props.insert("id".to_string(), id.into());
+                    }
+                }
+                if let Some(attr) = url_attribute(tag) {
+                    let link = attr == "href";
+                    for (_, value) in props.iter_mut().filter(|(key, _)| key.eq_ignore_ascii_case(attr)) {
+                        if let Some(url) = value.as_str().and_then(|url| rewrite_url(url, link, options)) {
+                            *value = url.into();
+                        }
+                    }
+                }
+                if let Some(rewriter) = options.image_rewriter.as_ref().filter(|_| tag.eq_ignore_ascii_case("img")) {
+                    let src = props.iter().find(|(key, _)| key.eq_ignore_ascii_case("src"));
+                    if let Some((key, src)) = src.and_then(|(key, v)| Some((key.clone(), v.as_str()?.to_string()))) {
+                        if let Some(src_set) = rewriter.src_set(&src) {
+                            props.insert(src_set_key.to_string(), src_set.into());
+                        }
+                        props.insert(key, rewriter.rewrite(&src).into());
+                    }
+                }
+                match scheme_action(tag, props, &options.url_schemes) {
+                    SchemeAction::Allow => {}
+                    SchemeAction::Drop => return false,
+                    SchemeAction::Text => {
+                        *node = unlinked(tag, props, children, *span);
+                        return true;
+                    }
+                }
+                if let Some(class) = options.class_map.get(tag.as_str()) {
+                    let existing = props.get(class_key).and_then(|v| v.as_str()).filter(|c| !c.is_empty());
+                    let class = match existing {
+                        Some(existing) => format!("{} {}", existing, class),
+                        None => class.clone(),
+                    };
+                    props.insert(class_key.to_string(), class.into());
+                }
+                if let Some(mapped) = options.tag_map.get(tag.as_str()) {
+                    *tag = mapped.clone();
+                }
+                rewrite_elements(children, options);
+            }
+            Node::Fragment { children } => rewrite_elements(children, options),
+            Node::Text { .. } | Node::Comment { .. } | Node::Raw { .. } => {}
+        }
+        true
+    });
+}
+
+/// Applies just the `url_schemes` part of `rewrite_elements`, for trees
+/// that skip the other rewrites.
+pub(crate) fn check_url_schemes<S: Deref<Target = str> + From<String>>(nodes: &mut Vec<Node<S>>, schemes: &UrlSchemes) {
+    nodes.retain_mut(|node| {
+        match node {
+            Node::Element { tag, props, children, span, .. } => match scheme_action(tag, props, schemes) {
+                SchemeAction::Allow => check_url_schemes(children, schemes),
+                SchemeAction::Drop => return false,
+                SchemeAction::Text => *node = unlinked(tag, props, children, *span),
+            },
+            Node::Fragment { children } => check_url_schemes(children, schemes),
+            Node::Text { .. } | Node::Comment { .. } | Node::Raw { .. } => {}
+        }
+        true
+    });
+}
+
+/// The prop holding the URL of a link or image, whatever the case of the
+/// tag; HTML attribute names are case-insensitive, so callers match the
+/// prop name with `eq_ignore_ascii_case`.
+fn url_attribute(tag: &str) -> Option<&'static str> {
+    if tag.eq_ignore_ascii_case("a") {
+        Some("href")
+    } else if tag.eq_ignore_ascii_case("img") {
+        Some("src")
+    } else {
+        None
+    }
+}
+
+/// The `url_schemes` action for a link's `href` or an image's `src`: the
+/// first disallowed one when the prop appears in several cases.
+fn scheme_action(tag: &str, props: &IndexMap<String, Value>, schemes: &UrlSchemes) -> SchemeAction {
+    let Some(attr) = url_attribute(tag) else {
+        return SchemeAction::Allow;
+    };
+    props
+        .iter()
+        .filter(|(key, _)| key.eq_ignore_ascii_case(attr))
+        .filter_map(|(_, v)| v.as_str())
+        .map(|url| schemes.action(url))
+        .find(|action| *action != SchemeAction::Allow)
+        .unwrap_or(SchemeAction::Allow)
+}
+
+/// The text left of a link or image with a disallowed URL scheme.
+fn unlinked<S: Deref<Target = str> + From<String>>(tag: &str, props: &IndexMap<String, Value>, children: &[Node<S>], span: Option<Span>) -> Node<S> {
+    let content = if tag.eq_ignore_ascii_case("img") {
+        let alt = props.iter().find(|(key, _)| key.eq_ignore_ascii_case("alt"));
+        alt.and_then(|(_, v)| v.as_str()).unwrap_or_default().to_string()
+    } else {
+        crate::text_content(children)
+    };
+    Node::Text { content: content.into(), span }
+}
+
+/// The HTML attribute name for a prop, which may use its React name.
+pub(crate) fn html_attribute(prop: &str) -> &str {
+    REACT_PROPS.iter().find(|&&(_, react)| react == prop).map_or(prop, |&(html, _)| html)
+}
+
+/// The React name for a prop, which may use its HTML attribute name.
+pub(crate) fn react_prop(attribute: &str) -> &str {
+    REACT_PROPS.iter().find(|&&(html, _)| attribute.eq_ignore_ascii_case(html)).map_or(attribute, |&(_, react)| react)
+}
+
+/// Renames `props` to React or HTML attribute names, keeping their order.
+fn restyle_props(props: &mut IndexMap<String, Value>, style: PropStyle) {
+    let rename = |key: &str| -> Option<&'static str> {
+        REACT_PROPS.iter().find_map(|&(html, react)| match style {
+            PropStyle::React if key.eq_ignore_ascii_case(html) => Some(react),
+            PropStyle::Html if key == react => Some(html),
+            PropStyle::Preact if key != html && (key.eq_ignore_ascii_case(html) || key.eq_ignore_ascii_case(react)) => Some(html),
+            _ => None,
+        })
+    };
+    if !props.keys().any(|key| rename(key).is_some()) {
+        return;
+    }
+    *props = props.drain(..).map(|(key, value)| match rename(&key) {
+        Some(renamed) => (renamed.to_string(), value),
+        None => (key, value),
+    }).collect();
+}
+
+/// Prefixes an in-page anchor with `id_prefix` and passes a link through
+/// `link_rewriter`, then resolves a relative `url` against `base_url`,
+/// first turning a link to a `.md` file into its route. Absolute URLs and
+/// in-page anchors are not resolved.
+fn rewrite_url(url: &str, link: bool, options: &TranspileOptions) -> Option<String> {
+    let mut rewritten = None;
+    if let (Some(prefix), Some(anchor)) = (&options.id_prefix, url.strip_prefix('#')) {
+        if link && !anchor.is_empty() {
+            rewritten = Some(format!("#{}{}", prefix, anchor));
+        }
+    }
+    if let Some(rewriter) = options.link_rewriter.as_ref().filter(|_| link) {
+        let url = rewritten.as_deref().unwrap_or(url);
+        rewritten = Some(rewriter.rewrite(url, LinkKind::of(url)));
+    }
+    let url = rewritten.as_deref().unwrap_or(url);
+    if url.is_empty() || url.starts_with('#') || SCHEME_RE.is_match(url) {
+        return rewritten;
+    }
+    let mut url = url.to_string();
+    if link && options.md_links_as_routes {
+        if let Some(caps) = MD_PATH_RE.captures(&url) {
+            url = format!("{}{}", &caps[1], caps.get(2).map_or("", |rest| rest.as_str()));
+        }
+    }
+    match &options.base_url {
+        Some(base) => base.join(&url).ok().map(String::from),
+        None => Some(url),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, Node, PropStyle, TranspileOptions};
+
+    #[test]
+    fn test_tag_map() {
+        let options = TranspileOptions::builder()
+            .allowed_tags(["a"])
+            .map_tag("h1", "Heading")
+            .map_tag("a", "Link")
+            .map_tag("code", "InlineCode")
+            .build();
+        let ast = parse("# Title\n\n[x](/x) <a href=\"/y\">y</a> `z`", &options);
+
+        assert_eq!(ast[0], Node::element("Heading").child(Node::text("Title")));
+        let tags: Vec<_> = ast[1].children().unwrap().iter().filter_map(Node::tag).collect();
+        assert_eq!(tags, ["Link", "Link", "InlineCode"]);
+    }
+
+    #[test]
+    fn test_base_url() {
+        let options = TranspileOptions::builder()
+            .base_url(crate::Url::parse("https://example.com/docs/guide/").unwrap())
+            .md_links_as_routes(true)
+            .build();
+        let markdown = "[a](setup.md#install) [b](../api.md) [c](/abs) [d](#top) [e](https://x.org/readme.md) ![i](img/logo.png)[^1]\n\n[^1]: Note.";
+        let ast = parse(markdown, &options);
+
+        let urls: Vec<_> = crate::iter(&ast)
+            .filter_map(|node| match node {
+                Node::Element { props, .. } => props.get("href").or(props.get("src"))?.as_str(),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(urls, [
+            "https://example.com/docs/guide/setup#install",
+            "https://example.com/docs/api",
+            "https://example.com/abs",
+            "#top",
+            "https://x.org/readme.md",
+            "https://example.com/docs/guide/img/logo.png",
+            "#fn-1",
+        ]);
+    }
+
+    #[test]
+    fn test_id_prefix() {
+        let options = TranspileOptions::builder()
+            .allowed_tags(["span"])
+            .heading_ids(true)
+            .id_prefix("post-7-")
+            .build();
+        let ast = parse("# Intro\n\nSee [intro](#intro) <span id=\"x\">x</span>[^1] [top](#)\n\n[^1]: Note.", &options);
+
+        let values: Vec<_> = crate::iter(&ast)
+            .filter_map(|node| match node {
+                Node::Element { props, .. } => props.get("id").or(props.get("href"))?.as_str(),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(values, ["post-7-intro", "#post-7-intro", "post-7-x", "#post-7-fn-1", "#", "post-7-fn-1"]);
+        assert_eq!(crate::outline(&ast)[0].slug, "post-7-intro");
+    }
+
+    #[test]
+    fn test_prop_style() {
+        let markdown = "<label for=\"x\" class=\"l\" tabindex=\"1\" data-k=\"v\">L</label>\n\n```rust\nx\n```";
+        let props = |style| {
+            let options = TranspileOptions::builder().allowed_tags(["label"]).prop_style(style).class("pre", "code").build();
+            let ast = parse(markdown, &options);
+            let keys = |node: &Node| match node {
+                Node::Element { props, .. } => props.keys().cloned().collect::<Vec<_>>(),
+                _ => Vec::new(),
+            };
+            (keys(crate::find(&ast, |n| n.tag() == Some("label")).unwrap()), keys(&ast[1]), keys(&ast[1].children().unwrap()[0]))
+        };
+
+        assert_eq!(props(PropStyle::Preserve), (
+            vec!["for".to_string(), "class".into(), "tabindex".into(), "data-k".into()],
+            vec!["className".to_string()],
+            vec!["className".to_string()],
+        ));
+        assert_eq!(props(PropStyle::React), (
+            vec!["htmlFor".to_string(), "className".into(), "tabIndex".into(), "data-k".into()],
+            vec!["className".to_string()],
+            vec!["className".to_string()],
+        ));
+        assert_eq!(props(PropStyle::Html), (
+            vec!["for".to_string(), "class".into(), "tabindex".into(), "data-k".into()],
+            vec!["class".to_string()],
+            vec!["class".to_string()],
+        ));
+        assert_eq!(props(PropStyle::Preact), props(PropStyle::Html));
+
+        let options = TranspileOptions::builder().allowed_tags(["label"]).prop_style(PropStyle::Preact).build();
+        let ast = parse("<label TabIndex=\"1\" htmlFor=\"x\" onClick=\"f\">L</label>", &options);
+        let label = crate::find(&ast, |n| n.tag() == Some("label")).unwrap();
+        assert_eq!(label, &Node::element("label").prop("tabindex", "1").prop("for", "x").prop("onClick", "f").child(Node::text("L")));
+    }
+
+    #[test]
+    fn test_class_map() {
+        let options = TranspileOptions::builder()
+            .class("blockquote", "md-quote")
+            .class("code", "md-code")
+            .map_tag("blockquote", "Quote")
+            .build();
+        let ast = parse("> quote
+
+```rust
+x
+```", &options);
+
+        assert_eq!(ast[0], Node::element("Quote").prop("className", "md-quote")
+            .child(Node::element("p").child(Node::text("quote"))));
+        let code = &ast[1].children().unwrap()[0];
+        assert_eq!(code, &Node::element("code").prop("className", "language-rust md-code").child(Node::text("x\n")));
+    }
+}
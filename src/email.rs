@@ -0,0 +1,163 @@
+//! HTML for email bodies: only elements and attributes email clients
+//! render reliably, with styles inlined.
+
+use std::collections::HashMap;
+use indexmap::IndexMap;
+use serde_json::Value;
+use crate::elements::html_attribute;
+use crate::render_html::render_html;
+use crate::{Node, SchemeAction, UrlSchemes};
+
+/// Elements kept in email HTML. Others are unwrapped, keeping their
+/// content.
+const EMAIL_TAGS: &[&str] = &[
+    "p", "h1", "h2", "h3", "h4", "h5", "h6", "a", "strong", "b", "em", "i", "u", "s", "del", "sub", "sup", "code",
+    "pre", "blockquote", "ul", "ol", "li", "br", "hr", "img", "table", "thead", "tbody", "tr", "th", "td", "span",
+    "div",
+];
+
+/// Elements dropped with their content.
+const DROPPED_TAGS: &[&str] = &["script", "style", "iframe", "object", "embed", "form", "template", "video", "audio"];
+
+/// Attributes kept on any element, besides `style`.
+const GLOBAL_ATTRIBUTES: &[&str] = &["title", "dir", "lang"];
+
+/// Attributes kept on specific elements.
+const ELEMENT_ATTRIBUTES: &[(&str, &[&str])] = &[
+    ("a", &["href"]),
+    ("img", &["src", "alt", "width", "height"]),
+    ("ol", &["start"]),
+    ("td", &["colspan", "rowspan", "align"]),
+    ("th", &["colspan", "rowspan", "align"]),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmailOptions {
+    /// Inline CSS for each tag, written before any `style` the element
+    /// already has.
+    pub styles: HashMap<String, String>,
+    /// Schemes allowed in `href` and `src`; links with other schemes are
+    /// unwrapped and images dropped.
+    pub url_schemes: UrlSchemes,
+}
+
+impl Default for EmailOptions {
+    fn default() -> Self {
+        let styles = [
+            ("p", "margin:0 0 16px;line-height:1.5"),
+            ("h1", "margin:0 0 16px;font-size:24px;line-height:1.25"),
+            ("h2", "margin:0 0 16px;font-size:20px;line-height:1.25"),
+            ("h3", "margin:0 0 16px;font-size:16px;line-height:1.25"),
+            ("a", "color:#0366d6;text-decoration:underline"),
+            ("blockquote", "margin:0 0 16px;padding:0 12px;border-left:4px solid #dddddd;color:#555555"),
+            ("pre", "margin:0 0 16px;padding:12px;background-color:#f6f8fa;font-family:monospace;white-space:pre-wrap"),
+            ("code", "font-family:monospace"),
+            ("ul", "margin:0 0 16px;padding-left:24px"),
+            ("ol", "margin:0 0 16px;padding-left:24px"),
+            ("img", "max-width:100%;height:auto;border:0"),
+            ("hr", "border:0;border-top:1px solid #dddddd;margin:16px 0"),
+            ("table", "border-collapse:collapse;margin:0 0 16px"),
+            ("th", "border:1px solid #dddddd;padding:6px 12px;text-align:left"),
+            ("td", "border:1px solid #dddddd;padding:6px 12px"),
+        ];
+        EmailOptions {
+            styles: styles.into_iter().map(|(tag, style)| (tag.to_string(), style.to_string())).collect(),
+            url_schemes: UrlSchemes::default(),
+        }
+    }
+}
+
+/// Renders `nodes` as HTML safe for email clients: elements outside a
+/// conservative set are unwrapped (`script`, `style`, `iframe`, forms and
+/// media are dropped), attributes are limited to a per-element allowlist,
+/// classes are replaced by the inline `styles` for each tag, and task list
+/// checkboxes become `☑`/`☐`. Raw HTML and comments are left out. The
+/// output is content only, without a layout table around it.
+pub fn render_email_html(nodes: &[Node], options: &EmailOptions) -> String {
+    render_html(&email_nodes(nodes, options))
+}
+
+fn email_nodes(nodes: &[Node], options: &EmailOptions) -> Vec<Node> {
+    let mut converted = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        convert(node, options, &mut converted);
+    }
+    converted
+}
+
+fn convert(node: &Node, options: &EmailOptions, out: &mut Vec<Node>) {
+    let Node::Element { tag, props, children, .. } = node else {
+        match node {
+            Node::Text { content, .. } => out.push(Node::text(content.clone())),
+            Node::Fragment { children } => out.extend(email_nodes(children, options)),
+            _ => {}
+        }
+        return;
+    };
+    let tag = tag.as_str();
+    if DROPPED_TAGS.contains(&tag) {
+        return;
+    }
+    if tag == "input" {
+        if props.get("type").and_then(Value::as_str) == Some("checkbox") {
+            let checked = props.get("checked").and_then(Value::as_bool).unwrap_or(false);
+            out.push(Node::text(if checked { "\u{2611} " } else { "\u{2610} " }));
+        }
+        return;
+    }
+    let allowed_url = |key: &str| {
+        props.get(key).and_then(Value::as_str).is_some_and(|url| options.url_schemes.action(url) == SchemeAction::Allow)
+    };
+    if !EMAIL_TAGS.contains(&tag) || (tag == "a" && !allowed_url("href")) {
+        out.extend(email_nodes(children, options));
+        return;
+    }
+    if tag == "img" && !allowed_url("src") {
+        return;
+    }
+    out.push(Node::Element {
+        tag: tag.to_string(),
+        props: email_props(tag, props, options),
+        children: email_nodes(children, options),
+        span: None,
+        content_id: None,
+    });
+}
+
+fn email_props(tag: &str, props: &IndexMap<String, Value>, options: &EmailOptions) -> IndexMap<String, Value> {
+    let allowed = ELEMENT_ATTRIBUTES.iter().find(|&&(t, _)| t == tag).map_or(&[][..], |&(_, attributes)| attributes);
+    let mut email_props = IndexMap::new();
+    let mut styles: Vec<&str> = options.styles.get(tag).map(String::as_str).into_iter().collect();
+    for (key, value) in props {
+        let name = html_attribute(key);
+        if name == "style" {
+            styles.extend(value.as_str());
+        } else if allowed.contains(&name) || GLOBAL_ATTRIBUTES.contains(&name) {
+            email_props.insert(name.to_string(), value.clone());
+        }
+    }
+    let style = styles.iter().map(|style| style.trim().trim_end_matches(';')).filter(|style| !style.is_empty()).collect::<Vec<_>>();
+    if !style.is_empty() {
+        email_props.insert("style".to_string(), style.join(";").into());
+    }
+    email_props
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_render_email_html() {
+        let markdown = "Hi [you](https://a.io \"T\")!\n\n<Card class=\"c\" style=\"color:red\"><span class=\"x\" onclick=\"f()\">in</span></Card><script>x</script>\n\n- [x] done";
+        let options = TranspileOptions::builder().allowed_tags(["Card", "span", "script"]).build();
+        let mut email = EmailOptions::default();
+        email.styles.clear();
+        email.styles.insert("a".to_string(), "color:#00f;".to_string());
+        email.styles.insert("span".to_string(), "font-weight:bold".to_string());
+        assert_eq!(render_email_html(&parse(markdown, &options), &email), "<p>Hi <a href=\"https://a.io\" title=\"T\" style=\"color:#00f\">you</a>!</p>\n\
+            <p><span style=\"font-weight:bold\">in</span></p>\n\
+            <ul><li>\u{2611} done</li></ul>");
+    }
+}
@@ -0,0 +1,177 @@
+//! Streaming form of `parse`: open/close/text events instead of a tree.
+//!
+//! Events go through the same tree builder as `parse`, so HTML handling
+//! and the tag allowlist behave identically, but each top-level block is
+//! released as soon as it is complete. Memory use is bounded by the
+//! largest block rather than the whole document.
+
+use std::collections::VecDeque;
+use indexmap::IndexMap;
+use pulldown_cmark::{OffsetIter, Parser};
+use serde::Serialize;
+use crate::code::route_code;
+use crate::elements::rewrite_elements;
+use crate::limits::limit_input;
+use crate::transform::rewrite_text;
+use crate::typography::apply_typography;
+use crate::whitespace::clean_whitespace;
+use crate::{normalize, Node, Span, TranspileOptions, TreeBuilder};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+pub enum JsxEvent {
+    Open {
+        tag: String,
+        props: IndexMap<String, serde_json::Value>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        span: Option<Span>,
+    },
+    Close {
+        tag: String,
+    },
+    Text {
+        content: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        span: Option<Span>,
+    },
+    Comment {
+        content: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        span: Option<Span>,
+    },
+    Raw {
+        content: String,
+        format: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        span: Option<Span>,
+    },
+}
+
+/// Parses `markdown` lazily into events. Fragments produce no events of
+/// their own. `code_handlers`, `normalize`, `whitespace`, `typography`,
+/// `text_hook` and the per-element options (`tag_map`, `base_url`, ...) are
+/// applied per block. `footnotes` (definitions stay where they were
+/// written, as with `FootnoteMode::InPlace`), `heading_ids`, `transforms`,
+/// `content_ids` and `keys` need the whole tree and are not applied, nor is
+/// `preprocess`. Consumers bound the work done by how many events they
+/// take, so only `max_input_bytes` is enforced.
+pub fn parse_events<'a>(markdown: &'a str, options: &'a TranspileOptions) -> impl Iterator<Item = JsxEvent> + 'a {
+    let (markdown, _) = limit_input(markdown, options.max_input_bytes);
+    Events {
+        parser: Parser::new_ext(markdown, options.parser_options()).into_offset_iter(),
+        builder: Some(TreeBuilder::new(markdown, options)),
+        options,
+        queue: VecDeque::new(),
+    }
+}
+
+struct Events<'a> {
+    parser: OffsetIter<'a, pulldown_cmark::DefaultBrokenLinkCallback>,
+    builder: Option<TreeBuilder<'a, 'a, String>>,
+    options: &'a TranspileOptions,
+    queue: VecDeque<JsxEvent>,
+}
+
+impl Events<'_> {
+    fn release(&mut self, mut nodes: Vec<Node>) {
+        route_code(&mut nodes, &self.options.code_handlers);
+        if self.options.normalize {
+            normalize(&mut nodes);
+        }
+        clean_whitespace(&mut nodes, &self.options.whitespace);
+        apply_typography(&mut nodes, self.options);
+        rewrite_text(&mut nodes, self.options.text_hook.as_deref());
+        rewrite_elements(&mut nodes, self.options);
+        for node in nodes {
+            push_events(node, &mut self.queue);
+        }
+    }
+}
+
+impl Iterator for Events<'_> {
+    type Item = JsxEvent;
+
+    fn next(&mut self) -> Option<JsxEvent> {
+        loop {
+            if let Some(event) = self.queue.pop_front() {
+                return Some(event);
+            }
+            let builder = self.builder.as_mut()?;
+            match self.parser.next() {
+                Some((event, range)) => {
+                    builder.event(event, range);
+                    if builder.stack.is_empty() {
+                        let nodes = std::mem::take(&mut builder.root);
+                        self.release(nodes);
+                    }
+                }
+                None => {
+                    let nodes = self.builder.take().unwrap().finish();
+                    self.release(nodes);
+                }
+            }
+        }
+    }
+}
+
+fn push_events(node: Node, queue: &mut VecDeque<JsxEvent>) {
+    match node {
+        Node::Element { tag, props, children, span, .. } => {
+            queue.push_back(JsxEvent::Open { tag: tag.clone(), props, span });
+            for child in children {
+                push_events(child, queue);
+            }
+            queue.push_back(JsxEvent::Close { tag });
+        }
+        Node::Text { content, span } => queue.push_back(JsxEvent::Text { content, span }),
+        Node::Fragment { children } => {
+            for child in children {
+                push_events(child, queue);
+            }
+        }
+        Node::Comment { content, span } => queue.push_back(JsxEvent::Comment { content, span }),
+        Node::Raw { content, format, span } => queue.push_back(JsxEvent::Raw { content, format, span }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn tree_events(nodes: Vec<Node>) -> Vec<JsxEvent> {
+        let mut queue = VecDeque::new();
+        for node in nodes {
+            push_events(node, &mut queue);
+        }
+        queue.into()
+    }
+
+    #[test]
+    fn test_parse_events() {
+        let options = TranspileOptions::default();
+        let events: Vec<_> = parse_events("# Hi\n\nText", &options).collect();
+
+        assert_eq!(events.len(), 6);
+        assert!(matches!(&events[0], JsxEvent::Open { tag, .. } if tag == "h1"));
+        assert_eq!(events[1], JsxEvent::Text { content: "Hi".to_string(), span: None });
+        assert_eq!(events[5], JsxEvent::Close { tag: "p".to_string() });
+        let json = serde_json::to_string(&events[5]).unwrap();
+        assert_eq!(json, r#"{"event":"close","tag":"p"}"#);
+    }
+
+    #[test]
+    fn test_parse_events_matches_tree() {
+        let markdown = "<div class=\"a\">\n\n- one\n- **two**\n\n</div>\n\n```rs\ncode\n```\n\n![alt](x.png) <script>x</script>\n\n| a |\n| - |\n| 1 |";
+        let options = TranspileOptions {
+            allowed_tags: vec!["div".to_string()],
+            source_spans: true,
+            normalize: true,
+            ..TranspileOptions::untrusted(Vec::new())
+        };
+        let events: Vec<_> = parse_events(markdown, &options).collect();
+
+        assert_eq!(events, tree_events(parse(markdown, &options)));
+    }
+
+}
@@ -0,0 +1,101 @@
+//! Excerpts for listing pages and RSS summaries.
+
+use crate::content_id::assign_content_ids;
+use crate::plain::to_plain_text;
+use crate::Node;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Excerpt {
+    /// The excerpted nodes, untruncated.
+    pub nodes: Vec<Node>,
+    /// Plain text of `nodes`, cut at a word boundary to at most
+    /// `max_chars` characters (ending in `…` if cut).
+    pub text: String,
+}
+
+/// Takes everything before a `<!-- more -->` comment, or the first
+/// paragraph if there is none.
+///
+/// The marker may sit between blocks or inside a top-level one, in which
+/// case that block is kept up to the marker.
+pub fn excerpt(nodes: &[Node], max_chars: usize) -> Excerpt {
+    let nodes = before_more(nodes).unwrap_or_else(|| {
+        let paragraph = nodes.iter().find(|node| matches!(node, Node::Element { tag, .. } if tag == "p"));
+        paragraph.into_iter().cloned().collect()
+    });
+    let text = truncate(&to_plain_text(&nodes), max_chars);
+    Excerpt { nodes, text }
+}
+
+fn is_more(node: &Node) -> bool {
+    matches!(node, Node::Comment { content, .. } if content.trim() == "more")
+}
+
+fn before_more(nodes: &[Node]) -> Option<Vec<Node>> {
+    for (i, node) in nodes.iter().enumerate() {
+        if is_more(node) {
+            return Some(nodes[..i].to_vec());
+        }
+        let Node::Element { tag, props, children, span, content_id } = node else { continue };
+        if let Some(at) = children.iter().position(is_more) {
+            let mut out = nodes[..i].to_vec();
+            out.push(Node::Element {
+                tag: tag.clone(),
+                props: props.clone(),
+                children: children[..at].to_vec(),
+                span: *span,
+                content_id: None,
+            });
+            // The cut element no longer matches its id.
+            if content_id.is_some() {
+                assign_content_ids(&mut out[i..]);
+            }
+            return Some(out);
+        }
+    }
+    None
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    // Leave room for the ellipsis.
+    let cut = text.char_indices().nth(max_chars.saturating_sub(1)).map_or(text.len(), |(i, _)| i);
+    let head = &text[..cut];
+    let head = match head.rfind(char::is_whitespace) {
+        Some(space) if !text[cut..].starts_with(char::is_whitespace) => &head[..space],
+        _ => head,
+    };
+    format!("{}…", head.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_excerpt() {
+        let options = TranspileOptions::default();
+        let ast = parse("# Title\n\nFirst *paragraph* here.\n\nSecond.", &options);
+
+        let first = excerpt(&ast, 100);
+        assert_eq!(first.nodes, ast[1..2]);
+        assert_eq!(first.text, "First paragraph here.");
+        assert_eq!(excerpt(&ast, 12).text, "First…");
+        assert_eq!(excerpt(&ast, 16).text, "First paragraph…");
+    }
+
+    #[test]
+    fn test_excerpt_more_marker() {
+        let options = TranspileOptions::default();
+        let ast = parse("Intro.\n\nMore intro.\n\n<!-- more -->\n\nRest.", &options);
+        let more = excerpt(&ast, 100);
+        assert_eq!(more.nodes, ast[..2]);
+        assert_eq!(more.text, "Intro.\n\nMore intro.");
+
+        let ast = parse("Intro <!-- more --> rest.\n\nNext.", &options);
+        assert_eq!(excerpt(&ast, 100).text, "Intro");
+    }
+}
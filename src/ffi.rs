@@ -0,0 +1,108 @@
+//! C ABI, for embedding the parser in C, C++, Go (cgo) and other runtimes.
+//! The header is `include/md2jsx.h`, generated with
+//! `cbindgen --config cbindgen.toml --output include/md2jsx.h`.
+
+use std::cell::Cell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+use crate::{parse, TranspileOptions};
+
+/// Outcome of the last `md2jsx_parse` call on the calling thread.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Md2jsxStatus {
+    Ok = 0,
+    /// `input` was null.
+    NullArgument = 1,
+    /// `input` or `options_json` was not UTF-8.
+    InvalidUtf8 = 2,
+    /// `options_json` did not deserialize to `TranspileOptions`.
+    InvalidOptions = 3,
+    /// The parser panicked.
+    Panic = 4,
+}
+
+thread_local! {
+    static LAST_STATUS: Cell<Md2jsxStatus> = const { Cell::new(Md2jsxStatus::Ok) };
+}
+
+/// Parses the NUL-terminated UTF-8 `input` with `options_json` (a
+/// `TranspileOptions` object, or null for the defaults) and returns the
+/// AST as a JSON string, to be released with `md2jsx_free`. Returns null
+/// on error; `md2jsx_last_status` tells why.
+///
+/// # Safety
+///
+/// `input` and `options_json`, if not null, must point to NUL-terminated
+/// strings valid for the duration of the call.
+#[no_mangle]
+pub unsafe extern "C" fn md2jsx_parse(input: *const c_char, options_json: *const c_char) -> *mut c_char {
+    let result = catch_unwind(AssertUnwindSafe(|| parse_json(input, options_json)))
+        .unwrap_or(Err(Md2jsxStatus::Panic));
+    let (status, json) = match result {
+        Ok(json) => (Md2jsxStatus::Ok, json.into_raw()),
+        Err(status) => (status, ptr::null_mut()),
+    };
+    LAST_STATUS.with(|last| last.set(status));
+    json
+}
+
+unsafe fn parse_json(input: *const c_char, options_json: *const c_char) -> Result<CString, Md2jsxStatus> {
+    if input.is_null() {
+        return Err(Md2jsxStatus::NullArgument);
+    }
+    let input = CStr::from_ptr(input).to_str().map_err(|_| Md2jsxStatus::InvalidUtf8)?;
+    let options: TranspileOptions = if options_json.is_null() {
+        TranspileOptions::default()
+    } else {
+        let json = CStr::from_ptr(options_json).to_str().map_err(|_| Md2jsxStatus::InvalidUtf8)?;
+        serde_json::from_str(json).map_err(|_| Md2jsxStatus::InvalidOptions)?
+    };
+    let json = serde_json::to_string(&parse(input, &options)).map_err(|_| Md2jsxStatus::Panic)?;
+    // JSON escapes control characters, so the string has no interior NUL.
+    Ok(CString::new(json).expect("JSON has no NUL"))
+}
+
+/// Status of the last `md2jsx_parse` call on the calling thread.
+#[no_mangle]
+pub extern "C" fn md2jsx_last_status() -> Md2jsxStatus {
+    LAST_STATUS.with(Cell::get)
+}
+
+/// Releases a string returned by `md2jsx_parse`. Null is ignored.
+///
+/// # Safety
+///
+/// `json` must be null or a pointer returned by `md2jsx_parse` that has
+/// not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn md2jsx_free(json: *mut c_char) {
+    if !json.is_null() {
+        drop(CString::from_raw(json));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_md2jsx_parse() {
+        let input = CString::new("Hi <b>x</b>").unwrap();
+        let options = CString::new(r#"{"allowed_tags": ["b"]}"#).unwrap();
+        unsafe {
+            let json = md2jsx_parse(input.as_ptr(), options.as_ptr());
+            assert_eq!(md2jsx_last_status(), Md2jsxStatus::Ok);
+            let ast: Vec<crate::Node> = serde_json::from_str(CStr::from_ptr(json).to_str().unwrap()).unwrap();
+            assert_eq!(crate::find_all(&ast, |node| node.tag() == Some("b")).len(), 1);
+            md2jsx_free(json);
+
+            let options = CString::new(r#"{"nope": 1}"#).unwrap();
+            assert!(md2jsx_parse(input.as_ptr(), options.as_ptr()).is_null());
+            assert_eq!(md2jsx_last_status(), Md2jsxStatus::InvalidOptions);
+            assert!(md2jsx_parse(ptr::null(), ptr::null()).is_null());
+            assert_eq!(md2jsx_last_status(), Md2jsxStatus::NullArgument);
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! Flat, index-based form of the AST.
+//!
+//! Nodes are stored in document (pre-)order in one vector and refer to
+//! their children by index, which gives cheap random access and suits
+//! columnar processing of very large documents. Index 0 is always a
+//! fragment holding the top-level nodes.
+
+use serde::{Serialize, Deserialize};
+use indexmap::IndexMap;
+use crate::{parse, Node, Span, TranspileOptions};
+
+/// A node without its children; see `FlatAst::children`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(tag = "type")]
+pub enum FlatNode {
+    #[serde(rename = "element")]
+    Element {
+        tag: String,
+        props: IndexMap<String, serde_json::Value>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        span: Option<Span>,
+        #[serde(rename = "__id", default, skip_serializing_if = "Option::is_none")]
+        content_id: Option<String>,
+    },
+    #[serde(rename = "text")]
+    Text {
+        content: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        span: Option<Span>,
+    },
+    #[serde(rename = "fragment")]
+    Fragment,
+    #[serde(rename = "comment")]
+    Comment {
+        content: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        span: Option<Span>,
+    },
+    #[serde(rename = "raw")]
+    Raw {
+        content: String,
+        format: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        span: Option<Span>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct FlatAst {
+    pub nodes: Vec<FlatNode>,
+    /// `children[i]` lists the indices of the children of `nodes[i]`.
+    pub children: Vec<Vec<u32>>,
+}
+
+impl FlatAst {
+    pub fn from_nodes(nodes: &[Node]) -> FlatAst {
+        let mut flat = FlatAst {
+            nodes: vec![FlatNode::Fragment],
+            children: vec![Vec::new()],
+        };
+        flat.children[0] = nodes.iter().map(|node| flat.push(node)).collect();
+        flat
+    }
+
+    /// Rebuilds the nested top-level nodes.
+    pub fn to_nodes(&self) -> Vec<Node> {
+        if self.nodes.is_empty() {
+            return Vec::new();
+        }
+        match self.build(0) {
+            Node::Fragment { children } => children,
+            node => vec![node],
+        }
+    }
+
+    /// Top-level node indices.
+    pub fn roots(&self) -> &[u32] {
+        self.children.first().map_or(&[], Vec::as_slice)
+    }
+
+    /// Appends `node` and its descendants, returning its index.
+    fn push(&mut self, node: &Node) -> u32 {
+        let index = self.nodes.len();
+        let flat = match node {
+            Node::Element { tag, props, span, content_id, .. } => FlatNode::Element {
+                tag: tag.clone(),
+                props: props.clone(),
+                span: *span,
+                content_id: content_id.clone(),
+            },
+            Node::Text { content, span } => FlatNode::Text { content: content.clone(), span: *span },
+            Node::Fragment { .. } => FlatNode::Fragment,
+            Node::Comment { content, span } => FlatNode::Comment { content: content.clone(), span: *span },
+            Node::Raw { content, format, span } => FlatNode::Raw {
+                content: content.clone(),
+                format: format.clone(),
+                span: *span,
+            },
+        };
+        self.nodes.push(flat);
+        self.children.push(Vec::new());
+        let children = node.children().map_or(&[][..], Vec::as_slice);
+        let indices = children.iter().map(|child| self.push(child)).collect();
+        self.children[index] = indices;
+        index as u32
+    }
+
+    fn build(&self, index: u32) -> Node {
+        let children = || self.children[index as usize].iter().map(|&child| self.build(child)).collect();
+        match &self.nodes[index as usize] {
+            FlatNode::Element { tag, props, span, content_id } => Node::Element {
+                tag: tag.clone(),
+                props: props.clone(),
+                children: children(),
+                span: *span,
+                content_id: content_id.clone(),
+            },
+            FlatNode::Text { content, span } => Node::Text { content: content.clone(), span: *span },
+            FlatNode::Fragment => Node::Fragment { children: children() },
+            FlatNode::Comment { content, span } => Node::Comment { content: content.clone(), span: *span },
+            FlatNode::Raw { content, format, span } => Node::Raw {
+                content: content.clone(),
+                format: format.clone(),
+                span: *span,
+            },
+        }
+    }
+}
+
+/// Parses `markdown` into the flat form.
+pub fn parse_flat(markdown: &str, options: &TranspileOptions) -> FlatAst {
+    FlatAst::from_nodes(&parse(markdown, options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_flat() {
+        let flat = parse_flat("# Hi\n\n- a\n- *b*", &TranspileOptions::default());
+
+        assert_eq!(flat.nodes.len(), flat.children.len());
+        assert_eq!(flat.nodes[0], FlatNode::Fragment);
+        assert_eq!(flat.roots(), &[1, 3]);
+        assert!(matches!(&flat.nodes[3], FlatNode::Element { tag, .. } if tag == "ul"));
+        assert_eq!(flat.children[3], vec![4, 6]);
+        assert_eq!(flat.nodes[2], FlatNode::Text { content: "Hi".to_string(), span: None });
+    }
+
+    #[test]
+    fn test_flat_round_trip() {
+        let options = TranspileOptions { source_spans: true, ..Default::default() };
+        let ast = parse("# Hi <!-- c -->\n\n| a |\n| - |\n| 1 |\n\n> quote", &options);
+        let flat = FlatAst::from_nodes(&ast);
+
+        assert_eq!(flat.to_nodes(), ast);
+        let json = serde_json::to_string(&flat).unwrap();
+        assert!(json.starts_with(r#"{"nodes":[{"type":"fragment"},"#));
+        assert_eq!(serde_json::from_str::<FlatAst>(&json).unwrap(), flat);
+    }
+}
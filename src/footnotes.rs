@@ -0,0 +1,294 @@
+//! Footnote layouts other than definitions in place, from
+//! `TranspileOptions::footnotes`.
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use crate::{FootnoteNames, Node};
+
+/// Where footnote content ends up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+pub enum FootnoteMode {
+    /// Each definition is a `div` where it was written; references link
+    /// to it by label.
+    #[default]
+    InPlace,
+    /// Definitions are collected into a closing
+    /// `<section className="footnotes"><ol>` in order of first reference,
+    /// with back links; references show their number.
+    EndSection,
+    /// Each reference is replaced by its definition's content in a
+    /// `<span className="footnote-inline">`.
+    Inline,
+    /// References keep their marker, numbered in `data-sidenote`, followed
+    /// by the content in a `<span className="sidenote">` to be placed in
+    /// the margin. The first sidenote for a label has the `id` its markers
+    /// link to.
+    SidenoteProps,
+}
+
+/// A definition's content, numbered once it is first referenced.
+struct Definition {
+    children: Vec<Node>,
+    number: Option<usize>,
+}
+
+pub(crate) fn layout_footnotes(nodes: &mut Vec<Node>, mode: FootnoteMode, names: &FootnoteNames) {
+    if mode == FootnoteMode::InPlace {
+        return;
+    }
+    let mut definitions = IndexMap::new();
+    take_definitions(nodes, names, &mut definitions);
+    if definitions.is_empty() {
+        return;
+    }
+    let mut numbered = 0;
+    place_references(nodes, mode, names, &mut definitions, &mut numbered, &mut Vec::new());
+    if mode != FootnoteMode::EndSection {
+        return;
+    }
+    // Number references inside definitions too, in the order the
+    // definitions will be listed, which they may extend.
+    let mut pending: Vec<String> = definitions.keys().cloned().collect();
+    while let Some(index) = (0..pending.len()).min_by_key(|&i| definitions[&pending[i]].number.unwrap_or(usize::MAX)) {
+        let label = pending.remove(index);
+        let mut children = std::mem::take(&mut definitions[&label].children);
+        place_references(&mut children, mode, names, &mut definitions, &mut numbered, &mut Vec::new());
+        definitions[&label].children = children;
+    }
+
+    let mut ordered: Vec<(String, Definition)> = definitions.into_iter().collect();
+    ordered.sort_by_key(|(_, definition)| definition.number.unwrap_or(usize::MAX));
+    let items = ordered.into_iter().map(|(label, definition)| {
+        let mut children = definition.children;
+        if definition.number.is_some() {
+            let backref = Node::element("a")
+                .prop("href", format!("#{}ref-{}", names.id_prefix, label))
+                .prop("className", "footnote-backref")
+                .child(Node::text("\u{21a9}"));
+            match children.last_mut() {
+                Some(Node::Element { tag, children, .. }) if tag == "p" => {
+                    children.extend([Node::text(" "), backref]);
+                }
+                _ => children.push(backref),
+            }
+        }
+        Node::element("li").prop("id", format!("{}{}", names.id_prefix, label)).with_children(children)
+    });
+    nodes.push(Node::element("section").prop("className", "footnotes").child(Node::element("ol").with_children(items)));
+}
+
+fn take_definitions(nodes: &mut Vec<Node>, names: &FootnoteNames, definitions: &mut IndexMap<String, Definition>) {
+    nodes.retain_mut(|node| {
+        let label = definition_label(node, names).map(str::to_string);
+        match (label, node) {
+            (Some(label), Node::Element { children, .. }) => {
+                definitions.insert(label, Definition { children: std::mem::take(children), number: None });
+                false
+            }
+            (_, node) => {
+                if let Some(children) = node.children_mut() {
+                    take_definitions(children, names, definitions);
+                }
+                true
+            }
+        }
+    });
+}
+
+fn definition_label<'n>(node: &'n Node, names: &FootnoteNames) -> Option<&'n str> {
+    let Node::Element { tag, props, .. } = node else { return None };
+    if tag != "div" || props.get("className").and_then(|v| v.as_str()) != Some(names.definition_class.as_str()) {
+        return None;
+    }
+    props.get("id")?.as_str()?.strip_prefix(names.id_prefix.as_str())
+}
+
+/// The label of a `sup` wrapping a footnote reference link.
+fn reference_label(node: &Node, names: &FootnoteNames) -> Option<String> {
+    let Node::Element { tag, children, .. } = node else { return None };
+    let [Node::Element { tag: link, props, .. }] = children.as_slice() else { return None };
+    if tag != "sup" || link != "a" || props.get("className").and_then(|v| v.as_str()) != Some(names.ref_class.as_str()) {
+        return None;
+    }
+    let href = props.get("href")?.as_str()?;
+    Some(href.strip_prefix('#')?.strip_prefix(names.id_prefix.as_str())?.to_string())
+}
+
+/// Replaces or numbers the references in `nodes`. Inlined definition
+/// content is placed in turn; `expanding` holds the labels being inlined,
+/// so a reference back to one of them stays a marker.
+fn place_references(
+    nodes: &mut Vec<Node>,
+    mode: FootnoteMode,
+    names: &FootnoteNames,
+    definitions: &mut IndexMap<String, Definition>,
+    numbered: &mut usize,
+    expanding: &mut Vec<String>,
+) {
+    let mut placed = Vec::with_capacity(nodes.len());
+    for mut node in nodes.drain(..) {
+        let label = reference_label(&node, names);
+        let Some((label, definition)) = label.and_then(|label| definitions.get_mut(&label).map(|d| (label, d))) else {
+            if let Some(children) = node.children_mut() {
+                place_references(children, mode, names, definitions, numbered, expanding);
+            }
+            placed.push(node);
+            continue;
+        };
+        let first = definition.number.is_none();
+        let number = *definition.number.get_or_insert_with(|| {
+            *numbered += 1;
+            *numbered
+        });
+        let cyclic = expanding.contains(&label);
+        let mut content = match mode {
+            FootnoteMode::Inline | FootnoteMode::SidenoteProps if !cyclic => inline_content(&definition.children),
+            _ => Vec::new(),
+        };
+        if !content.is_empty() {
+            expanding.push(label.clone());
+            place_references(&mut content, mode, names, definitions, numbered, expanding);
+            expanding.pop();
+        }
+        match mode {
+            FootnoteMode::InPlace => placed.push(node),
+            FootnoteMode::EndSection => {
+                if let Some(Node::Element { props, children, .. }) = node.children_mut().and_then(|c| c.first_mut()) {
+                    if first {
+                        props.insert("id".to_string(), format!("{}ref-{}", names.id_prefix, label).into());
+                    }
+                    *children = vec![Node::text(number.to_string())];
+                }
+                placed.push(node);
+            }
+            // A reference inside its own content keeps its text, unlinked.
+            FootnoteMode::Inline if cyclic => {
+                placed.push(Node::element("sup").child(Node::text(node.children().map_or(String::new(), |c| crate::text_content(c)))));
+            }
+            FootnoteMode::Inline => {
+                placed.push(Node::element("span").prop("className", "footnote-inline").with_children(content));
+            }
+            FootnoteMode::SidenoteProps => {
+                if let Node::Element { props, .. } = &mut node {
+                    props.insert("data-sidenote".to_string(), number.into());
+                }
+                placed.push(node);
+                if cyclic {
+                    continue;
+                }
+                // References link to the first sidenote of their label.
+                let mut sidenote = Node::element("span").prop("className", "sidenote").prop("data-sidenote", number);
+                if first {
+                    sidenote = sidenote.prop("id", format!("{}{}", names.id_prefix, label));
+                }
+                placed.push(sidenote.with_children(content));
+            }
+        }
+    }
+    *nodes = placed;
+}
+
+/// A definition's content for use inside a paragraph: paragraphs are
+/// unwrapped and separated by spaces.
+fn inline_content(children: &[Node]) -> Vec<Node> {
+    let mut content = Vec::new();
+    for child in children {
+        if !content.is_empty() {
+            content.push(Node::text(" "));
+        }
+        match child {
+            Node::Element { tag, children, .. } if tag == "p" => content.extend(children.iter().cloned()),
+            child => content.push(child.clone()),
+        }
+    }
+    content
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, FootnoteMode, Node, TranspileOptions};
+
+    const MARKDOWN: &str = "A[^b] and B[^a].\n\n[^a]: First.\n[^b]: Second.";
+
+    fn sup(href: &str, id: Option<&str>, text: &str) -> Node {
+        let mut link = Node::element("a").prop("href", href).prop("className", "footnote-ref");
+        if let Some(id) = id {
+            link = link.prop("id", id);
+        }
+        Node::element("sup").child(link.child(Node::text(text)))
+    }
+
+    fn prop<'n>(node: &'n Node, name: &str) -> Option<&'n str> {
+        match node {
+            Node::Element { props, .. } => props.get(name)?.as_str(),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_footnotes_end_section() {
+        let options = TranspileOptions::builder().footnotes(FootnoteMode::EndSection).normalize(true).build();
+        let backref = |label: &str| Node::element("a").prop("href", format!("#fn-ref-{}", label)).prop("className", "footnote-backref").child(Node::text("\u{21a9}"));
+        assert_eq!(parse(MARKDOWN, &options), [
+            Node::element("p").with_children([
+                Node::text("A"),
+                sup("#fn-b", Some("fn-ref-b"), "1"),
+                Node::text(" and B"),
+                sup("#fn-a", Some("fn-ref-a"), "2"),
+                Node::text("."),
+            ]),
+            Node::element("section").prop("className", "footnotes").child(Node::element("ol").with_children([
+                Node::element("li").prop("id", "fn-b").child(Node::element("p").with_children([Node::text("Second. "), backref("b")])),
+                Node::element("li").prop("id", "fn-a").child(Node::element("p").with_children([Node::text("First. "), backref("a")])),
+            ])),
+        ]);
+    }
+
+    #[test]
+    fn test_footnotes_inline_and_sidenotes() {
+        let options = TranspileOptions::builder().footnotes(FootnoteMode::Inline).normalize(true).build();
+        let inline = |text: &str| Node::element("span").prop("className", "footnote-inline").child(Node::text(text));
+        assert_eq!(parse(MARKDOWN, &options), [Node::element("p").with_children([
+            Node::text("A"),
+            inline("Second."),
+            Node::text(" and B"),
+            inline("First."),
+            Node::text("."),
+        ])]);
+
+        let options = TranspileOptions::builder().footnotes(FootnoteMode::SidenoteProps).build();
+        let ast = parse(MARKDOWN, &options);
+        assert_eq!(ast.len(), 1);
+        let children = ast[0].children().unwrap();
+        assert_eq!(children[1], sup("#fn-b", None, "b").prop("data-sidenote", 1));
+        assert_eq!(children[2], Node::element("span").prop("className", "sidenote").prop("data-sidenote", 1).prop("id", "fn-b").child(Node::text("Second.")));
+    }
+
+    #[test]
+    fn test_footnotes_nested() {
+        let markdown = "A[^a].\n\n[^a]: B[^b].\n[^b]: C[^a].";
+        for mode in [FootnoteMode::EndSection, FootnoteMode::Inline, FootnoteMode::SidenoteProps] {
+            let ast = parse(markdown, &TranspileOptions::builder().footnotes(mode).build());
+            let ids: Vec<_> = crate::iter(&ast).filter_map(|node| prop(node, "id")).collect();
+            for node in crate::iter(&ast) {
+                if let Some(anchor) = prop(node, "href").and_then(|href| href.strip_prefix('#')) {
+                    assert!(ids.contains(&anchor), "{:?}: dangling #{}", mode, anchor);
+                }
+            }
+        }
+
+        let options = TranspileOptions::builder().footnotes(FootnoteMode::Inline).normalize(true).build();
+        let inline = |children: Vec<Node>| Node::element("span").prop("className", "footnote-inline").with_children(children);
+        assert_eq!(parse(markdown, &options), [Node::element("p").with_children([
+            Node::text("A"),
+            inline(vec![Node::text("B"), inline(vec![Node::text("C"), Node::element("sup").child(Node::text("a")), Node::text(".")]), Node::text(".")]),
+            Node::text("."),
+        ])]);
+
+        let options = TranspileOptions::builder().footnotes(FootnoteMode::EndSection).build();
+        let ast = parse(markdown, &options);
+        let item = crate::find(&ast, |node| prop(node, "id") == Some("fn-a")).unwrap();
+        assert!(crate::find(item.children().unwrap(), |node| *node == sup("#fn-b", Some("fn-ref-b"), "2")).is_some());
+    }
+}
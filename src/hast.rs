@@ -0,0 +1,142 @@
+//! Conversion to hast, the HTML syntax tree of unified/rehype.
+
+use indexmap::IndexMap;
+use serde_json::{json, Map, Value};
+use crate::elements::react_prop;
+use crate::{Node, Span};
+
+/// Converts the AST to a hast `root` for rehype plugins. Props take hast
+/// property names (`className` as a list of classes, `htmlFor`,
+/// `tabIndex`, `ariaLabel`, `dataFooBar`) and React `key`s are left out;
+/// comments become `comment` nodes, `raw` HTML becomes `raw` and other raw
+/// nodes text. Given the markdown `source` the AST was parsed from with
+/// `source_spans`, spanned nodes get a `position`.
+pub fn to_hast(nodes: &[Node], source: Option<&str>) -> Value {
+    let lines = source.map(Lines::new);
+    json!({ "type": "root", "children": convert_all(nodes, lines.as_ref()) })
+}
+
+/// Line starts of the source, for turning byte offsets into points.
+struct Lines<'s> {
+    source: &'s str,
+    starts: Vec<usize>,
+}
+
+impl<'s> Lines<'s> {
+    fn new(source: &'s str) -> Self {
+        let starts = std::iter::once(0).chain(source.match_indices('\n').map(|(i, _)| i + 1)).collect();
+        Lines { source, starts }
+    }
+
+    /// A unist point: 1-based line and column (in characters) and offset.
+    fn point(&self, offset: usize) -> Value {
+        let offset = offset.min(self.source.len());
+        let line = self.starts.partition_point(|&start| start <= offset);
+        let start = self.starts[line - 1];
+        let column = self.source.get(start..offset).map_or(offset - start, |text| text.chars().count()) + 1;
+        json!({ "line": line, "column": column, "offset": offset })
+    }
+
+    fn position(&self, span: &Span) -> Value {
+        json!({ "start": self.point(span.start), "end": self.point(span.end) })
+    }
+}
+
+fn convert_all(nodes: &[Node], lines: Option<&Lines>) -> Vec<Value> {
+    let mut converted = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        convert(node, lines, &mut converted);
+    }
+    converted
+}
+
+fn convert(node: &Node, lines: Option<&Lines>, out: &mut Vec<Value>) {
+    let (mut value, span) = match node {
+        Node::Element { tag, props, children, span, .. } => (json!({
+            "type": "element",
+            "tagName": tag,
+            "properties": properties(props),
+            "children": convert_all(children, lines),
+        }), span),
+        Node::Text { content, span } => (json!({ "type": "text", "value": content }), span),
+        Node::Fragment { children } => {
+            for child in children {
+                convert(child, lines, out);
+            }
+            return;
+        }
+        Node::Comment { content, span } => (json!({ "type": "comment", "value": content }), span),
+        Node::Raw { content, format, span } if format == "html" => (json!({ "type": "raw", "value": content }), span),
+        Node::Raw { content, span, .. } => (json!({ "type": "text", "value": content }), span),
+    };
+    if let (Some(lines), Some(span)) = (lines, span) {
+        value["position"] = lines.position(span);
+    }
+    out.push(value);
+}
+
+fn properties(props: &IndexMap<String, Value>) -> Value {
+    let mut properties = Map::new();
+    for (key, value) in props {
+        if key == "key" || value.is_null() {
+            continue;
+        }
+        let name = property_name(key);
+        let value = match (name.as_str(), value) {
+            ("className", Value::String(classes)) => classes.split_whitespace().collect::<Vec<_>>().into(),
+            (_, value) => value.clone(),
+        };
+        properties.insert(name, value);
+    }
+    Value::Object(properties)
+}
+
+/// The hast property name for a prop under its React or HTML name:
+/// `data-*` and `aria-*` attributes are camel-cased.
+fn property_name(key: &str) -> String {
+    let Some(rest) = key.strip_prefix("data-").or_else(|| key.strip_prefix("aria-")) else {
+        return react_prop(key).to_string();
+    };
+    let mut name = key[..4].to_string();
+    let mut upper = true;
+    for c in rest.chars() {
+        match c {
+            '-' => upper = true,
+            c if upper => {
+                name.extend(c.to_uppercase());
+                upper = false;
+            }
+            c => name.push(c),
+        }
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_to_hast() {
+        let markdown = "# Hé\n\n<label for=\"x\" class=\"a b\" data-foo-bar=\"1\" aria-label=\"L\">x</label>";
+        let options = TranspileOptions::builder().allowed_tags(["label"]).source_spans(true).build();
+        let hast = to_hast(&parse(markdown, &options), Some(markdown));
+        assert_eq!(hast["children"][0], json!({
+            "type": "element",
+            "tagName": "h1",
+            "properties": {},
+            "children": [{ "type": "text", "value": "Hé", "position": {
+                "start": { "line": 1, "column": 3, "offset": 2 },
+                "end": { "line": 1, "column": 5, "offset": 5 },
+            } }],
+            "position": {
+                "start": { "line": 1, "column": 1, "offset": 0 },
+                "end": { "line": 2, "column": 1, "offset": 6 },
+            },
+        }));
+        let label = &to_hast(&parse(markdown, &options), None)["children"][1]["children"][0];
+        assert_eq!(label["properties"], json!({ "htmlFor": "x", "className": ["a", "b"], "dataFooBar": "1", "ariaLabel": "L" }));
+        assert!(label.get("position").is_none());
+    }
+}
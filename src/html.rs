@@ -0,0 +1,220 @@
+//! Tokenizer for the raw HTML chunks pulldown-cmark hands us.
+//!
+//! A single `Html`/`InlineHtml` event can hold several tags and text runs
+//! (`<div><div>inner</div></div>`), so chunks are split into tokens before
+//! they reach the tree builder.
+
+use indexmap::IndexMap;
+use regex::Regex;
+use lazy_static::lazy_static;
+
+lazy_static! {
+    static ref TAG_RE: Regex = Regex::new(r#"<!--(?s:(.*?))-->|<(/?)([a-zA-Z][a-zA-Z0-9-]*)((?:[^>"']|"[^"]*"|'[^']*')*?)(/?)>"#).unwrap();
+    static ref ATTR_RE: Regex = Regex::new(r#"([a-zA-Z0-9_:@.-]+)(?:=(?:"([^"]*)"|'([^']*)'|([^>\s]+)))?"#).unwrap();
+    static ref PARTIAL_TAG_RE: Regex = Regex::new(r#"</?[a-zA-Z][a-zA-Z0-9-]*(?:[^<>"']|"[^"]*"|'[^']*')*(?:"[^"]*|'[^']*)?$"#).unwrap();
+    static ref SCRIPT_CLOSE_RE: Regex = Regex::new(r#"(?i)</script\s*>"#).unwrap();
+    static ref STYLE_CLOSE_RE: Regex = Regex::new(r#"(?i)</style\s*>"#).unwrap();
+}
+
+/// For `script` and `style` (the HTML raw text elements), returns the
+/// pattern matching their end tag.
+pub(crate) fn raw_text_close_re(name: &str) -> Option<&'static Regex> {
+    if name.eq_ignore_ascii_case("script") {
+        Some(&SCRIPT_CLOSE_RE)
+    } else if name.eq_ignore_ascii_case("style") {
+        Some(&STYLE_CLOSE_RE)
+    } else {
+        None
+    }
+}
+
+/// Returns the offset of a tag or comment that is cut off at the end of
+/// `chunk`, as happens when a tag's attributes or a comment span several
+/// lines of an HTML block.
+pub(crate) fn partial_tag_start(chunk: &str) -> Option<usize> {
+    let comment = chunk.rfind("<!--").filter(|&start| !chunk[start + 4..].contains("-->"));
+    match (comment, PARTIAL_TAG_RE.find(chunk)) {
+        (Some(comment), Some(tag)) => Some(comment.min(tag.start())),
+        (comment, tag) => comment.or(tag.map(|m| m.start())),
+    }
+}
+
+/// Where a tag or comment found by `partial_tag_start` stands at the end
+/// of the buffered chunk, so each following line is scanned only once.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum PartialTag {
+    Comment,
+    /// Inside a tag, and inside a quoted attribute value if `quote` is set.
+    Tag { quote: Option<u8> },
+}
+
+impl PartialTag {
+    /// The state at the end of `partial`, which starts at a cut-off tag or
+    /// comment.
+    pub(crate) fn new(partial: &str) -> Self {
+        if partial.starts_with("<!--") {
+            return PartialTag::Comment;
+        }
+        let tag = PartialTag::Tag { quote: None };
+        tag.resume(partial, 1).unwrap_or(tag)
+    }
+
+    /// The state once `chunk[from..]` has been appended, or `None` if the
+    /// tag or comment ends there and `chunk` needs `partial_tag_start` again.
+    pub(crate) fn resume(self, chunk: &str, from: usize) -> Option<Self> {
+        let bytes = chunk.as_bytes();
+        match self {
+            PartialTag::Comment => {
+                // `-->` can straddle `from`, but not overlap the `<!--`.
+                let from = from.saturating_sub(2).max(4).min(bytes.len());
+                (!bytes[from..].windows(3).any(|window| window == b"-->")).then_some(self)
+            }
+            PartialTag::Tag { mut quote } => {
+                for &byte in &bytes[from..] {
+                    match (quote, byte) {
+                        (Some(open), _) if byte == open => quote = None,
+                        (Some(_), _) => {}
+                        (None, b'"' | b'\'') => quote = Some(byte),
+                        (None, b'<' | b'>') => return None,
+                        (None, _) => {}
+                    }
+                }
+                Some(PartialTag::Tag { quote })
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum HtmlToken<'a> {
+    Open {
+        name: &'a str,
+        props: IndexMap<String, serde_json::Value>,
+        self_closing: bool,
+        raw: &'a str,
+    },
+    Close {
+        name: &'a str,
+        raw: &'a str,
+    },
+    Comment(&'a str),
+    Text(&'a str),
+}
+
+/// Splits an HTML chunk into tags and the text between them. `pos` is the
+/// byte offset of the next unread token and may be moved forward by the
+/// caller to skip input.
+pub(crate) struct Tokenizer<'a> {
+    src: &'a str,
+    pub(crate) pos: usize,
+}
+
+impl<'a> Tokenizer<'a> {
+    pub(crate) fn new(src: &'a str) -> Self {
+        Tokenizer { src, pos: 0 }
+    }
+
+    pub(crate) fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = HtmlToken<'a>;
+
+    fn next(&mut self) -> Option<HtmlToken<'a>> {
+        if self.pos >= self.src.len() {
+            return None;
+        }
+        let caps = match TAG_RE.captures_at(self.src, self.pos) {
+            Some(caps) => caps,
+            None => {
+                let text = self.rest();
+                self.pos = self.src.len();
+                return Some(HtmlToken::Text(text));
+            }
+        };
+        let whole = caps.get(0).unwrap();
+        if whole.start() > self.pos {
+            let text = &self.src[self.pos..whole.start()];
+            self.pos = whole.start();
+            return Some(HtmlToken::Text(text));
+        }
+        self.pos = whole.end();
+
+        if let Some(comment) = caps.get(1) {
+            return Some(HtmlToken::Comment(comment.as_str()));
+        }
+        let raw = whole.as_str();
+        let name = caps.get(3).unwrap().as_str();
+        if !caps[2].is_empty() {
+            return Some(HtmlToken::Close { name, raw });
+        }
+        Some(HtmlToken::Open {
+            name,
+            props: parse_attributes(caps.get(4).unwrap().as_str()),
+            self_closing: !caps[5].is_empty(),
+            raw,
+        })
+    }
+}
+
+fn parse_attributes(attrs: &str) -> IndexMap<String, serde_json::Value> {
+    let mut props = IndexMap::new();
+    for attr_caps in ATTR_RE.captures_iter(attrs) {
+        let key = attr_caps.get(1).unwrap().as_str().to_string();
+        let value = attr_caps.get(2)
+            .or_else(|| attr_caps.get(3))
+            .or_else(|| attr_caps.get(4))
+            .map(|m| serde_json::Value::String(m.as_str().to_string()))
+            .unwrap_or(serde_json::Value::Bool(true));
+        props.insert(key, value);
+    }
+    props
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize_nested_chunk() {
+        let tokens: Vec<_> = Tokenizer::new("<div class=\"a>b\"><div>inner</div></div>\n").collect();
+
+        assert_eq!(tokens.len(), 6);
+        match &tokens[0] {
+            HtmlToken::Open { name, props, self_closing, .. } => {
+                assert_eq!(*name, "div");
+                assert_eq!(props.get("class").unwrap(), "a>b");
+                assert!(!self_closing);
+            }
+            other => panic!("Expected open tag, got {:?}", other),
+        }
+        assert_eq!(tokens[2], HtmlToken::Text("inner"));
+        assert_eq!(tokens[4], HtmlToken::Close { name: "div", raw: "</div>" });
+        assert_eq!(tokens[5], HtmlToken::Text("\n"));
+    }
+
+    #[test]
+    fn test_partial_tag_start() {
+        assert_eq!(partial_tag_start("<div>\n<Player\n"), Some(6));
+        assert_eq!(partial_tag_start("<Box title=\"a\n"), Some(0));
+        assert_eq!(partial_tag_start("<Player src=\"a.mp4\" />\n"), None);
+        assert_eq!(partial_tag_start("if (a < b) {\n"), None);
+        assert_eq!(partial_tag_start("<p>\n<!-- a <div\n"), Some(4));
+        assert_eq!(partial_tag_start("<!-- a -->\n"), None);
+    }
+
+    #[test]
+    fn test_partial_tag_resume() {
+        let chunk = "<Box title=\"a\n";
+        let partial = PartialTag::new(chunk);
+        assert_eq!(partial, PartialTag::Tag { quote: Some(b'"') });
+        assert_eq!(partial.resume("<Box title=\"a\nb > c\n", chunk.len()), Some(partial));
+        assert_eq!(partial.resume("<Box title=\"a\nb\">\n", chunk.len()), None);
+
+        let chunk = "<!-- a\n";
+        assert_eq!(PartialTag::new(chunk).resume("<!-- a\n-->\n", chunk.len()), None);
+        assert_eq!(PartialTag::new("<!--").resume("<!-->", 4), Some(PartialTag::Comment));
+    }
+}
@@ -0,0 +1,70 @@
+//! Hyperscript output: `h(tag, props, children)` call trees for Mithril,
+//! Preact without JSX and similar libraries.
+
+use serde_json::{Map, Value};
+use crate::render_jsx::{js_string, js_value};
+use crate::Node;
+
+/// Renders `nodes` as a JavaScript array of `function(tag, props,
+/// children)` calls, e.g. `[h("h1", {"id": "intro"}, ["Hello"])]`. Props
+/// are written as they are in the AST, so choose `prop_style` for the
+/// target library. Comments are left out and `raw` nodes become text.
+pub fn render_hyperscript(nodes: &[Node], function: &str) -> String {
+    let calls: Vec<String> = nodes.iter().filter_map(|node| call(node, function)).collect();
+    format!("[{}]", calls.join(", "))
+}
+
+fn call(node: &Node, function: &str) -> Option<String> {
+    match node {
+        Node::Element { tag, props, children, .. } => {
+            let props = Value::Object(props.iter().map(|(k, v)| (k.clone(), v.clone())).collect());
+            Some(format!("{}({}, {}, {})", function, js_string(tag), js_value(&props), render_hyperscript(children, function)))
+        }
+        Node::Text { content, .. } | Node::Raw { content, .. } => Some(js_string(content)),
+        Node::Fragment { children } => {
+            let calls: Vec<String> = children.iter().filter_map(|child| call(child, function)).collect();
+            (!calls.is_empty()).then(|| calls.join(", "))
+        }
+        Node::Comment { .. } => None,
+    }
+}
+
+/// The same tree as JSON for clients that call `h` themselves: an element
+/// is `[tag, props, children]` and text is a string.
+pub fn to_hyperscript(nodes: &[Node]) -> Value {
+    let mut items = Vec::with_capacity(nodes.len());
+    push_items(nodes, &mut items);
+    Value::Array(items)
+}
+
+fn push_items(nodes: &[Node], items: &mut Vec<Value>) {
+    for node in nodes {
+        match node {
+            Node::Element { tag, props, children, .. } => {
+                let props: Map<String, Value> = props.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+                items.push(Value::Array(vec![tag.clone().into(), Value::Object(props), to_hyperscript(children)]));
+            }
+            Node::Text { content, .. } | Node::Raw { content, .. } => items.push(content.clone().into()),
+            Node::Fragment { children } => push_items(children, items),
+            Node::Comment { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_hyperscript() {
+        let ast = parse("# Hi\n\nSee [docs](/d \"Docs\")<!-- c -->", &TranspileOptions::default());
+        assert_eq!(render_hyperscript(&ast, "m"),
+            "[m(\"h1\", {}, [\"Hi\"]), m(\"p\", {}, [\"See \", m(\"a\", {\"href\":\"/d\",\"title\":\"Docs\"}, [\"docs\"])])]");
+        assert_eq!(to_hyperscript(&ast), json!([
+            ["h1", {}, ["Hi"]],
+            ["p", {}, ["See ", ["a", {"href": "/d", "title": "Docs"}, ["docs"]]]],
+        ]));
+    }
+}
@@ -0,0 +1,610 @@
+//! Incremental reparsing for live editors.
+//!
+//! `IncrementalParser` keeps the source and its AST. An edit reparses only
+//! the top-level blocks around the changed range (plus one neighbour on
+//! each side, since markdown blocks can merge across a boundary, e.g. two
+//! lists) and shifts the source positions of everything after them. The
+//! reparsed region widens until the blocks at its edges come out unchanged,
+//! so edits like opening a code fence still give the same AST as `parse`.
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::ops::Range;
+use pulldown_cmark::{Event, Parser, Tag};
+use unicase::UniCase;
+use crate::elements::rewrite_elements;
+use crate::transform::rewrite_text;
+use crate::typography::apply_typography;
+use crate::whitespace::clean_whitespace;
+use crate::{assign_content_ids, assign_keys, normalize, parse, FootnoteMode, Node, PropStyle, TranspileOptions, UrlSchemes, Whitespace};
+
+/// A link reference or footnote definition. Blocks anywhere in the
+/// document can use it, so regions are parsed with the ones outside them.
+#[derive(Debug, Clone, PartialEq)]
+enum Definition {
+    Link { label: UniCase<String>, dest: String, title: Option<String> },
+    Footnote { label: UniCase<String> },
+}
+
+/// Definitions by source offset.
+type Definitions = Vec<(usize, Definition)>;
+
+/// A document kept in sync with its AST.
+///
+/// `footnotes`, `heading_ids` and `transforms` in the options are not run,
+/// since they depend on the whole document: footnote definitions stay
+/// where they were written, as with `FootnoteMode::InPlace`, and the rest
+/// can be applied to `nodes()` as needed. The input limits and
+/// `preprocess`, which would shift edit offsets, are not applied either.
+///
+/// Edits that add, remove or change a link reference or footnote
+/// definition reparse the whole document, since blocks anywhere can use it.
+pub struct IncrementalParser {
+    options: TranspileOptions,
+    source: String,
+    nodes: Vec<Node>,
+    /// Source range of each top-level node. The nodes of one HTML block
+    /// share its range, since an edit can change where the block ends.
+    blocks: Vec<Range<usize>>,
+    definitions: Definitions,
+    /// An offset and the number of lines before it, to count on from.
+    line_mark: Cell<(usize, usize)>,
+}
+
+impl IncrementalParser {
+    pub fn new(source: impl Into<String>, options: TranspileOptions) -> Self {
+        let mut parser = IncrementalParser {
+            options,
+            source: source.into(),
+            nodes: Vec::new(),
+            blocks: Vec::new(),
+            definitions: Vec::new(),
+            line_mark: Cell::new((0, 0)),
+        };
+        (parser.nodes, parser.blocks, parser.definitions) = parser.parse_region(0..parser.source.len(), 0);
+        parser.assign_keys();
+        parser
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    /// Replaces `range` of the source with `replacement` and updates the
+    /// AST. Returns the indices of the top-level nodes that were reparsed;
+    /// nodes before them are untouched and nodes after them only had their
+    /// source positions shifted.
+    ///
+    /// With `keys` set, keys outside the reparsed nodes can change too,
+    /// since they depend on position or on duplicate siblings.
+    ///
+    /// Panics if `range` is out of bounds or not on char boundaries.
+    pub fn edit(&mut self, range: Range<usize>, replacement: &str) -> Range<usize> {
+        let changed = self.reparse(range, replacement);
+        self.assign_keys();
+        changed
+    }
+
+    fn assign_keys(&mut self) {
+        if let Some(strategy) = self.options.keys {
+            assign_keys(&mut self.nodes, strategy);
+        }
+    }
+
+    fn reparse(&mut self, range: Range<usize>, replacement: &str) -> Range<usize> {
+        let removed_lines = count_lines(&self.source[range.clone()]);
+        let delta = replacement.len() as isize - range.len() as isize;
+        let line_delta = count_lines(replacement) as isize - removed_lines as isize;
+        let affected = self.affected_blocks(&range);
+        let edit_start = range.start;
+        let edit_end = range.start + replacement.len();
+        if self.line_mark.get().0 > edit_start {
+            self.line_mark.set((0, 0));
+        }
+        self.source.replace_range(range, replacement);
+
+        let Some((mut first, mut last)) = affected else {
+            return self.reparse_all(delta);
+        };
+
+        // Reparse whole lines, so columns in the region stay valid, and
+        // take in any block sharing a line with the region. An edit can
+        // also change how its surroundings parse (an unclosed code fence,
+        // or an HTML block it now joins), so the region grows until its
+        // first and last blocks come out as before.
+        let shift_offset = |offset: usize| offset.saturating_add_signed(delta).min(self.source.len());
+        let mut start = edit_start;
+        let mut end = edit_end;
+        let mut step = 1;
+        let (nodes, blocks, definitions) = loop {
+            // Definitions before the first block belong to none, so an
+            // edit among them reparses from the top.
+            start = match first {
+                0 => 0,
+                _ => line_start(&self.source, self.blocks[first].start.min(start)),
+            };
+            while first > 0 && self.blocks[first - 1].end > start {
+                first -= 1;
+                start = line_start(&self.source, self.blocks[first].start);
+            }
+            end = line_end(&self.source, shift_offset(self.blocks[last].end).max(end));
+            while last + 1 < self.blocks.len() && shift_offset(self.blocks[last + 1].start) < end {
+                last += 1;
+                end = line_end(&self.source, shift_offset(self.blocks[last].end).max(end));
+            }
+            let (nodes, blocks, definitions) = self.parse_region(start..end, delta);
+
+            let head_settled = start == 0
+                || (blocks.first().map(|b| b.start) == Some(self.blocks[first].start)
+                    && nodes.first().is_some_and(|node| self.same_block(&self.nodes[first], node)));
+            let mut old_last = self.nodes[last].clone();
+            shift(&mut old_last, delta, line_delta, &self.options);
+            let tail_settled = end == self.source.len()
+                || (blocks.last().map(|b| b.start) == Some(shift_offset(self.blocks[last].start))
+                    && nodes.last().is_some_and(|node| self.same_block(&old_last, node)));
+            if head_settled && tail_settled {
+                break (nodes, blocks, definitions);
+            }
+            if !head_settled {
+                if first == 0 {
+                    start = 0;
+                }
+                first = first.saturating_sub(step);
+            }
+            if !tail_settled {
+                if last + 1 == self.blocks.len() {
+                    end = self.source.len();
+                }
+                last = (last + step).min(self.blocks.len() - 1);
+            }
+            step *= 2;
+        };
+
+        // Unchanged definitions in the region leave the rest of the
+        // document as it was.
+        let old_end = end.saturating_add_signed(-delta);
+        let old_definitions = self.definitions.iter().filter(|(offset, _)| (start..old_end).contains(offset));
+        if !old_definitions.map(|(_, definition)| definition).eq(definitions.iter().map(|(_, definition)| definition)) {
+            return self.reparse_all(delta);
+        }
+        let after = self.definitions.partition_point(|&(offset, _)| offset < old_end);
+        let mut tail_definitions = self.definitions.split_off(after);
+        for (offset, _) in &mut tail_definitions {
+            *offset = offset.saturating_add_signed(delta);
+        }
+        self.definitions.retain(|&(offset, _)| offset < start);
+        self.definitions.extend(definitions);
+        self.definitions.append(&mut tail_definitions);
+
+        let mut tail_nodes = self.nodes.split_off(last + 1);
+        let mut tail_blocks = self.blocks.split_off(last + 1);
+        for (node, block) in tail_nodes.iter_mut().zip(&mut tail_blocks) {
+            shift(node, delta, line_delta, &self.options);
+            block.start = block.start.saturating_add_signed(delta);
+            block.end = block.end.saturating_add_signed(delta);
+        }
+        self.nodes.truncate(first);
+        self.blocks.truncate(first);
+        let changed = first..first + nodes.len();
+        self.nodes.extend(nodes);
+        self.blocks.extend(blocks);
+        self.nodes.append(&mut tail_nodes);
+        self.blocks.append(&mut tail_blocks);
+        changed
+    }
+
+    fn reparse_all(&mut self, delta: isize) -> Range<usize> {
+        self.line_mark.set((0, 0));
+        (self.nodes, self.blocks, self.definitions) = self.parse_region(0..self.source.len(), delta);
+        0..self.nodes.len()
+    }
+
+    /// First and last block to reparse for an edit of `range` (in the
+    /// source before the edit), or `None` if the whole document should be
+    /// reparsed.
+    fn affected_blocks(&self, range: &Range<usize>) -> Option<(usize, usize)> {
+        if self.blocks.is_empty() {
+            return None;
+        }
+        let first = Some(self.blocks.partition_point(|b| b.end <= range.start)).filter(|&i| i < self.blocks.len());
+        let last = self.blocks.partition_point(|b| b.start <= range.end).checked_sub(1);
+        let (first, last) = match (first, last) {
+            (Some(first), Some(last)) if first <= last => (first, last),
+            // Between two blocks, or before the first / after the last.
+            (Some(next), _) => (next, next.saturating_sub(1)),
+            (None, _) => (self.blocks.len() - 1, self.blocks.len() - 1),
+        };
+        let first = first.min(last).saturating_sub(1);
+        let last = (last.max(first) + 1).min(self.blocks.len() - 1);
+        Some((first, last))
+    }
+
+    /// Whether a reparsed node equals the old one, apart from the keys
+    /// `edit` assigns afterwards.
+    fn same_block(&self, old: &Node, new: &Node) -> bool {
+        if self.options.keys.is_none() {
+            return old == new;
+        }
+        let mut old = old.clone();
+        strip_keys(&mut old);
+        &old == new
+    }
+
+    /// Parses `self.source[range]`, returning top-level nodes positioned
+    /// in the whole source along with their ranges, and the definitions in
+    /// the region. `delta` is how far the edit moved the source after it,
+    /// to tell which of the old definitions lie outside the region.
+    fn parse_region(&self, range: Range<usize>, delta: isize) -> (Vec<Node>, Vec<Range<usize>>, Definitions) {
+        let options = TranspileOptions {
+            source_spans: true,
+            normalize: false,
+            whitespace: Whitespace::default(),
+            content_ids: false,
+            keys: None,
+            transforms: Vec::new(),
+            preprocess: None,
+            text_hook: None,
+            typography: HashMap::new(),
+            heading_ids: false,
+            footnotes: FootnoteMode::InPlace,
+            max_input_bytes: None,
+            timeout_ms: None,
+            tag_map: HashMap::new(),
+            class_map: HashMap::new(),
+            prop_style: PropStyle::Preserve,
+            base_url: None,
+            md_links_as_routes: false,
+            link_rewriter: None,
+            image_rewriter: None,
+            id_prefix: None,
+            url_schemes: UrlSchemes::any(),
+            ..self.options.clone()
+        };
+        let text = &self.source[range.clone()];
+        let (definitions, html_blocks) = scan(text, range.start, &options);
+        let old_range = range.start..range.end.saturating_add_signed(-delta);
+        let prefix = self.definitions_prefix(&old_range, &definitions);
+        let lines_before = self.lines_before(range.start) as isize - count_lines(&prefix) as isize;
+
+        let mut nodes = parse(&(prefix.clone() + text), &options);
+        nodes.retain(|node| span_start(node).is_none_or(|start| start >= prefix.len()));
+        for node in &mut nodes {
+            shift(node, range.start as isize - prefix.len() as isize, lines_before, &options);
+        }
+        if self.options.normalize {
+            normalize(&mut nodes);
+        }
+        clean_whitespace(&mut nodes, &self.options.whitespace);
+        apply_typography(&mut nodes, &self.options);
+        rewrite_text(&mut nodes, self.options.text_hook.as_deref());
+        rewrite_elements(&mut nodes, &self.options);
+
+        // Ranges are taken after normalizing, which can merge or drop
+        // blocks. A block runs up to the next one, since an element left
+        // open by inline HTML has a span covering only its opening tag.
+        let mut starts: Vec<usize> = Vec::with_capacity(nodes.len());
+        for node in &nodes {
+            let start = span_start(node).unwrap_or_else(|| starts.last().copied().unwrap_or(range.start));
+            let html_block = html_blocks.iter().find(|block| block.contains(&start));
+            starts.push(html_block.map_or(start, |block| block.start));
+        }
+        let mut blocks: Vec<Range<usize>> = Vec::with_capacity(starts.len());
+        for &start in starts.iter().rev() {
+            let end = match blocks.last() {
+                Some(next) if next.start == start => next.end,
+                Some(next) => next.start,
+                None => range.end,
+            };
+            blocks.push(start..end);
+        }
+        blocks.reverse();
+
+        if !self.options.source_spans {
+            for node in &mut nodes {
+                clear_spans(node);
+            }
+        }
+        if self.options.content_ids {
+            assign_content_ids(&mut nodes);
+        }
+        (nodes, blocks, definitions)
+    }
+
+    /// Markdown defining what the definitions outside `old_range` (in the
+    /// source before the edit) define, to parse the region after. As in
+    /// the whole document, the first definition of a link label wins;
+    /// footnotes only need to exist.
+    fn definitions_prefix(&self, old_range: &Range<usize>, found: &[(usize, Definition)]) -> String {
+        let mut links = String::new();
+        let mut footnotes = String::new();
+        let mut seen = Vec::new();
+        for (offset, definition) in &self.definitions {
+            if old_range.contains(offset) || seen.contains(&definition_label(definition)) {
+                continue;
+            }
+            seen.push(definition_label(definition));
+            let in_region = found.iter().any(|(_, found)| definition_label(found) == definition_label(definition));
+            match definition {
+                Definition::Link { .. } if in_region && *offset >= old_range.end => {}
+                Definition::Link { label, dest, title } => {
+                    links.push_str(&format!("[{}]: <{}>", label, escape_destination(dest)));
+                    if let Some(title) = title {
+                        links.push_str(&format!(" \"{}\"", escape_destination(title)));
+                    }
+                    links.push('\n');
+                }
+                Definition::Footnote { .. } if in_region => {}
+                Definition::Footnote { label } => footnotes.push_str(&format!("[^{}]: x\n\n", label)),
+            }
+        }
+        if !links.is_empty() {
+            links.push('\n');
+        }
+        if !footnotes.is_empty() {
+            // A paragraph ends the last footnote, so the region's first
+            // line can't continue it.
+            footnotes.push_str(".\n\n");
+        }
+        links + &footnotes
+    }
+
+    /// Lines before `offset`, counted on from the last offset asked for
+    /// unless that lies beyond it.
+    fn lines_before(&self, offset: usize) -> usize {
+        let (mut from, mut lines) = self.line_mark.get();
+        if from > offset {
+            (from, lines) = (0, 0);
+        }
+        lines += count_lines(&self.source[from..offset]);
+        self.line_mark.set((offset, lines));
+        lines
+    }
+}
+
+/// The definitions and HTML block ranges in `text`, with offsets moved
+/// on by `offset`.
+fn scan(text: &str, offset: usize, options: &TranspileOptions) -> (Definitions, Vec<Range<usize>>) {
+    let mut definitions = Vec::new();
+    let mut html_blocks = Vec::new();
+    let mut events = Parser::new_ext(text, options.parser_options()).into_offset_iter();
+    for (event, range) in events.by_ref() {
+        match event {
+            Event::Start(Tag::HtmlBlock) => html_blocks.push(offset + range.start..offset + range.end),
+            Event::Start(Tag::FootnoteDefinition(label)) => {
+                definitions.push((offset + range.start, Definition::Footnote { label: UniCase::new(label.to_string()) }));
+            }
+            _ => {}
+        }
+    }
+    for (label, link) in events.reference_definitions().iter() {
+        definitions.push((offset + link.span.start, Definition::Link {
+            label: UniCase::new(label.to_string()),
+            dest: link.dest.to_string(),
+            title: link.title.as_ref().map(|title| title.to_string()),
+        }));
+    }
+    definitions.sort_by_key(|&(offset, _)| offset);
+    (definitions, html_blocks)
+}
+
+/// A definition's label, with footnotes told apart from links.
+fn definition_label(definition: &Definition) -> (bool, &UniCase<String>) {
+    match definition {
+        Definition::Link { label, .. } => (false, label),
+        Definition::Footnote { label } => (true, label),
+    }
+}
+
+/// `text` as a link destination or title: escaped so it reads back as
+/// is, with line breaks as character references.
+fn escape_destination(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '<' | '>' | '"' | '&' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\n' => out.push_str("&#10;"),
+            '\r' => out.push_str("&#13;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn span_start(node: &Node) -> Option<usize> {
+    match node {
+        Node::Element { span, .. } | Node::Text { span, .. } | Node::Comment { span, .. } | Node::Raw { span, .. } => span.map(|span| span.start),
+        Node::Fragment { .. } => None,
+    }
+}
+
+fn count_lines(text: &str) -> usize {
+    text.bytes().filter(|&b| b == b'\n').count()
+}
+
+fn line_start(source: &str, offset: usize) -> usize {
+    source[..offset].rfind('\n').map_or(0, |i| i + 1)
+}
+
+/// End of the line containing `offset`, or `offset` itself if it already
+/// is the start of a line.
+fn line_end(source: &str, offset: usize) -> usize {
+    let offset = offset.min(source.len());
+    if offset > 0 && source.as_bytes()[offset - 1] == b'\n' {
+        return offset;
+    }
+    source[offset..].find('\n').map_or(source.len(), |i| offset + i + 1)
+}
+
+/// Moves the source positions of `node` and its descendants.
+fn shift(node: &mut Node, bytes: isize, lines: isize, options: &TranspileOptions) {
+    let span = match node {
+        Node::Element { span, props, .. } => {
+            if let (true, Some(serde_json::Value::String(pos))) = (options.sourcepos, props.get_mut("data-sourcepos")) {
+                *pos = shift_sourcepos(pos, lines);
+            }
+            span
+        }
+        Node::Text { span, .. } | Node::Comment { span, .. } | Node::Raw { span, .. } => span,
+        Node::Fragment { .. } => &mut None,
+    };
+    if let Some(span) = span {
+        span.start = (span.start as isize + bytes) as usize;
+        span.end = (span.end as isize + bytes) as usize;
+        span.line = (span.line as isize + lines) as usize;
+    }
+    if let Some(children) = node.children_mut() {
+        for child in children {
+            shift(child, bytes, lines, options);
+        }
+    }
+}
+
+/// Adds `lines` to both line numbers of a `l:c-l:c` position.
+fn shift_sourcepos(pos: &str, lines: isize) -> String {
+    let shift_one = |part: &str| match part.split_once(':') {
+        Some((line, column)) => match line.parse::<isize>() {
+            Ok(line) => format!("{}:{}", line + lines, column),
+            Err(_) => part.to_string(),
+        },
+        None => part.to_string(),
+    };
+    match pos.split_once('-') {
+        Some((start, end)) => format!("{}-{}", shift_one(start), shift_one(end)),
+        None => pos.to_string(),
+    }
+}
+
+fn strip_keys(node: &mut Node) {
+    if let Node::Element { props, .. } = node {
+        props.shift_remove("key");
+    }
+    if let Some(children) = node.children_mut() {
+        children.iter_mut().for_each(strip_keys);
+    }
+}
+
+fn clear_spans(node: &mut Node) {
+    match node {
+        Node::Element { span, .. } | Node::Text { span, .. } | Node::Comment { span, .. } | Node::Raw { span, .. } => {
+            *span = None;
+        }
+        Node::Fragment { .. } => {}
+    }
+    if let Some(children) = node.children_mut() {
+        children.iter_mut().for_each(clear_spans);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::KeyStrategy;
+
+    fn assert_edit(source: &str, range: Range<usize>, replacement: &str, options: TranspileOptions) -> Range<usize> {
+        let mut parser = IncrementalParser::new(source, options.clone());
+        let changed = parser.edit(range.clone(), replacement);
+
+        let mut expected = source.to_string();
+        expected.replace_range(range, replacement);
+        assert_eq!(parser.source(), expected);
+        assert_eq!(parser.nodes(), parse(&expected, &options), "after edit: {:?}", expected);
+        changed
+    }
+
+    #[test]
+    fn test_incremental_edit() {
+        let source = "# Title\n\nFirst paragraph.\n\nSecond *paragraph*.\n\n- a\n- b\n\nLast.\n";
+        let options = TranspileOptions { source_spans: true, sourcepos: true, ..Default::default() };
+
+        // The split-off text joins the next paragraph, so the list after
+        // it is reparsed too.
+        let changed = assert_edit(source, 17..26, "para\n\nsplit", options.clone());
+        assert_eq!(changed, 0..4);
+        assert_edit(source, 0..0, "Intro\n\n", options.clone());
+        assert_edit(source, source.len()..source.len(), "\n- c\n", options.clone());
+        assert_edit(source, 49..54, "", options.clone());
+        assert_edit(source, 27..47, "", TranspileOptions::default());
+        assert_edit(source, 27..47, "x", TranspileOptions { sourcepos: true, content_ids: true, ..Default::default() });
+        assert_edit(source, 9..9, "- z\n\n", TranspileOptions { keys: Some(KeyStrategy::IndexPath), ..Default::default() });
+
+        // Normalizing drops the empty paragraph the closing tags leave.
+        let options = TranspileOptions { allowed_tags: vec!["a".into(), "table".into()], normalize: true, ..Default::default() };
+        assert_edit("---\n</a></table>\r\n", 6..17, "~~", options);
+    }
+
+    #[test]
+    fn test_incremental_edit_far_from_change() {
+        let source = "A\n\nB\n\nC\n\nD\n\nE\n\nF\n";
+        let mut parser = IncrementalParser::new(source, TranspileOptions::default());
+
+        assert_eq!(parser.edit(9..10, "Dee"), 2..5);
+        assert_eq!(parser.nodes(), parse("A\n\nB\n\nC\n\nDee\n\nE\n\nF\n", &TranspileOptions::default()));
+    }
+
+    #[test]
+    fn test_incremental_edit_reaching_later_blocks() {
+        let source = "A\n\nB\n\nC\n\n<div>\n\nD\n\nE\n";
+        let options = TranspileOptions { source_spans: true, allowed_tags: vec!["div".to_string()], ..Default::default() };
+
+        // An unclosed fence swallows the rest of the document.
+        assert_eq!(assert_edit(source, 3..3, "```\n", options.clone()), 0..2);
+        // An HTML block now starts on the line before "B".
+        assert_edit(source, 2..2, "</div>\n", options.clone());
+        assert_edit(source, 9..9, "x", options);
+    }
+
+    #[test]
+    fn test_incremental_definitions_reparse_all() {
+        let source = "See [x].\n\nOther.\n\nMore.\n";
+        let changed = assert_edit(source, source.len()..source.len(), "\n[x]: /url\n", TranspileOptions::default());
+        assert_eq!(changed, 0..3);
+
+        // Blocks away from the definitions still reparse on their own.
+        let source = "See [x][^n].\n\nOther.\n\nMore [x].\n\n[x]: /url\n\n[^n]: Note.\n";
+        assert_eq!(assert_edit(source, 22..26, "Less", TranspileOptions::default()), 1..4);
+        assert_eq!(assert_edit(source, 38..42, "/uri", TranspileOptions::default()), 0..4);
+    }
+
+    #[test]
+    fn test_incremental_edit_matches_parse() {
+        // The end of the HTML block moves, so the line after it is no
+        // longer part of it.
+        assert_edit("</div>\n<!-- c\n-->\n-->\n", 0..2, "", TranspileOptions::default());
+
+        let pieces = [
+            "</div>\n", "<div>\n", "<!-- c\n", "-->\n", "\n", "x", "[x]", "[x]: /u\n", "[X]: /v 't'\n", "[^1]",
+            "[^1]: n\n", "    i\n", "- a\n", "> q\n", "```\n", "# h\n", "[y][x]", " ", "<b>", "</b>",
+        ];
+        let options = [
+            TranspileOptions::default(),
+            TranspileOptions { source_spans: true, sourcepos: true, normalize: true, allowed_tags: vec!["div".into(), "b".into()], ..Default::default() },
+        ];
+        let mut seed = 7u64;
+        let mut random = |n: usize| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed % n as u64) as usize
+        };
+        for i in 0..3000 {
+            let options = &options[i % 2];
+            let mut source: String = (0..random(10)).map(|_| pieces[random(pieces.len())]).collect();
+            let mut parser = IncrementalParser::new(source.clone(), options.clone());
+            for _ in 0..3 {
+                let start = random(source.len() + 1);
+                let range = start..start + random((source.len() - start).min(12) + 1);
+                let replacement = pieces[random(pieces.len())];
+                parser.edit(range.clone(), replacement);
+                source.replace_range(range, replacement);
+                assert_eq!(parser.nodes(), parse(&source, options), "after edit: {:?}", source);
+            }
+        }
+    }
+}
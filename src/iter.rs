@@ -0,0 +1,218 @@
+//! Depth-first iteration and search over the AST.
+
+use std::slice;
+use indexmap::IndexMap;
+use crate::{Node, Span};
+
+/// Pre-order iterator over nodes and all their descendants; see `iter`.
+pub struct Iter<'a> {
+    stack: Vec<slice::Iter<'a, Node>>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a Node;
+
+    fn next(&mut self) -> Option<&'a Node> {
+        loop {
+            let top = self.stack.last_mut()?;
+            match top.next() {
+                Some(node) => {
+                    if let Some(children) = node.children() {
+                        self.stack.push(children.iter());
+                    }
+                    return Some(node);
+                }
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+/// Mutable access to one node's own fields, yielded by `iter_mut`.
+///
+/// Children are left out: the iterator goes on to yield them itself, and
+/// handing out the parent's child list as well would alias them.
+#[derive(Debug)]
+pub enum NodeMut<'a> {
+    Element {
+        tag: &'a mut String,
+        props: &'a mut IndexMap<String, serde_json::Value>,
+        span: &'a mut Option<Span>,
+        content_id: &'a mut Option<String>,
+    },
+    Text {
+        content: &'a mut String,
+        span: &'a mut Option<Span>,
+    },
+    Fragment,
+    Comment {
+        content: &'a mut String,
+        span: &'a mut Option<Span>,
+    },
+    Raw {
+        content: &'a mut String,
+        format: &'a mut String,
+        span: &'a mut Option<Span>,
+    },
+}
+
+/// Pre-order iterator yielding `NodeMut`s; see `iter_mut`.
+pub struct IterMut<'a> {
+    stack: Vec<slice::IterMut<'a, Node>>,
+}
+
+impl<'a> Iterator for IterMut<'a> {
+    type Item = NodeMut<'a>;
+
+    fn next(&mut self) -> Option<NodeMut<'a>> {
+        loop {
+            let top = self.stack.last_mut()?;
+            let Some(node) = top.next() else {
+                self.stack.pop();
+                continue;
+            };
+            let (item, children) = match node {
+                Node::Element { tag, props, children, span, content_id } => {
+                    (NodeMut::Element { tag, props, span, content_id }, Some(children))
+                }
+                Node::Text { content, span } => (NodeMut::Text { content, span }, None),
+                Node::Fragment { children } => (NodeMut::Fragment, Some(children)),
+                Node::Comment { content, span } => (NodeMut::Comment { content, span }, None),
+                Node::Raw { content, format, span } => (NodeMut::Raw { content, format, span }, None),
+            };
+            if let Some(children) = children {
+                self.stack.push(children.iter_mut());
+            }
+            return Some(item);
+        }
+    }
+}
+
+/// A text node with the elements enclosing it; see `texts`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextContext<'a> {
+    pub content: &'a str,
+    pub span: Option<Span>,
+    /// Enclosing elements, outermost first. Fragments are skipped.
+    pub ancestors: Vec<&'a Node>,
+}
+
+impl TextContext<'_> {
+    /// Whether the text is inside an element with tag `tag`.
+    pub fn inside(&self, tag: &str) -> bool {
+        self.ancestors.iter().any(|node| node.tag() == Some(tag))
+    }
+}
+
+/// Iterator over text nodes and their ancestors; see `texts`.
+pub struct Texts<'a> {
+    /// Each level's remaining siblings, with the element they belong to.
+    stack: Vec<(slice::Iter<'a, Node>, Option<&'a Node>)>,
+}
+
+impl<'a> Iterator for Texts<'a> {
+    type Item = TextContext<'a>;
+
+    fn next(&mut self) -> Option<TextContext<'a>> {
+        loop {
+            let (top, _) = self.stack.last_mut()?;
+            let Some(node) = top.next() else {
+                self.stack.pop();
+                continue;
+            };
+            match node {
+                Node::Text { content, span } => {
+                    let ancestors = self.stack.iter().filter_map(|(_, parent)| *parent).collect();
+                    return Some(TextContext { content, span: *span, ancestors });
+                }
+                Node::Element { children, .. } => self.stack.push((children.iter(), Some(node))),
+                Node::Fragment { children } => self.stack.push((children.iter(), None)),
+                Node::Comment { .. } | Node::Raw { .. } => {}
+            }
+        }
+    }
+}
+
+/// Iterates over the text in `nodes` in document order, with the path of
+/// elements around each piece, for highlighting, annotation or filtering
+/// that depends on context (skipping code, say).
+pub fn texts(nodes: &[Node]) -> Texts<'_> {
+    Texts { stack: vec![(nodes.iter(), None)] }
+}
+
+/// Iterates over `nodes` and all their descendants, depth-first, each
+/// node before its children.
+pub fn iter(nodes: &[Node]) -> Iter<'_> {
+    Iter { stack: vec![nodes.iter()] }
+}
+
+/// Like `iter`, with mutable access to each node's fields other than its
+/// children.
+pub fn iter_mut(nodes: &mut [Node]) -> IterMut<'_> {
+    IterMut { stack: vec![nodes.iter_mut()] }
+}
+
+/// The first node in document order matching `predicate`.
+pub fn find(nodes: &[Node], mut predicate: impl FnMut(&Node) -> bool) -> Option<&Node> {
+    iter(nodes).find(|node| predicate(node))
+}
+
+/// All nodes matching `predicate`, in document order.
+pub fn find_all(nodes: &[Node], mut predicate: impl FnMut(&Node) -> bool) -> Vec<&Node> {
+    iter(nodes).filter(|node| predicate(node)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_iter() {
+        let ast = parse("# A *b*\n\n- c", &TranspileOptions::default());
+
+        let tags: Vec<_> = iter(&ast).map(|node| node.tag().unwrap_or("#")).collect();
+        assert_eq!(tags, ["h1", "#", "em", "#", "ul", "li", "#"]);
+        assert_eq!(find(&ast, |node| node.tag() == Some("li")), Some(&ast[1].children().unwrap()[0]));
+        assert_eq!(find_all(&ast, |node| matches!(node, Node::Text { .. })).len(), 3);
+        assert!(find(&[], |_| true).is_none());
+    }
+
+    #[test]
+    fn test_texts() {
+        let ast = parse("a *b [c](/x)*\n\n> `d`", &TranspileOptions::default());
+        let texts: Vec<_> = texts(&ast).collect();
+
+        let summary: Vec<_> = texts.iter()
+            .map(|text| (text.content, text.ancestors.iter().map(|node| node.tag().unwrap()).collect::<Vec<_>>()))
+            .collect();
+        assert_eq!(summary, [
+            ("a ", vec!["p"]),
+            ("b ", vec!["p", "em"]),
+            ("c", vec!["p", "em", "a"]),
+            ("d", vec!["blockquote", "p", "code"]),
+        ]);
+        assert!(texts[2].inside("a") && !texts[0].inside("a"));
+        assert_eq!(texts[2].ancestors[2].children().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_iter_mut() {
+        let mut ast = parse("[a](/x) and [b](/y)", &TranspileOptions::default());
+
+        for node in iter_mut(&mut ast) {
+            match node {
+                NodeMut::Element { tag, props, .. } if tag == "a" => {
+                    props.insert("target".to_string(), "_blank".into());
+                }
+                NodeMut::Text { content, .. } => content.make_ascii_uppercase(),
+                _ => {}
+            }
+        }
+        let targets = find_all(&ast, |node| matches!(node, Node::Element { props, .. } if props.contains_key("target")));
+        assert_eq!(targets.len(), 2);
+        assert_eq!(crate::text_content(&ast), "A AND B");
+    }
+}
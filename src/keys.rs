@@ -0,0 +1,105 @@
+//! React `key` props for every element, so consumers mapping `children`
+//! arrays get stable reconciliation without key warnings.
+
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::content_id::for_each_hash;
+use crate::Node;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+pub enum KeyStrategy {
+    /// The element's position, as child indices from the root joined with
+    /// dots (`"2.0.1"`). Cheap, but inserting a node rekeys its later
+    /// siblings.
+    IndexPath,
+    /// The element's content hash (see `assign_content_ids`), with `-1`,
+    /// `-2`, ... appended to repeats among siblings. Survives reordering.
+    ContentHash,
+}
+
+/// Sets a `key` prop on every element in `nodes`, replacing any earlier
+/// keys. Keys are unique among siblings.
+pub fn assign_keys(nodes: &mut [Node], strategy: KeyStrategy) {
+    match strategy {
+        KeyStrategy::IndexPath => index_keys(nodes, ""),
+        KeyStrategy::ContentHash => {
+            for_each_hash(nodes, &mut |node, hash| {
+                if let Node::Element { props, .. } = node {
+                    props.insert("key".to_string(), Value::String(hash.to_string()));
+                }
+            });
+            dedupe_keys(nodes);
+        }
+    }
+}
+
+fn index_keys(nodes: &mut [Node], prefix: &str) {
+    for (i, node) in nodes.iter_mut().enumerate() {
+        let path = format!("{}{}", prefix, i);
+        if let Node::Element { props, .. } = node {
+            props.insert("key".to_string(), Value::String(path.clone()));
+        }
+        if let Some(children) = node.children_mut() {
+            index_keys(children, &format!("{}.", path));
+        }
+    }
+}
+
+fn dedupe_keys(nodes: &mut [Node]) {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for node in nodes {
+        if let Node::Element { props, .. } = node {
+            if let Some(Value::String(key)) = props.get_mut("key") {
+                let count = seen.entry(key.clone()).or_insert(0);
+                if *count > 0 {
+                    *key = format!("{}-{}", key, count);
+                }
+                *count += 1;
+            }
+        }
+        if let Some(children) = node.children_mut() {
+            dedupe_keys(children);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, query_all, TranspileOptions};
+
+    fn keys(nodes: &[Node], selector: &str) -> Vec<String> {
+        query_all(nodes, selector).unwrap().into_iter()
+            .filter_map(|node| match node {
+                Node::Element { props, .. } => props.get("key").and_then(|v| v.as_str()).map(String::from),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_index_path_keys() {
+        let options = TranspileOptions { keys: Some(KeyStrategy::IndexPath), ..Default::default() };
+        let ast = parse("# Hi\n\n- a\n- *b*", &options);
+
+        assert_eq!(keys(&ast, "h1"), ["0"]);
+        assert_eq!(keys(&ast, "li"), ["1.0", "1.1"]);
+        assert_eq!(keys(&ast, "em"), ["1.1.0"]);
+    }
+
+    #[test]
+    fn test_content_hash_keys() {
+        let options = TranspileOptions { keys: Some(KeyStrategy::ContentHash), ..Default::default() };
+        let ast = parse("- a\n- a\n- b\n- a", &options);
+        let items = keys(&ast, "li");
+
+        assert_eq!(items[1], format!("{}-1", items[0]));
+        assert_eq!(items[3], format!("{}-2", items[0]));
+        assert_ne!(items[2], items[0]);
+        // A node keeps its key when an earlier sibling is inserted.
+        let moved = parse("- c\n- b", &options);
+        assert_eq!(keys(&moved, "li")[1], items[2]);
+    }
+}
@@ -0,0 +1,291 @@
+//! Lexical serialized editor state output.
+
+use serde_json::{json, Map, Value};
+use crate::markdown::BLOCK_TAGS;
+use crate::{text_content, Node};
+
+/// Lexical's text format bits by tag.
+const FORMATS: &[(&str, u32)] = &[
+    ("strong", 1),
+    ("b", 1),
+    ("em", 2),
+    ("i", 2),
+    ("del", 4),
+    ("s", 4),
+    ("u", 8),
+    ("code", 16),
+    ("sub", 32),
+    ("sup", 64),
+    ("mark", 128),
+];
+
+/// Converts `nodes` to Lexical's serialized editor state
+/// (`{"root": {"type": "root", "children": [...]}}`), for
+/// `editor.parseEditorState`. Uses the core, rich text, list, link, code
+/// and table node types; text formatting becomes the `format` bitmask.
+/// Core Lexical has no image node, so images become their `alt` text.
+/// Elements without a Lexical equivalent are unwrapped, and comments and
+/// raw content dropped.
+pub fn to_lexical(nodes: &[Node]) -> Value {
+    json!({ "root": element("root", Map::new(), blocks(nodes)) })
+}
+
+fn is_block(node: &Node) -> bool {
+    match node {
+        Node::Element { tag, children, .. } => BLOCK_TAGS.contains(&tag.as_str()) || children.iter().any(is_block),
+        Node::Fragment { children } => children.iter().any(is_block),
+        _ => false,
+    }
+}
+
+/// An element node with Lexical's common element fields.
+fn element(kind: &str, mut fields: Map<String, Value>, children: Vec<Value>) -> Value {
+    fields.insert("children".into(), children.into());
+    fields.insert("direction".into(), Value::Null);
+    fields.insert("format".into(), "".into());
+    fields.insert("indent".into(), 0.into());
+    fields.insert("type".into(), kind.into());
+    fields.insert("version".into(), 1.into());
+    Value::Object(fields)
+}
+
+fn fields<const N: usize>(pairs: [(&str, Value); N]) -> Map<String, Value> {
+    pairs.into_iter().map(|(key, value)| (key.to_string(), value)).collect()
+}
+
+fn text(content: &str, format: u32) -> Value {
+    json!({ "detail": 0, "format": format, "mode": "normal", "style": "", "text": content, "type": "text", "version": 1 })
+}
+
+fn linebreak() -> Value {
+    json!({ "type": "linebreak", "version": 1 })
+}
+
+fn blocks(nodes: &[Node]) -> Vec<Value> {
+    let mut blocks = Vec::new();
+    let mut run = Vec::new();
+    for node in nodes {
+        if is_block(node) {
+            flush(&mut run, &mut blocks);
+            block(node, &mut blocks);
+        } else {
+            inline(node, 0, &mut run);
+        }
+    }
+    flush(&mut run, &mut blocks);
+    blocks
+}
+
+fn flush(run: &mut Vec<Value>, blocks: &mut Vec<Value>) {
+    if run.iter().all(|node| node["type"] == "text" && node["text"].as_str().is_some_and(|text| text.trim().is_empty())) {
+        run.clear();
+        return;
+    }
+    blocks.push(element("paragraph", Map::new(), std::mem::take(run)));
+}
+
+fn block(node: &Node, out: &mut Vec<Value>) {
+    let Node::Element { tag, props, children, .. } = node else {
+        if let Node::Fragment { children } = node {
+            out.extend(blocks(children));
+        }
+        return;
+    };
+    out.push(match tag.as_str() {
+        "p" => element("paragraph", Map::new(), inlines(children)),
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => element("heading", fields([("tag", tag.as_str().into())]), inlines(children)),
+        "blockquote" => {
+            // A quote holds inline content; its paragraphs are joined by
+            // line breaks.
+            element("quote", Map::new(), joined(children))
+        }
+        "ul" | "ol" => list(tag, props, children),
+        "pre" => {
+            let language = match children.as_slice() {
+                [Node::Element { tag, props, .. }] if tag == "code" => {
+                    let class = props.get("className").or_else(|| props.get("class")).and_then(Value::as_str).unwrap_or_default();
+                    class.split_whitespace().find_map(|class| class.strip_prefix("language-")).map(str::to_string)
+                }
+                _ => None,
+            };
+            let code = text_content(children);
+            let mut lines = Vec::new();
+            for (i, line) in code.strip_suffix('\n').unwrap_or(&code).split('\n').enumerate() {
+                if i > 0 {
+                    lines.push(linebreak());
+                }
+                if !line.is_empty() {
+                    lines.push(text(line, 0));
+                }
+            }
+            element("code", fields([("language", language.into())]), lines)
+        }
+        "hr" => json!({ "type": "horizontalrule", "version": 1 }),
+        "table" => {
+            let mut rows = Vec::new();
+            table_rows(children, &mut rows);
+            element("table", Map::new(), rows)
+        }
+        _ => return out.extend(blocks(children)),
+    });
+}
+
+/// Inline content of block children, with a line break between blocks.
+fn joined(children: &[Node]) -> Vec<Value> {
+    let mut content = Vec::new();
+    for child in children {
+        let mut inner = Vec::new();
+        match child {
+            Node::Element { tag, children, .. } if is_block(child) && !matches!(tag.as_str(), "ul" | "ol") => inner = joined(children),
+            _ if is_block(child) => {}
+            _ => inline(child, 0, &mut inner),
+        }
+        if inner.is_empty() || inner.iter().all(|node| node["text"].as_str().is_some_and(|t| t.trim().is_empty())) {
+            continue;
+        }
+        if !content.is_empty() {
+            content.push(linebreak());
+        }
+        content.extend(inner);
+    }
+    content
+}
+
+/// A list; Lexical nests a list in a `listitem` of its own, after the item
+/// it belongs to.
+fn list(tag: &str, props: &indexmap::IndexMap<String, Value>, children: &[Node]) -> Value {
+    let start = props.get("start").and_then(Value::as_u64).unwrap_or(1);
+    let mut items = Vec::new();
+    let mut checklist = false;
+    let mut value = start;
+    for child in children {
+        let Node::Element { tag, children, .. } = child else { continue };
+        if tag != "li" {
+            continue;
+        }
+        let checked = children.iter().chain(children.iter().filter_map(Node::children).flatten()).find_map(|node| match node {
+            Node::Element { tag, props, .. } if tag == "input" => Some(props.get("checked").and_then(Value::as_bool).unwrap_or(false)),
+            _ => None,
+        });
+        checklist |= checked.is_some();
+        let (lists, content): (Vec<&Node>, Vec<&Node>) =
+            children.iter().partition(|node| matches!(node, Node::Element { tag, .. } if tag == "ul" || tag == "ol"));
+        let content: Vec<Node> = content.into_iter().cloned().collect();
+        let mut item = fields([("value", value.into())]);
+        if let Some(checked) = checked {
+            item.insert("checked".into(), checked.into());
+        }
+        items.push(element("listitem", item, joined(&content)));
+        value += 1;
+        for nested in lists {
+            if let Node::Element { tag, props, children, .. } = nested {
+                items.push(element("listitem", fields([("value", value.into())]), vec![list(tag, props, children)]));
+                value += 1;
+            }
+        }
+    }
+    let list_type = match (checklist, tag) {
+        (true, _) => "check",
+        (false, "ol") => "number",
+        (false, _) => "bullet",
+    };
+    element("list", fields([("listType", list_type.into()), ("start", start.into()), ("tag", tag.into())]), items)
+}
+
+fn table_rows(nodes: &[Node], rows: &mut Vec<Value>) {
+    for node in nodes {
+        match node {
+            Node::Element { tag, children, .. } if tag == "tr" => {
+                let cells = children.iter().filter_map(|cell| match cell {
+                    Node::Element { tag, children, .. } if tag == "td" || tag == "th" => {
+                        let header = if tag == "th" { 1 } else { 0 };
+                        let fields = fields([("colSpan", 1.into()), ("headerState", header.into()), ("backgroundColor", Value::Null)]);
+                        Some(element("tablecell", fields, blocks(children)))
+                    }
+                    _ => None,
+                });
+                rows.push(element("tablerow", Map::new(), cells.collect()));
+            }
+            node => {
+                if let Some(children) = node.children() {
+                    table_rows(children, rows);
+                }
+            }
+        }
+    }
+}
+
+fn inlines(nodes: &[Node]) -> Vec<Value> {
+    let mut inlines = Vec::new();
+    for node in nodes {
+        inline(node, 0, &mut inlines);
+    }
+    inlines
+}
+
+fn inline(node: &Node, format: u32, out: &mut Vec<Value>) {
+    match node {
+        Node::Text { content, .. } if !content.is_empty() => out.push(text(content, format)),
+        Node::Element { tag, props, children, .. } => {
+            let format = format | FORMATS.iter().find(|&&(t, _)| t == tag).map_or(0, |&(_, bit)| bit);
+            match tag.as_str() {
+                "br" => out.push(linebreak()),
+                "img" => {
+                    if let Some(alt) = props.get("alt").and_then(Value::as_str).filter(|alt| !alt.is_empty()) {
+                        out.push(text(alt, format));
+                    }
+                }
+                "a" => {
+                    let mut content = Vec::new();
+                    for child in children {
+                        inline(child, format, &mut content);
+                    }
+                    let fields = fields([
+                        ("url", props.get("href").cloned().unwrap_or_default()),
+                        ("rel", Value::Null),
+                        ("target", Value::Null),
+                        ("title", props.get("title").cloned().unwrap_or_default()),
+                    ]);
+                    out.push(element("link", fields, content));
+                }
+                "input" => {}
+                _ => {
+                    for child in children {
+                        inline(child, format, out);
+                    }
+                }
+            }
+        }
+        Node::Fragment { children } => {
+            for child in children {
+                inline(child, format, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_to_lexical() {
+        let markdown = "## A ***b***\n\n- one\n  - two\n\n```js\nx\ny\n```";
+        let state = to_lexical(&parse(markdown, &TranspileOptions::default()));
+        let root = &state["root"];
+        assert_eq!(root["type"], "root");
+        assert_eq!(root["children"][0]["tag"], "h2");
+        assert_eq!(root["children"][0]["children"][1], text("b", 3));
+
+        let list = &root["children"][1];
+        assert_eq!((&list["listType"], &list["tag"]), (&json!("bullet"), &json!("ul")));
+        assert_eq!(list["children"][0]["children"], json!([text("one", 0)]));
+        assert_eq!(list["children"][1]["children"][0]["children"][0]["children"], json!([text("two", 0)]));
+
+        let code = &root["children"][2];
+        assert_eq!(code["language"], "js");
+        assert_eq!(code["children"], json!([text("x", 0), linebreak(), text("y", 0)]));
+    }
+}
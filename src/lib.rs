@@ -1,295 +1,1596 @@
-use pulldown_cmark::{Parser, Options, Event, Tag};
+use pulldown_cmark::{CodeBlockKind, CowStr, Parser, Options, Event, Tag, TagEnd};
 use serde::{Serialize, Deserialize};
-use std::collections::HashMap;
+use indexmap::IndexMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::ops::{Deref, Range};
+use std::sync::Arc;
 use regex::Regex;
-use lazy_static::lazy_static;
 
-lazy_static! {
-    static ref TAG_RE: Regex = Regex::new(r#"^<([a-zA-Z0-9-]+)([^>]*?)(/?)>$"#).unwrap();
-    static ref ATTR_RE: Regex = Regex::new(r#"([a-zA-Z0-9-]+)(?:=(?:"([^"]*)"|'([^']*)'|([^>\s]+)))?"#).unwrap();
-}
+mod amp;
+mod android_html;
+mod ansi;
+mod attributed;
+#[cfg(feature = "cbor")]
+mod cbor;
+mod code;
+mod compact;
+mod compose;
+mod constructs;
+mod content_id;
+mod delta;
+mod diff;
+mod elements;
+mod email;
+mod events;
+mod excerpt;
+#[cfg(feature = "ffi")]
+mod ffi;
+mod flat;
+mod footnotes;
+mod hast;
+mod html;
+mod hyperscript;
+mod incremental;
+mod iter;
+mod keys;
+mod lexical;
+mod limits;
+mod links;
+mod markdown;
+mod mdast;
+#[cfg(feature = "uniffi")]
+mod mobile;
+#[cfg(feature = "msgpack")]
+mod msgpack;
+// The addon needs symbols from the Node.js host, absent in test binaries.
+#[cfg(all(feature = "node", not(test)))]
+mod node;
+mod normalize;
+mod notion;
+mod outline;
+mod pandoc;
+mod partial;
+mod presets;
+mod prosemirror;
+#[cfg(feature = "python")]
+mod python;
+mod plain;
+mod query;
+mod render_html;
+mod render_jsx;
+mod render_module;
+mod quotes;
+#[cfg(feature = "schema")]
+mod schema;
+mod search;
+mod section;
+mod serialize;
+mod slate;
+mod slug;
+mod stats;
+mod streaming;
+mod styled_text;
+mod svelte;
+mod transform;
+#[cfg(feature = "typescript")]
+mod typescript;
+mod typography;
+mod validate;
+mod visitor;
+mod vue;
+mod whitespace;
+mod xml;
+
+pub use amp::{render_amp_html, AmpIssue, AmpIssueKind};
+pub use android_html::render_android_html;
+pub use ansi::render_ansi;
+pub use attributed::to_attributed_runs;
+#[cfg(feature = "cbor")]
+pub use cbor::{from_cbor, to_cbor, CborError};
+pub use code::{CodeHandler, Highlighter};
+pub use compact::{to_compact_json, to_compact_value, CompactOptions};
+pub use compose::to_compose_spec;
+pub use constructs::Construct;
+pub use content_id::assign_content_ids;
+pub use delta::to_delta;
+pub use diff::{apply, diff, Patch, PatchError};
+pub use email::{render_email_html, EmailOptions};
+pub use events::{parse_events, JsxEvent};
+pub use excerpt::{excerpt, Excerpt};
+#[cfg(feature = "ffi")]
+pub use ffi::{md2jsx_free, md2jsx_last_status, md2jsx_parse, Md2jsxStatus};
+pub use flat::{parse_flat, FlatAst, FlatNode};
+pub use footnotes::FootnoteMode;
+pub use hast::to_hast;
+pub use hyperscript::{render_hyperscript, to_hyperscript};
+pub use incremental::IncrementalParser;
+pub use iter::{find, find_all, iter, iter_mut, texts, Iter, IterMut, NodeMut, TextContext, Texts};
+pub use keys::{assign_keys, KeyStrategy};
+pub use lexical::to_lexical;
+pub use limits::{parse_limited, LimitedParse, Truncation};
+pub use links::{ImageRewriter, ImageTemplate, LinkKind, LinkRewriter, SchemeAction, UrlSchemes};
+pub use markdown::to_markdown;
+pub use mdast::to_mdast;
+#[cfg(feature = "msgpack")]
+pub use msgpack::{from_msgpack, to_msgpack, MsgpackError};
+pub use normalize::normalize;
+pub use notion::to_notion;
+pub use outline::{outline, Heading};
+pub use pandoc::to_pandoc;
+pub use partial::PartialOptions;
+pub use plain::{to_formatted_text, to_plain_text, to_plain_text_with, PlainTextOptions, TextLayout};
+pub use prosemirror::{to_prosemirror, to_prosemirror_with, ProsemirrorSchema};
+pub use quotes::Quotes;
+pub use render_html::render_html;
+pub use render_jsx::render_jsx;
+pub use render_module::{render_module, JsxRuntime, ModuleOptions};
+pub use query::{query, query_all, query_all_mut, query_mut, Selector, SelectorError};
+#[cfg(feature = "schema")]
+pub use schema::schema;
+pub use search::{search_index, SearchRecord};
+pub use section::{split_sections, Section};
+pub use serialize::{to_json, write_ndjson, Configured, JsonStyle, SerializeConfig, TagCase};
+pub use slate::to_slate;
+pub use slug::{GithubSlugger, Slugger};
+pub use stats::{stats, DocStats};
+pub use streaming::StreamingParser;
+pub use svelte::render_svelte;
+pub use transform::{Preprocess, TextHook, Transform};
+#[cfg(feature = "typescript")]
+pub use typescript::typescript_definitions;
+pub use validate::{validate, Violation, ViolationKind};
+pub use vue::render_vue;
+pub use visitor::{visit, walk, Flow, Visitor, VisitorMut};
+pub use typography::TYPOGRAPHER;
+pub use url::Url;
+pub use whitespace::Whitespace;
+pub use xml::{render_xhtml, render_xhtml_document};
+
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!("md2jsx");
 
+use elements::{check_url_schemes, rewrite_elements};
+use limits::{limit_input, Deadline};
+use slug::assign_heading_ids;
+use whitespace::clean_whitespace;
+use footnotes::layout_footnotes;
+use code::route_code;
+use typography::apply_typography;
+use transform::rewrite_text;
+use html::{HtmlToken, PartialTag, Tokenizer, partial_tag_start, raw_text_close_re};
+
+/// An AST node. Strings are owned by default; `parse_borrowed` produces
+/// `BorrowedNode`s whose strings borrow from the markdown source.
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "schema", schemars(rename = "Node"))]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+#[cfg_attr(feature = "typescript", ts(concrete(S = String)))]
 #[serde(tag = "type")]
-pub enum Node {
+pub enum Node<S = String> {
     #[serde(rename = "element")]
     Element {
-        tag: String,
-        props: HashMap<String, serde_json::Value>,
-        children: Vec<Node>,
+        tag: S,
+        props: IndexMap<String, serde_json::Value>,
+        children: Vec<Node<S>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[cfg_attr(feature = "typescript", ts(optional))]
+        span: Option<Span>,
+        /// Hash of the tag, props and descendant text; see
+        /// `assign_content_ids`.
+        #[serde(rename = "__id", default, skip_serializing_if = "Option::is_none")]
+        #[cfg_attr(feature = "typescript", ts(optional, rename = "__id"))]
+        content_id: Option<String>,
     },
     #[serde(rename = "text")]
     Text {
-        content: String,
+        content: S,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[cfg_attr(feature = "typescript", ts(optional))]
+        span: Option<Span>,
+    },
+    /// A group of siblings with no wrapper element, like `React.Fragment`.
+    /// Lets a transform put several nodes where one is expected;
+    /// `normalize` splices fragments into their parent.
+    #[serde(rename = "fragment")]
+    Fragment {
+        children: Vec<Node<S>>,
+    },
+    /// An HTML comment (`<!-- content -->`) or tool annotation. Carries
+    /// no visible content; renderers may drop it.
+    #[serde(rename = "comment")]
+    Comment {
+        content: S,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[cfg_attr(feature = "typescript", ts(optional))]
+        span: Option<Span>,
     },
+    /// Verbatim source in `format` (`"html"`, `"latex"`, ...), kept apart
+    /// from text so renderers can inject, highlight or drop it.
+    #[serde(rename = "raw")]
+    Raw {
+        content: S,
+        format: S,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[cfg_attr(feature = "typescript", ts(optional))]
+        span: Option<Span>,
+    },
+}
+
+/// Location of a node in the markdown source. `start` and `end` are byte
+/// offsets; `line` and `column` (1-based, column in bytes) locate `start`.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Chainable constructors for building or extending an AST by hand:
+///
+/// ```
+/// use md2jsx::Node;
+///
+/// let link = Node::element("a")
+///     .prop("href", "https://example.com")
+///     .child(Node::text("Example"));
+/// ```
+impl Node {
+    pub fn element(tag: impl Into<String>) -> Node {
+        Node::Element {
+            tag: tag.into(),
+            props: IndexMap::new(),
+            children: Vec::new(),
+            span: None,
+            content_id: None,
+        }
+    }
+
+    pub fn text(content: impl Into<String>) -> Node {
+        Node::Text {
+            content: content.into(),
+            span: None,
+        }
+    }
+
+    pub fn comment(content: impl Into<String>) -> Node {
+        Node::Comment {
+            content: content.into(),
+            span: None,
+        }
+    }
+
+    pub fn raw(content: impl Into<String>, format: impl Into<String>) -> Node {
+        Node::Raw {
+            content: content.into(),
+            format: format.into(),
+            span: None,
+        }
+    }
+
+    pub fn fragment(children: impl IntoIterator<Item = Node>) -> Node {
+        Node::Fragment {
+            children: children.into_iter().collect(),
+        }
+    }
+}
+
+/// Chainable setters and child access, shared by owned and borrowed
+/// nodes. Methods adding props or children leave nodes that cannot hold
+/// them unchanged.
+impl<S> Node<S> {
+    pub fn prop(mut self, key: impl Into<String>, value: impl Into<serde_json::Value>) -> Node<S> {
+        if let Node::Element { props, .. } = &mut self {
+            props.insert(key.into(), value.into());
+        }
+        self
+    }
+
+    pub fn child(mut self, node: Node<S>) -> Node<S> {
+        if let Some(children) = self.children_mut() {
+            children.push(node);
+        }
+        self
+    }
+
+    pub fn with_children(mut self, nodes: impl IntoIterator<Item = Node<S>>) -> Node<S> {
+        if let Some(children) = self.children_mut() {
+            children.extend(nodes);
+        }
+        self
+    }
+
+    pub fn with_span(mut self, value: Span) -> Node<S> {
+        match &mut self {
+            Node::Element { span, .. }
+            | Node::Text { span, .. }
+            | Node::Comment { span, .. }
+            | Node::Raw { span, .. } => *span = Some(value),
+            Node::Fragment { .. } => {}
+        }
+        self
+    }
+
+    /// Child list of an element or fragment.
+    pub fn children(&self) -> Option<&Vec<Node<S>>> {
+        match self {
+            Node::Element { children, .. } | Node::Fragment { children } => Some(children),
+            Node::Text { .. } | Node::Comment { .. } | Node::Raw { .. } => None,
+        }
+    }
+
+    pub fn children_mut(&mut self) -> Option<&mut Vec<Node<S>>> {
+        match self {
+            Node::Element { children, .. } | Node::Fragment { children } => Some(children),
+            Node::Text { .. } | Node::Comment { .. } | Node::Raw { .. } => None,
+        }
+    }
+}
+
+impl<S: Deref<Target = str>> Node<S> {
+    /// Tag name of an element.
+    pub fn tag(&self) -> Option<&str> {
+        match self {
+            Node::Element { tag, .. } => Some(tag),
+            _ => None,
+        }
+    }
+}
+
+/// A node whose strings borrow from the markdown source where possible.
+pub type BorrowedNode<'a> = Node<Cow<'a, str>>;
+
+impl BorrowedNode<'_> {
+    /// Copies borrowed strings, detaching the node from the source.
+    pub fn into_owned(self) -> Node {
+        match self {
+            Node::Element { tag, props, children, span, content_id } => Node::Element {
+                tag: tag.into_owned(),
+                props,
+                children: children.into_iter().map(BorrowedNode::into_owned).collect(),
+                span,
+                content_id,
+            },
+            Node::Text { content, span } => Node::Text { content: content.into_owned(), span },
+            Node::Fragment { children } => Node::Fragment {
+                children: children.into_iter().map(BorrowedNode::into_owned).collect(),
+            },
+            Node::Comment { content, span } => Node::Comment { content: content.into_owned(), span },
+            Node::Raw { content, format, span } => Node::Raw {
+                content: content.into_owned(),
+                format: format.into_owned(),
+                span,
+            },
+        }
+    }
+}
+
+fn element<S>(tag: impl Into<S>, props: IndexMap<String, serde_json::Value>) -> Node<S> {
+    Node::Element {
+        tag: tag.into(),
+        props,
+        children: Vec::new(),
+        span: None,
+        content_id: None,
+    }
+}
+
+fn text_node<S>(content: impl Into<S>) -> Node<S> {
+    Node::Text {
+        content: content.into(),
+        span: None,
+    }
+}
+
+/// String types the tree builder can produce: `String`, or a `Cow` that
+/// borrows from the source.
+trait SourceStr<'a>: Deref<Target = str> + From<&'a str> + From<String> {}
+
+impl<'a, T: Deref<Target = str> + From<&'a str> + From<String>> SourceStr<'a> for T {}
+
+/// How `<script>` and `<style>` elements (and everything between their
+/// opening and closing tags) are handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+pub enum RawTextPolicy {
+    /// Treat them like any other tag, subject to `allowed_tags`.
+    #[default]
+    Allow,
+    /// Emit the whole element, tags included, as a single text node.
+    Escape,
+    /// Drop the whole element from the output.
+    Remove,
+    /// Emit the whole element, tags included, as a `raw` node with format
+    /// `"html"`.
+    Raw,
+}
+
+/// How a line break inside a paragraph (without trailing spaces or a
+/// backslash) is emitted. Hard breaks are always a `br` element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+pub enum SoftBreak {
+    /// A `"\n"` text node, which HTML renders as a space.
+    #[default]
+    Newline,
+    /// A `" "` text node, for targets that render newlines literally.
+    Space,
+    /// A `br` element, for chat-style line breaks.
+    Br,
 }
 
+/// Naming of emitted props. Markdown-generated props use React names
+/// (`className`); attributes of allowed HTML keep their source names
+/// unless converted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+pub enum PropStyle {
+    /// Leave names as generated and as written in the source.
+    #[default]
+    Preserve,
+    /// JSX names throughout: `className`, `htmlFor`, `tabIndex`, ...
+    React,
+    /// Plain HTML attribute names throughout: `class`, `for`, `tabindex`,
+    /// ... `to_plain_text`, `stats` and `to_markdown` expect React names.
+    Html,
+    /// HTML names as Preact and Solid take them in JSX: like `Html`, but
+    /// known DOM attributes are lowercased whatever their source casing
+    /// (`TabIndex` too).
+    #[serde(alias = "Solid")]
+    Preact,
+}
+
+/// Markdown syntax extensions on top of CommonMark. The defaults match
+/// GitHub: everything but heading attributes is on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+#[serde(default)]
+pub struct Extensions {
+    pub tables: bool,
+    /// `[^label]` references and their definitions.
+    pub footnotes: bool,
+    /// `~~text~~`, rendered as `del`.
+    pub strikethrough: bool,
+    /// `- [x]` list items, rendered with a disabled checkbox.
+    pub tasklists: bool,
+    /// Curly quotes, en/em dashes and ellipses.
+    pub smart_punctuation: bool,
+    /// `# Heading {#id .class}`, setting the heading's `id` and
+    /// `className`.
+    pub heading_attributes: bool,
+}
+
+impl Default for Extensions {
+    fn default() -> Self {
+        Extensions {
+            tables: true,
+            footnotes: true,
+            strikethrough: true,
+            tasklists: true,
+            smart_punctuation: true,
+            heading_attributes: false,
+        }
+    }
+}
+
+impl Extensions {
+    /// Only CommonMark, with every extension off.
+    pub fn none() -> Self {
+        Extensions {
+            tables: false,
+            footnotes: false,
+            strikethrough: false,
+            tasklists: false,
+            smart_punctuation: false,
+            heading_attributes: false,
+        }
+    }
+
+    fn parser_options(&self) -> Options {
+        let mut options = Options::empty();
+        options.set(Options::ENABLE_TABLES, self.tables);
+        options.set(Options::ENABLE_FOOTNOTES, self.footnotes);
+        options.set(Options::ENABLE_STRIKETHROUGH, self.strikethrough);
+        options.set(Options::ENABLE_TASKLISTS, self.tasklists);
+        options.set(Options::ENABLE_SMART_PUNCTUATION, self.smart_punctuation);
+        options.set(Options::ENABLE_HEADING_ATTRIBUTES, self.heading_attributes);
+        options
+    }
+}
+
+/// Ids and classes of footnote markup, to match existing site CSS or keep
+/// ids from clashing with other content on the page.
+///
+/// `to_plain_text`, `stats` and `to_markdown` recognize footnotes by the
+/// default names only.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+#[serde(default)]
+pub struct FootnoteNames {
+    /// Prepended to a footnote's label to form its definition's `id`.
+    pub id_prefix: String,
+    /// `className` of the reference link.
+    pub ref_class: String,
+    /// `className` of the definition's `div`.
+    pub definition_class: String,
+}
+
+impl Default for FootnoteNames {
+    fn default() -> Self {
+        FootnoteNames {
+            id_prefix: "fn-".to_string(),
+            ref_class: "footnote-ref".to_string(),
+            definition_class: "footnote-definition".to_string(),
+        }
+    }
+}
+
+/// Parser settings. New fields are added over time, so outside this crate
+/// build them with `TranspileOptions::builder()` or start from
+/// `TranspileOptions::default()` and assign fields.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+#[serde(default, deny_unknown_fields)]
+#[non_exhaustive]
 pub struct TranspileOptions {
     pub allowed_tags: Vec<String>,
+    /// Applied to `<script>`/`<style>` regardless of `allowed_tags`.
+    pub raw_text_elements: RawTextPolicy,
+    /// Attach a source `span` to every node.
+    pub source_spans: bool,
+    /// Add a CommonMark-style `data-sourcepos` prop (`"3:1-5:20"`) to
+    /// block-level elements.
+    pub sourcepos: bool,
+    /// Run `normalize` on the AST before any transforms.
+    pub normalize: bool,
+    /// Give every element a `content_id` (`__id` in JSON) once transforms
+    /// have run.
+    pub content_ids: bool,
+    /// Give every element a React `key` prop, after any content ids.
+    pub keys: Option<KeyStrategy>,
+    /// Markdown syntax extensions to recognize.
+    pub extensions: Extensions,
+    /// Constructs left as literal text, e.g. images and headings in chat.
+    pub disabled_constructs: HashSet<Construct>,
+    pub soft_break: SoftBreak,
+    /// Trimming and collapsing of text, after `normalize`.
+    pub whitespace: Whitespace,
+    /// Quote marks produced by `extensions.smart_punctuation`.
+    pub quotes: Quotes,
+    /// Further replacements made with smart punctuation (`(c)` to `©`,
+    /// `->` to `→`, ...), in text outside code. Longer patterns win. The
+    /// parser splits text at `<` and some other characters, so patterns
+    /// spanning them need `normalize`.
+    pub typography: HashMap<String, String>,
+    pub prop_style: PropStyle,
+    pub footnote_names: FootnoteNames,
+    /// Where footnote definitions are rendered.
+    pub footnotes: FootnoteMode,
+    /// Renames elements (`h1` to `Heading`, `a` to `Link`, ...), whether
+    /// they come from markdown or allowed HTML. Applied after transforms.
+    pub tag_map: HashMap<String, String>,
+    /// Classes added to every element of a tag (`table` to `md-table`,
+    /// ...), after any it already has. Keyed by the tag before `tag_map`.
+    pub class_map: HashMap<String, String>,
+    /// Renders fenced code blocks by language (`chart`, `math`, ...)
+    /// instead of as `pre`/`code`.
+    pub code_handlers: HashMap<String, CodeHandler>,
+    /// Resolves relative link `href`s and image `src`s against this URL.
+    /// Absolute URLs and `#anchor` links are kept.
+    #[cfg_attr(feature = "typescript", ts(as = "Option<String>"))]
+    pub base_url: Option<url::Url>,
+    /// Drops the `.md` extension from relative links (`guide/setup.md#x`
+    /// becomes `guide/setup#x`), for content authored for GitHub and
+    /// served as SPA routes.
+    pub md_links_as_routes: bool,
+    /// Schemes allowed in link and image URLs; others become text or are
+    /// dropped.
+    pub url_schemes: UrlSchemes,
+    /// Rewrites every link `href` before `base_url` resolution.
+    #[serde(skip)]
+    #[cfg_attr(feature = "typescript", ts(skip))]
+    pub link_rewriter: Option<Arc<dyn LinkRewriter>>,
+    /// Rewrites every image `src` after `base_url` resolution, optionally
+    /// adding a `srcSet`.
+    #[serde(skip)]
+    #[cfg_attr(feature = "typescript", ts(skip))]
+    pub image_rewriter: Option<Arc<dyn ImageRewriter>>,
+    /// Prepended to every `id` and in-page `#anchor` link (footnotes,
+    /// heading ids, ids in allowed HTML), so several documents on one page
+    /// do not produce duplicate DOM ids. `to_markdown` expects unprefixed
+    /// footnote ids.
+    pub id_prefix: Option<String>,
+    /// Deepest element nesting to build. An element opened deeper is left
+    /// out and its children take its place, so pathological input cannot
+    /// produce a tree deep enough to exhaust the stack of recursive
+    /// consumers. Inline code, footnote references and code blocks may
+    /// add up to two levels below it.
+    pub max_depth: Option<usize>,
+    /// Only the first this many bytes of input are parsed, cut back to the
+    /// last line break. See `parse_limited`.
+    pub max_input_bytes: Option<usize>,
+    /// Parsing stops once it has run this long, keeping what was built.
+    /// Checked between parser events, and not enforced on
+    /// `wasm32-unknown-unknown`, which has no clock.
+    pub timeout_ms: Option<u64>,
+    /// Give every heading without an `id` one slugged from its text,
+    /// before transforms run.
+    pub heading_ids: bool,
+    /// Slugs for `heading_ids`; `GithubSlugger` if `None`.
+    #[serde(skip)]
+    #[cfg_attr(feature = "typescript", ts(skip))]
+    pub slugger: Option<Arc<dyn Slugger>>,
+    /// Rewrites the markdown before parsing.
+    #[serde(skip)]
+    #[cfg_attr(feature = "typescript", ts(skip))]
+    pub preprocess: Option<Arc<dyn Preprocess>>,
+    /// Rewrites each text node, after `normalize` and `whitespace`.
+    #[serde(skip)]
+    #[cfg_attr(feature = "typescript", ts(skip))]
+    pub text_hook: Option<Arc<dyn TextHook>>,
+    /// Rewrites applied to the finished AST, in order.
+    #[serde(skip)]
+    #[cfg_attr(feature = "typescript", ts(skip))]
+    pub transforms: Vec<Arc<dyn Transform>>,
 }
 
-fn parse_html_tag(html: &str) -> Option<(String, HashMap<String, serde_json::Value>, bool)> {
-    let html = html.trim();
-    if let Some(caps) = TAG_RE.captures(html) {
-        let tag_name = caps.get(1).unwrap().as_str().to_string();
-        let attrs_str = caps.get(2).unwrap().as_str();
-        let is_self_closing = !caps.get(3).unwrap().as_str().is_empty();
-        
-        let mut props = HashMap::new();
-        for attr_caps in ATTR_RE.captures_iter(attrs_str) {
-            let key = attr_caps.get(1).unwrap().as_str().to_string();
-            let value = attr_caps.get(2)
-                .or_else(|| attr_caps.get(3))
-                .or_else(|| attr_caps.get(4))
-                .map(|m| serde_json::Value::String(m.as_str().to_string()))
-                .unwrap_or(serde_json::Value::Bool(true));
-            props.insert(key, value);
+impl TranspileOptions {
+    /// Options suited to user-submitted content: only the given tags are
+    /// rendered, and script/style elements are removed entirely.
+    pub fn untrusted(allowed_tags: Vec<String>) -> Self {
+        TranspileOptions {
+            allowed_tags,
+            raw_text_elements: RawTextPolicy::Remove,
+            ..Default::default()
         }
-        
-        return Some((tag_name, props, is_self_closing));
     }
-    
-    // Handle closing tags
-    if html.starts_with("</") && html.ends_with(">") {
-        let tag_name = html[2..html.len()-1].trim().to_string();
-        return Some((tag_name, HashMap::new(), false));
+
+    pub fn builder() -> TranspileOptionsBuilder {
+        TranspileOptionsBuilder::default()
+    }
+
+    /// Reads options from JSON with the field names used here
+    /// (`{"allowed_tags": ["Card"], "soft_break": "Br"}`). Missing fields
+    /// keep their defaults; unknown ones are an error. Hooks (`transforms`,
+    /// rewriters, `slugger`) cannot be given this way.
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// Like `from_json`, for TOML.
+    #[cfg(feature = "toml")]
+    pub fn from_toml(toml: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(toml)
+    }
+
+    fn parser_options(&self) -> Options {
+        constructs::without_disabled(self.extensions.parser_options(), &self.disabled_constructs)
+    }
+}
+
+/// Chainable setters for `TranspileOptions`, starting from the defaults:
+///
+/// ```
+/// use md2jsx::{KeyStrategy, TranspileOptions};
+///
+/// let options = TranspileOptions::builder()
+///     .allowed_tags(["CustomBox"])
+///     .source_spans(true)
+///     .keys(KeyStrategy::IndexPath)
+///     .build();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TranspileOptionsBuilder {
+    options: TranspileOptions,
+}
+
+impl TranspileOptionsBuilder {
+    pub fn allowed_tags(mut self, tags: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.options.allowed_tags = tags.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn raw_text_elements(mut self, policy: RawTextPolicy) -> Self {
+        self.options.raw_text_elements = policy;
+        self
+    }
+
+    pub fn source_spans(mut self, enabled: bool) -> Self {
+        self.options.source_spans = enabled;
+        self
+    }
+
+    pub fn sourcepos(mut self, enabled: bool) -> Self {
+        self.options.sourcepos = enabled;
+        self
+    }
+
+    pub fn normalize(mut self, enabled: bool) -> Self {
+        self.options.normalize = enabled;
+        self
+    }
+
+    pub fn content_ids(mut self, enabled: bool) -> Self {
+        self.options.content_ids = enabled;
+        self
+    }
+
+    pub fn keys(mut self, strategy: KeyStrategy) -> Self {
+        self.options.keys = Some(strategy);
+        self
+    }
+
+    pub fn extensions(mut self, extensions: Extensions) -> Self {
+        self.options.extensions = extensions;
+        self
+    }
+
+    pub fn disabled_constructs(mut self, constructs: impl IntoIterator<Item = Construct>) -> Self {
+        self.options.disabled_constructs = constructs.into_iter().collect();
+        self
+    }
+
+    pub fn soft_break(mut self, soft_break: SoftBreak) -> Self {
+        self.options.soft_break = soft_break;
+        self
+    }
+
+    pub fn whitespace(mut self, whitespace: Whitespace) -> Self {
+        self.options.whitespace = whitespace;
+        self
+    }
+
+    pub fn typography(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.options.typography.insert(from.into(), to.into());
+        self
+    }
+
+    /// Adds the `TYPOGRAPHER` replacements to `typography`.
+    pub fn typographer(mut self) -> Self {
+        for (from, to) in TYPOGRAPHER {
+            self.options.typography.insert(from.to_string(), to.to_string());
+        }
+        self
+    }
+
+    pub fn quotes(mut self, quotes: Quotes) -> Self {
+        self.options.quotes = quotes;
+        self
+    }
+
+    pub fn prop_style(mut self, style: PropStyle) -> Self {
+        self.options.prop_style = style;
+        self
+    }
+
+    pub fn footnotes(mut self, mode: FootnoteMode) -> Self {
+        self.options.footnotes = mode;
+        self
+    }
+
+    pub fn footnote_names(mut self, names: FootnoteNames) -> Self {
+        self.options.footnote_names = names;
+        self
+    }
+
+    /// Emits `tag` elements as `to` instead.
+    pub fn map_tag(mut self, tag: impl Into<String>, to: impl Into<String>) -> Self {
+        self.options.tag_map.insert(tag.into(), to.into());
+        self
+    }
+
+    /// Adds `class` to the `className` of every `tag` element.
+    pub fn class(mut self, tag: impl Into<String>, class: impl Into<String>) -> Self {
+        self.options.class_map.insert(tag.into(), class.into());
+        self
+    }
+
+    pub fn code_handler(mut self, language: impl Into<String>, handler: CodeHandler) -> Self {
+        self.options.code_handlers.insert(language.into(), handler);
+        self
+    }
+
+    pub fn base_url(mut self, url: url::Url) -> Self {
+        self.options.base_url = Some(url);
+        self
+    }
+
+    pub fn md_links_as_routes(mut self, enabled: bool) -> Self {
+        self.options.md_links_as_routes = enabled;
+        self
+    }
+
+    pub fn url_schemes(mut self, schemes: UrlSchemes) -> Self {
+        self.options.url_schemes = schemes;
+        self
+    }
+
+    pub fn link_rewriter(mut self, rewriter: impl LinkRewriter + 'static) -> Self {
+        self.options.link_rewriter = Some(Arc::new(rewriter));
+        self
+    }
+
+    pub fn image_rewriter(mut self, rewriter: impl ImageRewriter + 'static) -> Self {
+        self.options.image_rewriter = Some(Arc::new(rewriter));
+        self
+    }
+
+    pub fn id_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.options.id_prefix = Some(prefix.into());
+        self
+    }
+
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.options.max_depth = Some(depth);
+        self
+    }
+
+    pub fn max_input_bytes(mut self, max: usize) -> Self {
+        self.options.max_input_bytes = Some(max);
+        self
+    }
+
+    pub fn timeout_ms(mut self, ms: u64) -> Self {
+        self.options.timeout_ms = Some(ms);
+        self
+    }
+
+    pub fn heading_ids(mut self, enabled: bool) -> Self {
+        self.options.heading_ids = enabled;
+        self
+    }
+
+    pub fn slugger(mut self, slugger: impl Slugger + 'static) -> Self {
+        self.options.slugger = Some(Arc::new(slugger));
+        self
+    }
+
+    /// Sets the hook that rewrites the markdown source before it is parsed,
+    /// replacing any set before.
+    pub fn preprocess(mut self, preprocess: impl Preprocess + 'static) -> Self {
+        self.options.preprocess = Some(Arc::new(preprocess));
+        self
+    }
+
+    pub fn text_hook(mut self, hook: impl TextHook + 'static) -> Self {
+        self.options.text_hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Appends a transform, to run after those added before it.
+    pub fn transform(mut self, transform: impl Transform + 'static) -> Self {
+        self.options.transforms.push(Arc::new(transform));
+        self
+    }
+
+    pub fn build(self) -> TranspileOptions {
+        self.options
+    }
+}
+
+/// A `<script>` or `<style>` element currently being swallowed.
+struct RawTextElement {
+    close_re: &'static Regex,
+    start: usize,
+    end: usize,
+    /// Stack height when the element was opened.
+    depth: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FrameKind {
+    /// Opened by a markdown `Start` event, closed by its `End` event.
+    Markdown,
+    /// Opened by an allowed HTML tag, closed by the matching end tag.
+    Html,
+    /// Placeholder for a markdown container whose start was swallowed;
+    /// its children are handed to the parent when it closes.
+    Transparent,
+}
+
+struct Frame<S> {
+    node: Node<S>,
+    kind: FrameKind,
+    /// Element nesting depth inside this frame, including it.
+    depth: usize,
+    /// Opened beyond `max_depth`; its children are handed to the parent
+    /// when it closes.
+    flatten: bool,
+}
+
+struct TreeBuilder<'a, 'o, S> {
+    source: &'a str,
+    options: &'o TranspileOptions,
+    root: Vec<Node<S>>,
+    stack: Vec<Frame<S>>,
+    raw_text: Option<RawTextElement>,
+    /// Nesting inside a disabled construct whose source was emitted as
+    /// text; its events are skipped.
+    literal_depth: usize,
+    /// Source offset and text of an HTML block line ending mid-tag, kept
+    /// until the following lines complete the tag.
+    pending_html: Option<(usize, String, PartialTag)>,
+    /// Byte offset of each line start, when source spans are enabled.
+    line_starts: Vec<usize>,
+}
+
+impl<'a, 'o, S: SourceStr<'a>> TreeBuilder<'a, 'o, S> {
+    fn new(source: &'a str, options: &'o TranspileOptions) -> Self {
+        TreeBuilder {
+            source,
+            options,
+            root: Vec::new(),
+            stack: Vec::new(),
+            raw_text: None,
+            literal_depth: 0,
+            pending_html: None,
+            line_starts: if options.source_spans || options.sourcepos {
+                std::iter::once(0)
+                    .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+                    .collect()
+            } else {
+                Vec::new()
+            },
+        }
+    }
+
+    /// 1-based line and byte column of a source offset.
+    fn position(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        (line, offset - self.line_starts[line - 1] + 1)
+    }
+
+    fn span(&self, range: Range<usize>) -> Option<Span> {
+        if !self.options.source_spans {
+            return None;
+        }
+        let (line, column) = self.position(range.start);
+        Some(Span {
+            start: range.start,
+            end: range.end,
+            line,
+            column,
+        })
+    }
+
+    /// Formats a range as `start_line:start_col-end_line:end_col`, where the
+    /// end is the last character of the block (trailing newlines excluded).
+    fn sourcepos(&self, range: Range<usize>) -> String {
+        let content = self.source[range.clone()].trim_end_matches(['\n', '\r']);
+        let last = range.start + content.len().max(1) - 1;
+        let (start_line, start_column) = self.position(range.start);
+        let (end_line, end_column) = self.position(last);
+        format!("{}:{}-{}:{}", start_line, start_column, end_line, end_column)
+    }
+
+    /// `text` as an `S`, borrowed when it appears verbatim in the source
+    /// at `offset`.
+    fn string(&self, text: &str, offset: usize) -> S {
+        let source = self.source;
+        match source.get(offset..offset + text.len()) {
+            Some(slice) if slice == text => S::from(slice),
+            _ => S::from(text.to_string()),
+        }
+    }
+
+    /// Parser output as an `S`, borrowed when it is a slice of the source.
+    fn cow(&self, text: CowStr<'a>) -> S {
+        match text {
+            CowStr::Borrowed(text) => S::from(text),
+            text => S::from(text.into_string()),
+        }
+    }
+
+    fn add_sourcepos(&self, node: &mut Node<S>, range: Range<usize>) {
+        if let (true, Node::Element { props, .. }) = (self.options.sourcepos, node) {
+            let pos = self.sourcepos(range);
+            props.insert("data-sourcepos".to_string(), serde_json::Value::String(pos));
+        }
+    }
+
+    /// Sets the span of `node` and of any descendants that lack one.
+    fn spanned(&self, mut node: Node<S>, range: Range<usize>) -> Node<S> {
+        fn fill<S>(node: &mut Node<S>, value: Span) {
+            match node {
+                Node::Element { children, span, .. } => {
+                    span.get_or_insert(value);
+                    for child in children {
+                        fill(child, value);
+                    }
+                }
+                Node::Text { span, .. } | Node::Comment { span, .. } | Node::Raw { span, .. } => {
+                    span.get_or_insert(value);
+                }
+                Node::Fragment { children } => {
+                    for child in children {
+                        fill(child, value);
+                    }
+                }
+            }
+        }
+        if let Some(value) = self.span(range) {
+            fill(&mut node, value);
+        }
+        node
+    }
+
+    fn append(&mut self, node: Node<S>) {
+        match self.stack.last_mut() {
+            Some(Frame { node: Node::Element { children, .. }, .. }) => children.push(node),
+            _ => self.root.push(node),
+        }
+    }
+
+    fn open(&mut self, node: Node<S>, kind: FrameKind) {
+        let parent = self.stack.last().map_or(0, |frame| frame.depth);
+        let flatten = kind == FrameKind::Transparent || self.options.max_depth.is_some_and(|max| parent >= max);
+        let depth = if flatten { parent } else { parent + 1 };
+        self.stack.push(Frame { node, kind, depth, flatten });
+    }
+
+    fn pop(&mut self) -> bool {
+        match self.stack.pop() {
+            Some(Frame { node: Node::Element { children, .. }, flatten: true, .. }) => {
+                for child in children {
+                    self.append(child);
+                }
+                true
+            }
+            Some(frame) => {
+                self.append(frame.node);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Closes the innermost markdown container, along with any HTML
+    /// elements left open inside it.
+    fn close_markdown(&mut self) {
+        if let Some(index) = self.stack.iter().rposition(|f| f.kind != FrameKind::Html) {
+            while self.stack.len() > index + 1 {
+                self.pop();
+            }
+            if self.stack[index].kind == FrameKind::Markdown && !self.stack[index].flatten {
+                let frame = self.stack.pop().unwrap();
+                self.append(finish_markdown_element(frame.node));
+            } else {
+                self.pop();
+            }
+        }
+    }
+
+    /// Closes the innermost open HTML element named `name` (and anything
+    /// opened after it). Only elements inside the current markdown container
+    /// are considered, so a stray end tag never breaks markdown structure.
+    /// `end` is the source offset just past the end tag.
+    fn close_html(&mut self, name: &str, end: usize) -> bool {
+        let found = self.stack.iter()
+            .rev()
+            .take_while(|f| f.kind == FrameKind::Html)
+            .position(|f| matches!(&f.node, Node::Element { tag, .. } if &**tag == name));
+        match found {
+            Some(offset) => {
+                let index = self.stack.len() - 1 - offset;
+                if let Node::Element { span: Some(span), .. } = &mut self.stack[index].node {
+                    span.end = end;
+                }
+                while self.stack.len() > index {
+                    self.pop();
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn finish(mut self) -> Vec<Node<S>> {
+        self.flush_pending_html();
+        self.finish_raw_text();
+        // Markdown containers are only left open when parsing stopped early.
+        while let Some(frame) = self.stack.last() {
+            if frame.kind == FrameKind::Html {
+                self.pop();
+            } else {
+                self.close_markdown();
+            }
+        }
+        self.root
+    }
+
+    fn event(&mut self, event: Event<'a>, range: Range<usize>) {
+        if !matches!(event, Event::Html(_)) {
+            self.flush_pending_html();
+        }
+        if let Some(raw) = self.raw_text.as_mut() {
+            match &event {
+                Event::Start(_) => {
+                    raw.end = raw.end.max(range.end);
+                    self.open(element("", IndexMap::new()), FrameKind::Transparent);
+                    return;
+                }
+                Event::End(_) if self.stack.len() > raw.depth => {
+                    self.close_markdown();
+                    return;
+                }
+                // The enclosing container ended before the closing tag.
+                Event::End(_) => self.finish_raw_text(),
+                // Scanned for the closing tag below.
+                Event::Html(_) | Event::InlineHtml(_) => {}
+                _ => {
+                    raw.end = raw.end.max(range.end);
+                    return;
+                }
+            }
+        }
+        if self.literal_depth > 0 {
+            match event {
+                Event::Start(_) => self.literal_depth += 1,
+                Event::End(_) => self.literal_depth -= 1,
+                _ => {}
+            }
+            return;
+        }
+        if let Some(construct) = Construct::of(&event).filter(|c| self.options.disabled_constructs.contains(c)) {
+            self.literal(construct, event, range);
+            return;
+        }
+
+        match event {
+            Event::End(TagEnd::HtmlBlock) if self.options.disabled_constructs.contains(&Construct::Html) => self.close_markdown(),
+            Event::Start(Tag::HtmlBlock) | Event::End(TagEnd::HtmlBlock) => {}
+            Event::Start(tag) => {
+                let block = is_block(&tag);
+                let mut node = element_for_tag(tag, self.options);
+                if block {
+                    self.add_sourcepos(&mut node, range.clone());
+                }
+                let node = self.spanned(node, range);
+                self.open(node, FrameKind::Markdown);
+            }
+            Event::End(_) => self.close_markdown(),
+            Event::Text(content) => {
+                let localized = match self.options.extensions.smart_punctuation {
+                    true => self.options.quotes.localize(&content, self.source, range.clone()),
+                    false => None,
+                };
+                let content = match localized {
+                    Some(mark) if *mark != *content => CowStr::from(mark.to_string()),
+                    _ => content,
+                };
+                let node = self.spanned(text_node(self.cow(content)), range);
+                self.append(node);
+            }
+            Event::Code(code) => {
+                let node = element("code", IndexMap::new()).child(text_node(self.cow(code)));
+                let node = self.spanned(node, range);
+                self.append(node);
+            }
+            Event::FootnoteReference(label) => {
+                let mut props = IndexMap::new();
+                let names = &self.options.footnote_names;
+                props.insert("href".to_string(), serde_json::Value::String(format!("#{}{}", names.id_prefix, label)));
+                props.insert("className".to_string(), serde_json::Value::String(names.ref_class.clone()));
+                let link = element("a", props).child(text_node(self.cow(label)));
+                let node = self.spanned(element("sup", IndexMap::new()).child(link), range);
+                self.append(node);
+            }
+            Event::TaskListMarker(checked) => {
+                let mut props = IndexMap::new();
+                props.insert("type".to_string(), serde_json::Value::String("checkbox".to_string()));
+                props.insert("checked".to_string(), serde_json::Value::Bool(checked));
+                props.insert("disabled".to_string(), serde_json::Value::Bool(true));
+                let node = self.spanned(element("input", props), range);
+                self.append(node);
+            }
+            Event::Rule => {
+                let mut node = element("hr", IndexMap::new());
+                self.add_sourcepos(&mut node, range.clone());
+                let node = self.spanned(node, range);
+                self.append(node);
+            }
+            Event::Html(html) => self.block_html(&html, range.start),
+            Event::InlineHtml(html) => self.html(&html, range.start),
+            Event::SoftBreak if !self.stack.is_empty() => {
+                let node = match self.options.soft_break {
+                    SoftBreak::Newline => text_node("\n"),
+                    SoftBreak::Space => text_node(" "),
+                    SoftBreak::Br => element("br", IndexMap::new()),
+                };
+                let node = self.spanned(node, range);
+                self.append(node);
+            }
+            Event::HardBreak if !self.stack.is_empty() => {
+                let node = self.spanned(element("br", IndexMap::new()), range);
+                self.append(node);
+            }
+            _ => {}
+        }
+    }
+
+    /// Emits a disabled construct as its source text, skipping the events
+    /// inside it. HTML blocks keep their events, each line becoming text in
+    /// a paragraph.
+    fn literal(&mut self, construct: Construct, event: Event<'a>, range: Range<usize>) {
+        let node = match event {
+            Event::Start(Tag::HtmlBlock) => {
+                let node = self.spanned(element("p", IndexMap::new()), range);
+                self.open(node, FrameKind::Markdown);
+                return;
+            }
+            Event::Html(html) | Event::InlineHtml(html) => text_node(self.cow(html)),
+            event => {
+                if matches!(event, Event::Start(_)) {
+                    self.literal_depth = 1;
+                }
+                let source = &self.source[range.clone()];
+                let text = text_node(S::from(source.trim_end_matches(['\n', '\r'])));
+                match construct.is_block() {
+                    true => element("p", IndexMap::new()).child(text),
+                    false => text,
+                }
+            }
+        };
+        let node = self.spanned(node, range);
+        self.append(node);
+    }
+
+    /// Handles one line of an HTML block. Tags whose attributes continue on
+    /// the next line are buffered and reassembled before parsing.
+    fn block_html(&mut self, line: &str, offset: usize) {
+        let (offset, mut chunk) = match self.pending_html.take() {
+            Some((start, mut pending, partial)) if start + pending.len() == offset => {
+                let from = pending.len();
+                pending.push_str(line);
+                // Only the new line can end the tag, so the buffer isn't
+                // rescanned for every line of a long one.
+                if let Some(partial) = partial.resume(&pending, from) {
+                    self.pending_html = Some((start, pending, partial));
+                    return;
+                }
+                (start, pending)
+            }
+            pending => {
+                self.pending_html = pending;
+                self.flush_pending_html();
+                (offset, line.to_string())
+            }
+        };
+        match partial_tag_start(&chunk) {
+            Some(0) => {
+                let partial = PartialTag::new(&chunk);
+                self.pending_html = Some((offset, chunk, partial));
+            }
+            Some(index) => {
+                let rest = chunk.split_off(index);
+                self.html(&chunk, offset);
+                let partial = PartialTag::new(&rest);
+                self.pending_html = Some((offset + index, rest, partial));
+            }
+            None => self.html(&chunk, offset),
+        }
+    }
+
+    fn flush_pending_html(&mut self) {
+        if let Some((offset, chunk, _)) = self.pending_html.take() {
+            self.html(&chunk, offset);
+        }
+    }
+
+    /// Handles one raw HTML chunk starting at byte `offset` of the source.
+    /// Allowed tags open and close elements; everything else is kept as text.
+    fn html(&mut self, chunk: &str, offset: usize) {
+        let mut tokens = Tokenizer::new(chunk);
+        // Start of the pending run of text (and disallowed tags) in `chunk`.
+        let mut text_from: Option<usize> = None;
+        loop {
+            if let Some(raw) = self.raw_text.as_mut() {
+                match raw.close_re.find(tokens.rest()) {
+                    Some(m) => {
+                        tokens.pos += m.end();
+                        raw.end = offset + tokens.pos;
+                        self.finish_raw_text();
+                    }
+                    None => {
+                        raw.end = offset + chunk.len();
+                        break;
+                    }
+                }
+            }
+            let token_start = tokens.pos;
+            let Some(token) = tokens.next() else { break };
+            let token_range = offset + token_start..offset + tokens.pos;
+            match token {
+                HtmlToken::Open { name, .. }
+                    if self.options.raw_text_elements != RawTextPolicy::Allow
+                        && raw_text_close_re(name).is_some() =>
+                {
+                    self.flush_text(chunk, offset, text_from.take(), token_start);
+                    self.raw_text = Some(RawTextElement {
+                        close_re: raw_text_close_re(name).unwrap(),
+                        start: token_range.start,
+                        end: token_range.end,
+                        depth: self.stack.len(),
+                    });
+                    if chunk[..tokens.pos].ends_with("/>") {
+                        self.finish_raw_text();
+                    }
+                }
+                HtmlToken::Open { name, props, self_closing, .. } if self.is_allowed(name) => {
+                    self.flush_text(chunk, offset, text_from.take(), token_start);
+                    let tag = self.string(name, offset + token_start + 1);
+                    let node = self.spanned(element(tag, props), token_range);
+                    if self_closing || render_html::VOID_TAGS.contains(&name) {
+                        self.append(node);
+                    } else {
+                        self.open(node, FrameKind::Html);
+                    }
+                }
+                HtmlToken::Comment(content) => {
+                    self.flush_text(chunk, offset, text_from.take(), token_start);
+                    let content = self.string(content, offset + token_start + 4);
+                    let node = self.spanned(Node::Comment { content, span: None }, token_range);
+                    self.append(node);
+                }
+                HtmlToken::Close { name, .. } if self.is_allowed(name) => {
+                    self.flush_text(chunk, offset, text_from.take(), token_start);
+                    // End tags with no matching open element are dropped.
+                    self.close_html(name, token_range.end);
+                }
+                _ => {
+                    text_from.get_or_insert(token_start);
+                }
+            }
+        }
+        self.flush_text(chunk, offset, text_from, tokens.pos);
+    }
+
+    fn is_allowed(&self, name: &str) -> bool {
+        self.options.allowed_tags.iter().any(|tag| tag == name)
+    }
+
+    /// Appends `chunk[from..to]` as a text node unless it is blank.
+    fn flush_text(&mut self, chunk: &str, offset: usize, from: Option<usize>, to: usize) {
+        if let Some(from) = from {
+            let content = &chunk[from..to];
+            if !content.trim().is_empty() {
+                let node = self.spanned(text_node(self.string(content, offset + from)), offset + from..offset + to);
+                self.append(node);
+            }
+        }
+    }
+
+    fn finish_raw_text(&mut self) {
+        if let Some(raw) = self.raw_text.take() {
+            let source = self.source;
+            let content = source[raw.start..raw.end].trim_end();
+            let node = match self.options.raw_text_elements {
+                RawTextPolicy::Escape => text_node(content),
+                RawTextPolicy::Raw => Node::Raw { content: content.into(), format: "html".into(), span: None },
+                RawTextPolicy::Allow | RawTextPolicy::Remove => return,
+            };
+            let node = self.spanned(node, raw.start..raw.start + content.len());
+            self.append(node);
+        }
+    }
+}
+
+fn is_block(tag: &Tag) -> bool {
+    matches!(
+        tag,
+        Tag::Paragraph
+            | Tag::Heading { .. }
+            | Tag::BlockQuote(_)
+            | Tag::CodeBlock(_)
+            | Tag::List(_)
+            | Tag::Item
+            | Tag::FootnoteDefinition(_)
+            | Tag::Table(_)
+            | Tag::TableHead
+            | Tag::TableRow
+            | Tag::TableCell
+    )
+}
+
+/// Maps a markdown container to the element that represents it.
+fn element_for_tag<'a, S: SourceStr<'a>>(tag: Tag, options: &TranspileOptions) -> Node<S> {
+    match tag {
+        Tag::Heading { level, id, classes, .. } => {
+            const HEADINGS: [&str; 6] = ["h1", "h2", "h3", "h4", "h5", "h6"];
+            let mut props = IndexMap::new();
+            if let Some(id) = id {
+                props.insert("id".to_string(), serde_json::Value::String(id.to_string()));
+            }
+            if !classes.is_empty() {
+                props.insert("className".to_string(), serde_json::Value::String(classes.join(" ")));
+            }
+            element(HEADINGS[level as usize - 1], props)
+        }
+        Tag::Paragraph => element("p", IndexMap::new()),
+        Tag::Emphasis => element("em", IndexMap::new()),
+        Tag::Strong => element("strong", IndexMap::new()),
+        Tag::Link { dest_url, title, .. } => {
+            let mut props = IndexMap::new();
+            props.insert("href".to_string(), serde_json::Value::String(dest_url.to_string()));
+            if !title.is_empty() {
+                props.insert("title".to_string(), serde_json::Value::String(title.to_string()));
+            }
+            element("a", props)
+        },
+        Tag::Image { dest_url, title, .. } => {
+            let mut props = IndexMap::new();
+            props.insert("src".to_string(), serde_json::Value::String(dest_url.to_string()));
+            if !title.is_empty() {
+                props.insert("title".to_string(), serde_json::Value::String(title.to_string()));
+            }
+            element("img", props)
+        },
+        Tag::BlockQuote(_) => element("blockquote", IndexMap::new()),
+        // Wrapped in `pre` once the block ends.
+        Tag::CodeBlock(kind) => {
+            let mut props = IndexMap::new();
+            if let CodeBlockKind::Fenced(info) = kind {
+                if let Some(lang) = info.split_whitespace().next() {
+                    props.insert("className".to_string(), serde_json::Value::String(format!("language-{}", lang)));
+                }
+            }
+            element("code", props)
+        },
+        Tag::List(Some(start)) => {
+            let mut props = IndexMap::new();
+            if start != 1 {
+                props.insert("start".to_string(), serde_json::Value::from(start));
+            }
+            element("ol", props)
+        },
+        Tag::List(None) => element("ul", IndexMap::new()),
+        Tag::Item => element("li", IndexMap::new()),
+        Tag::Table(_) => element("table", IndexMap::new()),
+        Tag::TableHead => element("thead", IndexMap::new()),
+        Tag::TableRow => element("tr", IndexMap::new()),
+        Tag::TableCell => element("td", IndexMap::new()),
+        Tag::Strikethrough => element("del", IndexMap::new()),
+        Tag::FootnoteDefinition(label) => {
+            let mut props = IndexMap::new();
+            let names = &options.footnote_names;
+            props.insert("id".to_string(), serde_json::Value::String(format!("{}{}", names.id_prefix, label)));
+            props.insert("className".to_string(), serde_json::Value::String(names.definition_class.clone()));
+            element("div", props)
+        },
+        _ => element("div", IndexMap::new()),
+    }
+}
+
+/// Final touches for elements whose shape depends on their content.
+fn finish_markdown_element<'a, S: SourceStr<'a>>(mut node: Node<S>) -> Node<S> {
+    let Node::Element { tag, props, children, span, .. } = &mut node else {
+        return node;
+    };
+    match &**tag {
+        // Code blocks are the only markdown-opened `code` elements; inline
+        // code arrives as a single event.
+        "code" => {
+            let mut pre = element("pre", IndexMap::new());
+            if let Some(pos) = props.shift_remove("data-sourcepos") {
+                pre = pre.prop("data-sourcepos", pos);
+            }
+            if let Some(span) = *span {
+                pre = pre.with_span(span);
+            }
+            pre.child(node)
+        }
+        "img" => {
+            let alt = text_content(children);
+            children.clear();
+            props.insert("alt".to_string(), serde_json::Value::String(alt));
+            node
+        }
+        // pulldown-cmark puts header cells straight into the head; the DOM
+        // needs a row around them.
+        "thead" => {
+            let mut row = element("tr", IndexMap::new()).with_children(std::mem::take(children));
+            if let Some(span) = *span {
+                row = row.with_span(span);
+            }
+            children.push(row);
+            node
+        }
+        _ => node,
+    }
+}
+
+/// Concatenated text of `nodes` and their descendants.
+fn text_content<S: Deref<Target = str>>(nodes: &[Node<S>]) -> String {
+    let mut out = String::new();
+    for node in nodes {
+        match node {
+            Node::Element { children, .. } | Node::Fragment { children } => {
+                out.push_str(&text_content(children));
+            }
+            Node::Text { content, .. } => out.push_str(content),
+            Node::Comment { .. } | Node::Raw { .. } => {}
+        }
+    }
+    out
+}
+
+pub fn parse(markdown: &str, options: &TranspileOptions) -> Vec<Node> {
+    let markdown = preprocessed(markdown, options);
+    let mut nodes = build(&markdown, options);
+    finish_parse(&mut nodes, options);
+    nodes
+}
+
+/// `markdown` after the `preprocess` hook, if any.
+fn preprocessed<'m>(markdown: &'m str, options: &TranspileOptions) -> Cow<'m, str> {
+    match &options.preprocess {
+        Some(preprocess) => Cow::Owned(preprocess.preprocess(markdown)),
+        None => Cow::Borrowed(markdown),
+    }
+}
+
+/// Runs the post-build passes of `parse`.
+fn finish_parse(nodes: &mut Vec<Node>, options: &TranspileOptions) {
+    layout_footnotes(nodes, options.footnotes, &options.footnote_names);
+    route_code(nodes, &options.code_handlers);
+    if options.normalize {
+        normalize(nodes);
+    }
+    clean_whitespace(nodes, &options.whitespace);
+    apply_typography(nodes, options);
+    rewrite_text(nodes, options.text_hook.as_deref());
+    if options.heading_ids {
+        assign_heading_ids(nodes, options.slugger.as_deref().unwrap_or(&GithubSlugger));
+    }
+    for transform in &options.transforms {
+        transform.apply(nodes);
+    }
+    rewrite_elements(nodes, options);
+    if options.content_ids {
+        assign_content_ids(nodes);
+    }
+    if let Some(strategy) = options.keys {
+        assign_keys(nodes, strategy);
     }
-    
-    None
 }
 
-pub fn parse(markdown: &str, options: &TranspileOptions) -> Vec<Node> {
-    let mut p_options = Options::empty();
-    p_options.insert(Options::ENABLE_TABLES);
-    p_options.insert(Options::ENABLE_STRIKETHROUGH);
-    p_options.insert(Options::ENABLE_TASKLISTS);
-    p_options.insert(Options::ENABLE_FOOTNOTES);
-    p_options.insert(Options::ENABLE_SMART_PUNCTUATION);
-    
-    let parser = Parser::new_ext(markdown, p_options);
-    let mut stack: Vec<Node> = Vec::new();
-    let mut root: Vec<Node> = Vec::new();
+/// Like `parse`, but text, tag names and other strings borrow from
+/// `markdown` wherever they appear in it verbatim, saving an allocation
+/// per node. `url_schemes` is enforced, but the other steps `parse` runs
+/// around building the tree work on owned data and are skipped:
+/// `preprocess`, footnote layout, code handlers, `normalize`, whitespace,
+/// typography, `text_hook`, `heading_ids`, `transforms`, the other element
+/// rewrites (`tag_map`, `class_map`, `prop_style`, `base_url`, link and
+/// image rewriters, `id_prefix`), `content_ids` and `keys`. See
+/// `BorrowedNode::into_owned`.
+pub fn parse_borrowed<'a>(markdown: &'a str, options: &TranspileOptions) -> Vec<BorrowedNode<'a>> {
+    let mut nodes = build(markdown, options);
+    check_url_schemes(&mut nodes, &options.url_schemes);
+    nodes
+}
 
-    for event in parser {
-        match event {
-            Event::Start(tag) => {
-                let node = match tag {
-                    Tag::Heading { level, .. } => Node::Element {
-                        tag: format!("h{}", level as u32),
-                        props: HashMap::new(),
-                        children: Vec::new(),
-                    },
-                    Tag::Paragraph => Node::Element {
-                        tag: "p".to_string(),
-                        props: HashMap::new(),
-                        children: Vec::new(),
-                    },
-                    Tag::Emphasis => Node::Element {
-                        tag: "em".to_string(),
-                        props: HashMap::new(),
-                        children: Vec::new(),
-                    },
-                    Tag::Strong => Node::Element {
-                        tag: "strong".to_string(),
-                        props: HashMap::new(),
-                        children: Vec::new(),
-                    },
-                    Tag::Link { dest_url, .. } => {
-                        let mut props = HashMap::new();
-                        props.insert("href".to_string(), serde_json::Value::String(dest_url.to_string()));
-                        Node::Element {
-                            tag: "a".to_string(),
-                            props,
-                            children: Vec::new(),
-                        }
-                    },
-                    Tag::List(first) => Node::Element {
-                        tag: if first.is_some() { "ol".to_string() } else { "ul".to_string() },
-                        props: HashMap::new(),
-                        children: Vec::new(),
-                    },
-                    Tag::Item => Node::Element {
-                        tag: "li".to_string(),
-                        props: HashMap::new(),
-                        children: Vec::new(),
-                    },
-                    Tag::Table(_) => Node::Element {
-                        tag: "table".to_string(),
-                        props: HashMap::new(),
-                        children: Vec::new(),
-                    },
-                    Tag::TableHead => Node::Element {
-                        tag: "thead".to_string(),
-                        props: HashMap::new(),
-                        children: Vec::new(),
-                    },
-                    Tag::TableRow => Node::Element {
-                        tag: "tr".to_string(),
-                        props: HashMap::new(),
-                        children: Vec::new(),
-                    },
-                    Tag::TableCell => Node::Element {
-                        tag: "td".to_string(),
-                        props: HashMap::new(),
-                        children: Vec::new(),
-                    },
-                    Tag::Strikethrough => Node::Element {
-                        tag: "del".to_string(),
-                        props: HashMap::new(),
-                        children: Vec::new(),
-                    },
-                    Tag::FootnoteDefinition(label) => {
-                        let mut props = HashMap::new();
-                        props.insert("id".to_string(), serde_json::Value::String(format!("fn-{}", label)));
-                        props.insert("className".to_string(), serde_json::Value::String("footnote-definition".to_string()));
-                        Node::Element {
-                            tag: "div".to_string(),
-                            props,
-                            children: Vec::new(),
-                        }
-                    },
-                    _ => Node::Element {
-                        tag: "div".to_string(),
-                        props: HashMap::new(),
-                        children: Vec::new(),
-                    },
-                };
-                stack.push(node);
-            }
-            Event::End(_) => {
-                if let Some(node) = stack.pop() {
-                    if stack.is_empty() {
-                        root.push(node);
-                    } else {
-                        let parent = stack.last_mut().unwrap();
-                        if let Node::Element { children, .. } = parent {
-                            children.push(node);
-                        }
-                    }
-                }
-            }
-            Event::Text(text) => {
-                let node = Node::Text { content: text.to_string() };
-                if stack.is_empty() {
-                    root.push(node);
-                } else {
-                    let parent = stack.last_mut().unwrap();
-                    if let Node::Element { children, .. } = parent {
-                        children.push(node);
-                    }
-                }
-            }
-            Event::Code(code) => {
-                let node = Node::Element {
-                    tag: "code".to_string(),
-                    props: HashMap::new(),
-                    children: vec![Node::Text { content: code.to_string() }],
-                };
-                if stack.is_empty() {
-                    root.push(node);
-                } else {
-                    let parent = stack.last_mut().unwrap();
-                    if let Node::Element { children, .. } = parent {
-                        children.push(node);
-                    }
-                }
-            }
-            Event::FootnoteReference(label) => {
-                let mut props = HashMap::new();
-                props.insert("href".to_string(), serde_json::Value::String(format!("#fn-{}", label)));
-                props.insert("className".to_string(), serde_json::Value::String("footnote-ref".to_string()));
-                let node = Node::Element {
-                    tag: "sup".to_string(),
-                    props: HashMap::new(),
-                    children: vec![Node::Element {
-                        tag: "a".to_string(),
-                        props,
-                        children: vec![Node::Text { content: label.to_string() }],
-                    }],
-                };
-                if stack.is_empty() {
-                    root.push(node);
-                } else {
-                    let parent = stack.last_mut().unwrap();
-                    if let Node::Element { children, .. } = parent {
-                        children.push(node);
-                    }
-                }
-            }
-            Event::Html(html) | Event::InlineHtml(html) => {
-                if let Some((tag_name, props, is_self_closing)) = parse_html_tag(&html) {
-                    if options.allowed_tags.contains(&tag_name) {
-                        if html.starts_with("</") {
-                            // Closing tag
-                            if let Some(node) = stack.pop() {
-                                if stack.is_empty() {
-                                    root.push(node);
-                                } else {
-                                    let parent = stack.last_mut().unwrap();
-                                    if let Node::Element { children, .. } = parent {
-                                        children.push(node);
-                                    }
-                                }
-                            }
-                        } else {
-                            // Opening tag
-                            let node = Node::Element {
-                                tag: tag_name,
-                                props,
-                                children: Vec::new(),
-                            };
-                            if is_self_closing {
-                                if stack.is_empty() {
-                                    root.push(node);
-                                } else {
-                                    let parent = stack.last_mut().unwrap();
-                                    if let Node::Element { children, .. } = parent {
-                                        children.push(node);
-                                    }
-                                }
-                            } else {
-                                stack.push(node);
-                            }
-                        }
-                    } else {
-                        // Tag not allowed, treat as text
-                        let node = Node::Text { content: html.to_string() };
-                        if stack.is_empty() {
-                            root.push(node);
-                        } else {
-                            let parent = stack.last_mut().unwrap();
-                            if let Node::Element { children, .. } = parent {
-                                children.push(node);
-                            }
-                        }
-                    }
-                } else {
-                    // Treat unknown HTML as text
-                    let node = Node::Text { content: html.to_string() };
-                    if stack.is_empty() {
-                        root.push(node);
-                    } else {
-                        let parent = stack.last_mut().unwrap();
-                        if let Node::Element { children, .. } = parent {
-                            children.push(node);
-                        }
-                    }
-                }
-            }
-            Event::SoftBreak | Event::HardBreak => {
-                let node = Node::Text { content: "\n".to_string() };
-                if !stack.is_empty() {
-                    let parent = stack.last_mut().unwrap();
-                    if let Node::Element { children, .. } = parent {
-                        children.push(node);
-                    }
-                }
-            }
-            _ => {}
+fn build<'a, S: SourceStr<'a>>(markdown: &'a str, options: &TranspileOptions) -> Vec<Node<S>> {
+    build_limited(markdown, options).0
+}
+
+/// Builds the tree of `markdown` within `max_input_bytes` and `timeout_ms`,
+/// reporting which limit (if any) stopped it.
+fn build_limited<'a, S: SourceStr<'a>>(markdown: &'a str, options: &TranspileOptions) -> (Vec<Node<S>>, Option<Truncation>) {
+    let (markdown, mut truncated) = limit_input(markdown, options.max_input_bytes);
+    let mut deadline = Deadline::new(options.timeout_ms);
+    let mut builder = TreeBuilder::new(markdown, options);
+    for (event, range) in Parser::new_ext(markdown, options.parser_options()).into_offset_iter() {
+        if deadline.passed() {
+            truncated = Some(Truncation::Timeout);
+            break;
         }
+        builder.event(event, range);
     }
-    
-    root
+    (builder.finish(), truncated)
 }
 
 #[cfg(feature = "wasm")]
@@ -297,20 +1598,212 @@ mod wasm {
     use super::*;
     use wasm_bindgen::prelude::*;
 
-    #[wasm_bindgen]
-    pub fn transpile(markdown: &str, allowed_tags: Vec<String>) -> Result<JsValue, JsValue> {
-        let options = TranspileOptions { allowed_tags };
-        let ast = parse(markdown, &options);
+    // Declarations of the exports below, which wasm-bindgen would type as
+    // `any`. `ast.d.ts` is written by `typescript_definitions` (`npm run
+    // types`).
+    #[wasm_bindgen(typescript_custom_section)]
+    const TYPES: &str = r#"
+import type { DocStats, Node, TranspileOptions } from "./ast";
+export type { DocStats, Node, TranspileOptions } from "./ast";
+
+/** `TranspileOptions` fields to set, the rest keeping their defaults, or just the allowed tags. */
+export type TranspileInput = Partial<TranspileOptions> | string[];
+
+export function transpile(markdown: string, options?: TranspileInput): Node[];
+
+/** Like `transpile`, but returns the AST as a JSON string. */
+export function transpile_json(markdown: string, options?: TranspileInput): string;
+
+/** Word count, reading time and other statistics of `markdown`. */
+export function stats(markdown: string, options?: TranspileInput): DocStats;
+
+/** Encoding of `transpile_buffer`; `"msgpack"` needs the `msgpack` feature. */
+export type BufferFormat = "json" | "compact" | "msgpack";
+
+/**
+ * The AST encoded as UTF-8 JSON (the default), compact JSON or MessagePack, in a
+ * fresh `ArrayBuffer` a worker can transfer to the main thread without copying.
+ */
+export function transpile_buffer(markdown: string, options?: TranspileInput, format?: BufferFormat): ArrayBuffer;
+
+/** Parses a document arriving in chunks; see `StreamingParser`. */
+export class Transpiler {
+  free(): void;
+  constructor(options?: TranspileInput);
+  /** Appends `chunk` and returns the top-level nodes it completed. */
+  push(chunk: string): Node[];
+  /** Returns the remaining nodes. The transpiler cannot be used afterwards. */
+  finish(): Node[];
+}
+"#;
+
+    #[cfg(feature = "msgpack")]
+    #[wasm_bindgen(typescript_custom_section)]
+    const MSGPACK_TYPES: &str = r#"
+/** Like `transpile`, but returns the AST as MessagePack bytes. */
+export function transpile_msgpack(markdown: string, options?: TranspileInput): Uint8Array;
+"#;
+
+    /// Reads `options`: a `TranspileOptions` object with the Rust field
+    /// names, an array of allowed tags (the original signature), or
+    /// `undefined`/`null` for the defaults.
+    fn options(options: Option<JsValue>) -> Result<TranspileOptions, JsValue> {
+        let Some(options) = options.filter(|options| !options.is_null()) else {
+            return Ok(TranspileOptions::default());
+        };
+        if js_sys::Array::is_array(&options) {
+            let allowed_tags: Vec<String> = serde_wasm_bindgen::from_value(options)?;
+            return Ok(TranspileOptions { allowed_tags, ..Default::default() });
+        }
+        // Through `serde_json::Value`: serde-wasm-bindgen reads only the
+        // known fields of an object, so unknown ones would pass silently.
+        let options: serde_json::Value = serde_wasm_bindgen::from_value(options)?;
+        serde_json::from_value(options).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen(skip_typescript)]
+    pub fn transpile(markdown: &str, options: Option<JsValue>) -> Result<JsValue, JsValue> {
+        let ast = parse(markdown, &self::options(options)?);
         serde_wasm_bindgen::to_value(&ast).map_err(|e| JsValue::from_str(&e.to_string()))
     }
+
+    /// Like `transpile`, but returns the AST as a JSON string, for
+    /// `JSON.parse` or sending on as is. Faster than building the JS
+    /// objects through serde-wasm-bindgen for large documents.
+    #[wasm_bindgen(skip_typescript)]
+    pub fn transpile_json(markdown: &str, options: Option<JsValue>) -> Result<String, JsValue> {
+        let ast = parse(markdown, &self::options(options)?);
+        serde_json::to_string(&ast).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Like `transpile`, but returns the AST as MessagePack bytes.
+    #[cfg(feature = "msgpack")]
+    #[wasm_bindgen(skip_typescript)]
+    pub fn transpile_msgpack(markdown: &str, options: Option<JsValue>) -> Result<Vec<u8>, JsValue> {
+        Ok(to_msgpack(&parse(markdown, &self::options(options)?)))
+    }
+
+    /// Word count, reading time and other `DocStats` of `markdown`.
+    #[wasm_bindgen(js_name = stats, skip_typescript)]
+    pub fn markdown_stats(markdown: &str, options: Option<JsValue>) -> Result<JsValue, JsValue> {
+        let stats = stats(&parse(markdown, &self::options(options)?));
+        serde_wasm_bindgen::to_value(&stats).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen(skip_typescript)]
+    pub fn transpile_buffer(
+        markdown: &str,
+        options: Option<JsValue>,
+        format: Option<String>,
+    ) -> Result<js_sys::ArrayBuffer, JsValue> {
+        let ast = parse(markdown, &self::options(options)?);
+        let bytes = match format.as_deref().unwrap_or("json") {
+            "json" => serde_json::to_vec(&ast).map_err(|e| JsValue::from_str(&e.to_string()))?,
+            "compact" => to_compact_json(&ast, &CompactOptions::default()).into_bytes(),
+            #[cfg(feature = "msgpack")]
+            "msgpack" => to_msgpack(&ast),
+            other => return Err(JsValue::from_str(&format!("unsupported format: {}", other))),
+        };
+        // A copy out of wasm memory, so the buffer can be transferred.
+        Ok(js_sys::Uint8Array::from(bytes.as_slice()).buffer())
+    }
+
+    /// `StreamingParser` for JS, so the top of a large document can be
+    /// rendered before the rest has arrived. Like it, leaves footnote
+    /// definitions in place whatever `footnotes` says and does not add
+    /// `heading_ids`.
+    #[wasm_bindgen(skip_typescript)]
+    pub struct Transpiler {
+        parser: StreamingParser,
+    }
+
+    #[wasm_bindgen]
+    impl Transpiler {
+        #[wasm_bindgen(constructor)]
+        pub fn new(options: Option<JsValue>) -> Result<Transpiler, JsValue> {
+            Ok(Transpiler { parser: StreamingParser::new(self::options(options)?) })
+        }
+
+        pub fn push(&mut self, chunk: &str) -> Result<JsValue, JsValue> {
+            serde_wasm_bindgen::to_value(&self.parser.push(chunk)).map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+
+        pub fn finish(self) -> Result<JsValue, JsValue> {
+            serde_wasm_bindgen::to_value(&self.parser.finish()).map_err(|e| JsValue::from_str(&e.to_string()))
+        }
+    }
 }
 
 #[cfg(feature = "android")]
 mod android {
     use super::*;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
     use jni::JNIEnv;
     use jni::objects::{JClass, JString};
-    use jni::sys::jstring;
+    use jni::sys::{jobject, jstring};
+    #[cfg(feature = "msgpack")]
+    use jni::sys::jbyteArray;
+
+    /// Why a native call failed; thrown to Java as an exception.
+    enum Failure {
+        Jni(jni::errors::Error),
+        /// Malformed options or allowed tags: `IllegalArgumentException`.
+        InvalidArgument(String),
+        /// A panic or another bug: `RuntimeException`.
+        Internal(String),
+    }
+
+    impl From<jni::errors::Error> for Failure {
+        fn from(error: jni::errors::Error) -> Self {
+            Failure::Jni(error)
+        }
+    }
+
+    /// Runs `body`, turning an error or a panic into a Java exception and
+    /// a null return, so bad input never takes the app down.
+    fn guard(env: &mut JNIEnv, body: impl FnOnce(&mut JNIEnv) -> Result<jobject, Failure>) -> jobject {
+        let result = catch_unwind(AssertUnwindSafe(|| body(env))).unwrap_or_else(|panic| {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            Err(Failure::Internal(format!("md2jsx panicked: {}", message)))
+        });
+        let failure = match result {
+            Ok(object) => return object,
+            Err(failure) => failure,
+        };
+        // A failed JNI call may already have an exception pending.
+        if !env.exception_check().unwrap_or(false) {
+            let (class, message) = match failure {
+                Failure::Jni(jni::errors::Error::NullPtr(what) | jni::errors::Error::NullDeref(what)) => {
+                    ("java/lang/NullPointerException", format!("null argument ({})", what))
+                }
+                Failure::Jni(error) => ("java/lang/RuntimeException", error.to_string()),
+                Failure::InvalidArgument(message) => ("java/lang/IllegalArgumentException", message),
+                Failure::Internal(message) => ("java/lang/RuntimeException", message),
+            };
+            let _ = env.throw_new(class, message);
+        }
+        std::ptr::null_mut()
+    }
+
+    fn string(env: &mut JNIEnv, value: &JString) -> Result<String, Failure> {
+        Ok(env.get_string(value)?.into())
+    }
+
+    /// Options with only `allowed_tags` set, from a JSON array.
+    fn allowed_tags(env: &mut JNIEnv, allowed_tags_json: &JString) -> Result<TranspileOptions, Failure> {
+        let json = string(env, allowed_tags_json)?;
+        let allowed_tags = serde_json::from_str(&json)
+            .map_err(|e| Failure::InvalidArgument(format!("invalid allowed tags: {}", e)))?;
+        Ok(TranspileOptions { allowed_tags, ..Default::default() })
+    }
+
+    fn ast_json(nodes: &[Node]) -> Result<String, Failure> {
+        serde_json::to_string(nodes).map_err(|e| Failure::Internal(e.to_string()))
+    }
 
     #[no_mangle]
     pub extern "system" fn Java_com_clevertree_md2jsx_MarkdownParser_nativeParse(
@@ -319,15 +1812,98 @@ mod android {
         input: JString,
         allowed_tags_json: JString,
     ) -> jstring {
-        let input: String = env.get_string(&input).expect("Couldn't get java string!").into();
-        let allowed_tags_json: String = env.get_string(&allowed_tags_json).expect("Couldn't get java string!").into();
-        let allowed_tags: Vec<String> = serde_json::from_str(&allowed_tags_json).unwrap_or_default();
-        
-        let options = TranspileOptions { allowed_tags };
-        let ast = parse(&input, &options);
-        let result_json = serde_json::to_string(&ast).unwrap();
-        
-        env.new_string(result_json).expect("Couldn't create java string!").into_raw()
+        guard(&mut env, |env| {
+            let input = string(env, &input)?;
+            let options = allowed_tags(env, &allowed_tags_json)?;
+            let result_json = ast_json(&parse(&input, &options))?;
+            Ok(env.new_string(result_json)?.into_raw())
+        })
+    }
+
+    /// Like `nativeParse`, with every `TranspileOptions` field read from
+    /// `options_json` (see `TranspileOptions::from_json`). Invalid options
+    /// throw `IllegalArgumentException`.
+    #[no_mangle]
+    pub extern "system" fn Java_com_clevertree_md2jsx_MarkdownParser_nativeParseWithOptions(
+        mut env: JNIEnv,
+        _class: JClass,
+        input: JString,
+        options_json: JString,
+    ) -> jstring {
+        guard(&mut env, |env| {
+            let input = string(env, &input)?;
+            let options_json = string(env, &options_json)?;
+            let options = TranspileOptions::from_json(&options_json)
+                .map_err(|e| Failure::InvalidArgument(format!("invalid options: {}", e)))?;
+            let result_json = ast_json(&parse(&input, &options))?;
+            Ok(env.new_string(result_json)?.into_raw())
+        })
+    }
+
+    /// Like `nativeParse`, but returns the compact encoding with bare text.
+    #[no_mangle]
+    pub extern "system" fn Java_com_clevertree_md2jsx_MarkdownParser_nativeParseCompact(
+        mut env: JNIEnv,
+        _class: JClass,
+        input: JString,
+        allowed_tags_json: JString,
+    ) -> jstring {
+        guard(&mut env, |env| {
+            let input = string(env, &input)?;
+            let options = allowed_tags(env, &allowed_tags_json)?;
+            let ast = parse(&input, &options);
+            let result_json = to_compact_json(&ast, &CompactOptions { bare_text: true, ..Default::default() });
+            Ok(env.new_string(result_json)?.into_raw())
+        })
+    }
+
+    /// Like `nativeParse`, but returns HTML for `HtmlCompat.fromHtml`.
+    #[no_mangle]
+    pub extern "system" fn Java_com_clevertree_md2jsx_MarkdownParser_nativeParseHtml(
+        mut env: JNIEnv,
+        _class: JClass,
+        input: JString,
+        allowed_tags_json: JString,
+    ) -> jstring {
+        guard(&mut env, |env| {
+            let input = string(env, &input)?;
+            let options = allowed_tags(env, &allowed_tags_json)?;
+            let html = render_android_html(&parse(&input, &options));
+            Ok(env.new_string(html)?.into_raw())
+        })
+    }
+
+    /// Like `nativeParse`, but returns the `to_compose_spec` JSON.
+    #[no_mangle]
+    pub extern "system" fn Java_com_clevertree_md2jsx_MarkdownParser_nativeParseCompose(
+        mut env: JNIEnv,
+        _class: JClass,
+        input: JString,
+        allowed_tags_json: JString,
+    ) -> jstring {
+        guard(&mut env, |env| {
+            let input = string(env, &input)?;
+            let options = allowed_tags(env, &allowed_tags_json)?;
+            let json = to_compose_spec(&parse(&input, &options)).to_string();
+            Ok(env.new_string(json)?.into_raw())
+        })
+    }
+
+    /// Like `nativeParse`, but returns the AST as MessagePack bytes.
+    #[cfg(feature = "msgpack")]
+    #[no_mangle]
+    pub extern "system" fn Java_com_clevertree_md2jsx_MarkdownParser_nativeParseMsgpack(
+        mut env: JNIEnv,
+        _class: JClass,
+        input: JString,
+        allowed_tags_json: JString,
+    ) -> jbyteArray {
+        guard(&mut env, |env| {
+            let input = string(env, &input)?;
+            let options = allowed_tags(env, &allowed_tags_json)?;
+            let bytes = to_msgpack(&parse(&input, &options));
+            Ok(env.byte_array_from_slice(&bytes)?.into_raw())
+        })
     }
 }
 
@@ -336,26 +1912,13 @@ mod tests {
     use super::*;
 
     fn find_node<'a>(nodes: &'a [Node], tag_name: &str) -> Option<&'a Node> {
-        for node in nodes {
-            match node {
-                Node::Element { tag, children, .. } => {
-                    if tag == tag_name {
-                        return Some(node);
-                    }
-                    if let Some(found) = find_node(children, tag_name) {
-                        return Some(found);
-                    }
-                }
-                _ => {}
-            }
-        }
-        None
+        find(nodes, |node| node.tag() == Some(tag_name))
     }
 
     #[test]
     fn test_gfm_footnotes() {
         let markdown = "Here is a footnote[^1]\n\n[^1]: This is the footnote content.";
-        let options = TranspileOptions { allowed_tags: vec![] };
+        let options = TranspileOptions::default();
         let ast = parse(markdown, &options);
         println!("AST: {}", serde_json::to_string_pretty(&ast).unwrap());
         
@@ -378,16 +1941,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_footnote_names() {
+        let names = FootnoteNames {
+            id_prefix: "post-7-note-".to_string(),
+            ref_class: "note-ref".to_string(),
+            definition_class: "note".to_string(),
+        };
+        let options = TranspileOptions::builder().footnote_names(names).build();
+        let ast = parse("Text[^a]\n\n[^a]: Note.", &options);
+
+        let link = Node::element("a").prop("href", "#post-7-note-a").prop("className", "note-ref").child(Node::text("a"));
+        assert_eq!(find_node(&ast, "sup"), Some(&Node::element("sup").child(link)));
+        let div = Node::element("div").prop("id", "post-7-note-a").prop("className", "note")
+            .child(Node::element("p").child(Node::text("Note.")));
+        assert_eq!(find_node(&ast, "div"), Some(&div));
+    }
+
     #[test]
     fn test_basic_markdown() {
         let markdown = "# Hello\nThis is **bold**";
-        let options = TranspileOptions { allowed_tags: vec![] };
+        let options = TranspileOptions::default();
         let ast = parse(markdown, &options);
         
         assert_eq!(ast.len(), 2);
         if let Node::Element { tag, children, .. } = &ast[0] {
             assert_eq!(tag, "h1");
-            assert_eq!(children[0], Node::Text { content: "Hello".to_string() });
+            assert_eq!(children[0], Node::text("Hello"));
         } else {
             panic!("Expected h1 element");
         }
@@ -396,7 +1976,7 @@ mod tests {
     #[test]
     fn test_html_tags() {
         let markdown = "Hello <VideoPlayer src=\"test.mp4\" /> world";
-        let options = TranspileOptions { allowed_tags: vec!["VideoPlayer".to_string()] };
+        let options = TranspileOptions { allowed_tags: vec!["VideoPlayer".to_string()], ..Default::default() };
         let ast = parse(markdown, &options);
         
         let node = find_node(&ast, "VideoPlayer").expect("Should find VideoPlayer node");
@@ -408,7 +1988,7 @@ mod tests {
     #[test]
     fn test_nested_html() {
         let markdown = "<div>\n\n# Inside\n\n</div>";
-        let options = TranspileOptions { allowed_tags: vec!["div".to_string()] };
+        let options = TranspileOptions { allowed_tags: vec!["div".to_string()], ..Default::default() };
         let ast = parse(markdown, &options);
         
         assert!(find_node(&ast, "div").is_some());
@@ -417,7 +1997,7 @@ mod tests {
     #[test]
     fn test_allowed_tags_filtering() {
         let markdown = "<Allowed>Keep</Allowed><Forbidden>Drop</Forbidden>";
-        let options = TranspileOptions { allowed_tags: vec!["Allowed".to_string()] };
+        let options = TranspileOptions { allowed_tags: vec!["Allowed".to_string()], ..Default::default() };
         let ast = parse(markdown, &options);
         
         assert!(find_node(&ast, "Allowed").is_some());
@@ -427,7 +2007,7 @@ mod tests {
     #[test]
     fn test_gfm_table() {
         let markdown = "| Header |\n| --- |\n| Cell |";
-        let options = TranspileOptions { allowed_tags: vec![] };
+        let options = TranspileOptions::default();
         let ast = parse(markdown, &options);
         
         assert!(find_node(&ast, "table").is_some());
@@ -438,9 +2018,367 @@ mod tests {
     #[test]
     fn test_strikethrough() {
         let markdown = "~~deleted~~";
-        let options = TranspileOptions { allowed_tags: vec![] };
+        let options = TranspileOptions::default();
         let ast = parse(markdown, &options);
         
         assert!(find_node(&ast, "del").is_some());
     }
+
+    #[test]
+    fn test_raw_text_elements_removed() {
+        let markdown = "<script>\nalert(1)\n</script>\n\nHi <script>steal()</script> there\n\n<style>p { color: red }</style>";
+        let options = TranspileOptions::untrusted(vec!["script".to_string(), "style".to_string()]);
+        let ast = parse(markdown, &options);
+        let json = serde_json::to_string(&ast).unwrap();
+
+        assert!(find_node(&ast, "script").is_none());
+        assert!(find_node(&ast, "style").is_none());
+        assert!(!json.contains("alert") && !json.contains("steal") && !json.contains("color"));
+        assert!(json.contains("Hi ") && json.contains(" there"));
+    }
+
+    #[test]
+    fn test_raw_text_elements_escaped() {
+        let markdown = "Hi <script>*steal*()</script>";
+        let options = TranspileOptions {
+            allowed_tags: vec!["script".to_string()],
+            raw_text_elements: RawTextPolicy::Escape,
+            ..Default::default()
+        };
+        let ast = parse(markdown, &options);
+
+        assert!(find_node(&ast, "script").is_none());
+        assert!(find_node(&ast, "em").is_none());
+        if let Node::Element { children, .. } = &ast[0] {
+            assert_eq!(children[1], Node::text("<script>*steal*()</script>"));
+        }
+    }
+
+    #[test]
+    fn test_raw_text_elements_raw() {
+        let markdown = "<style>\np { color: red }\n</style>\n\nText";
+        let options = TranspileOptions { raw_text_elements: RawTextPolicy::Raw, ..Default::default() };
+        let ast = parse(markdown, &options);
+
+        assert_eq!(ast[0], Node::raw("<style>\np { color: red }\n</style>", "html"));
+        let json = serde_json::to_string(&ast[0]).unwrap();
+        assert!(json.starts_with(r#"{"type":"raw","content":"<style>"#));
+        assert!(json.ends_with(r#","format":"html"}"#));
+    }
+
+    #[test]
+    fn test_same_name_nested_tags() {
+        let markdown = "<div class=\"outer\">\n<div class=\"inner\">\n\n**inner**\n\n</div>\n\nafter\n\n</div>\n\n<div><div>x</div></div>\n\nend";
+        let options = TranspileOptions { allowed_tags: vec!["div".to_string()], ..Default::default() };
+        let ast = parse(markdown, &options);
+
+        assert_eq!(ast.len(), 3);
+        if let Node::Element { tag, props, children, .. } = &ast[0] {
+            assert_eq!(tag, "div");
+            assert_eq!(props.get("class").unwrap(), "outer");
+            assert_eq!(children.len(), 2);
+            if let Node::Element { props, children, .. } = &children[0] {
+                assert_eq!(props.get("class").unwrap(), "inner");
+                assert!(find_node(children, "strong").is_some());
+            } else {
+                panic!("Expected inner div");
+            }
+            if let Node::Element { tag, .. } = &children[1] {
+                assert_eq!(tag, "p");
+            }
+        } else {
+            panic!("Expected outer div");
+        }
+        if let Node::Element { children, .. } = &ast[1] {
+            assert_eq!(children.len(), 1);
+            assert!(find_node(children, "div").is_some());
+        }
+        if let Node::Element { tag, .. } = &ast[2] {
+            assert_eq!(tag, "p");
+        }
+    }
+
+    #[test]
+    fn test_multiline_html_tags() {
+        let markdown = "<div>\n<Player\n  src=\"a.mp4\"\n  title=\"Intro\"\n/>\n</div>\n\n<Player\n  src=\"b.mp4\"\n/>";
+        let options = TranspileOptions {
+            allowed_tags: vec!["div".to_string(), "Player".to_string()],
+            ..Default::default()
+        };
+        let ast = parse(markdown, &options);
+
+        assert_eq!(ast.len(), 2);
+        if let Node::Element { tag, children, .. } = &ast[0] {
+            assert_eq!(tag, "div");
+            assert_eq!(children.len(), 1);
+            if let Node::Element { tag, props, .. } = &children[0] {
+                assert_eq!(tag, "Player");
+                assert_eq!(props.get("src").unwrap(), "a.mp4");
+                assert_eq!(props.get("title").unwrap(), "Intro");
+            }
+        } else {
+            panic!("Expected div element");
+        }
+        let player = find_node(&ast[1..], "Player").expect("Should find inline Player");
+        if let Node::Element { props, .. } = player {
+            assert_eq!(props.get("src").unwrap(), "b.mp4");
+        }
+    }
+
+    #[test]
+    fn test_source_spans() {
+        let markdown = "# Title\n\nSome <Box>*boxed*</Box>";
+        let options = TranspileOptions {
+            allowed_tags: vec!["Box".to_string()],
+            source_spans: true,
+            ..Default::default()
+        };
+        let ast = parse(markdown, &options);
+
+        if let Node::Element { span, children, .. } = &ast[0] {
+            assert_eq!(*span, Some(Span { start: 0, end: 8, line: 1, column: 1 }));
+            if let Node::Text { span, .. } = &children[0] {
+                assert_eq!(*span, Some(Span { start: 2, end: 7, line: 1, column: 3 }));
+            }
+        }
+        let boxed = find_node(&ast, "Box").expect("Should find Box");
+        if let Node::Element { span, .. } = boxed {
+            assert_eq!(*span, Some(Span { start: 14, end: 32, line: 3, column: 6 }));
+        }
+
+        let json = serde_json::to_string(&parse(markdown, &TranspileOptions::default())).unwrap();
+        assert!(!json.contains("span"));
+    }
+
+    #[test]
+    fn test_sourcepos_props() {
+        let markdown = "# Title\n\n- one\n- two\n  continued\n";
+        let options = TranspileOptions { sourcepos: true, ..Default::default() };
+        let ast = parse(markdown, &options);
+
+        let sourcepos = |node: &Node| match node {
+            Node::Element { props, .. } => props.get("data-sourcepos").cloned(),
+            _ => None,
+        };
+        assert_eq!(sourcepos(&ast[0]).unwrap(), "1:1-1:7");
+        assert_eq!(sourcepos(&ast[1]).unwrap(), "3:1-5:11");
+        if let Node::Element { children, .. } = &ast[1] {
+            assert_eq!(sourcepos(&children[1]).unwrap(), "4:1-5:11");
+        }
+        if let Node::Element { children, .. } = &ast[0] {
+            assert!(sourcepos(&children[0]).is_none());
+        }
+    }
+
+    #[test]
+    fn test_block_elements() {
+        let markdown = "> quote\n\n```rust\nfn main() {}\n```\n\n![An *image*](cat.png \"Cat\")\n\n---\n\n- [x] done";
+        let ast = parse(markdown, &TranspileOptions::default());
+
+        assert!(find_node(&ast, "blockquote").is_some());
+        if let Some(Node::Element { children, .. }) = find_node(&ast, "pre") {
+            if let Node::Element { tag, props, .. } = &children[0] {
+                assert_eq!(tag, "code");
+                assert_eq!(props.get("className").unwrap(), "language-rust");
+            }
+        } else {
+            panic!("Expected pre element");
+        }
+        if let Some(Node::Element { props, children, .. }) = find_node(&ast, "img") {
+            assert_eq!(props.get("src").unwrap(), "cat.png");
+            assert_eq!(props.get("alt").unwrap(), "An image");
+            assert_eq!(props.get("title").unwrap(), "Cat");
+            assert!(children.is_empty());
+        } else {
+            panic!("Expected img element");
+        }
+        assert!(find_node(&ast, "hr").is_some());
+        if let Some(Node::Element { props, .. }) = find_node(&ast, "input") {
+            assert_eq!(props.get("checked").unwrap(), true);
+        }
+    }
+
+    #[test]
+    fn test_node_builder() {
+        let node = Node::element("a")
+            .prop("href", "https://example.com")
+            .prop("download", true)
+            .child(Node::text("Example"))
+            .with_children([Node::text("!")]);
+
+        let json = serde_json::to_value(&node).unwrap();
+        assert_eq!(json["tag"], "a");
+        assert_eq!(json["props"]["href"], "https://example.com");
+        assert_eq!(json["props"]["download"], true);
+        assert_eq!(json["children"][0]["content"], "Example");
+        assert_eq!(json["children"][1]["content"], "!");
+        assert_eq!(Node::text("x").prop("ignored", 1), Node::text("x"));
+    }
+
+    #[test]
+    fn test_fragment_serialization() {
+        let node = Node::fragment([Node::text("a"), Node::element("br")]);
+        let json = serde_json::to_string(&node).unwrap();
+
+        assert_eq!(json, r#"{"type":"fragment","children":[{"type":"text","content":"a"},{"type":"element","tag":"br","props":{},"children":[]}]}"#);
+        assert_eq!(serde_json::from_str::<Node>(&json).unwrap(), node);
+    }
+
+    #[test]
+    fn test_html_comments() {
+        let ast = parse("<!--\nsummary: draft\n-->\n\nText <!-- note --> here.", &TranspileOptions::default());
+
+        assert_eq!(ast[0], Node::comment("\nsummary: draft\n"));
+        match &ast[1] {
+            Node::Element { tag, children, .. } => {
+                assert_eq!(tag, "p");
+                assert_eq!(children[1], Node::comment(" note "));
+            }
+            other => panic!("Expected paragraph, got {:?}", other),
+        }
+        let json = serde_json::to_string(&ast[0]).unwrap();
+        assert_eq!(json, r#"{"type":"comment","content":"\nsummary: draft\n"}"#);
+    }
+
+    #[test]
+    fn test_prop_order() {
+        let options = TranspileOptions { allowed_tags: vec!["Card".to_string()], ..Default::default() };
+        let ast = parse("<Card zeta=\"1\" alpha=\"2\" mid />", &options);
+        let json = serde_json::to_string(&ast).unwrap();
+
+        assert!(json.contains(r#""props":{"zeta":"1","alpha":"2","mid":true}"#));
+        let ast = parse("[a](/x \"T\")", &options);
+        let json = serde_json::to_string(&ast).unwrap();
+        assert!(json.contains(r#""props":{"href":"/x","title":"T"}"#));
+    }
+
+    #[test]
+    fn test_parse_borrowed() {
+        let markdown = "# Hello\n\n<Card kind=\"a\">AT&amp;T</Card> <!-- c -->";
+        let options = TranspileOptions { allowed_tags: vec!["Card".to_string()], ..Default::default() };
+        let ast = parse_borrowed(markdown, &options);
+
+        let Node::Element { children, .. } = &ast[0] else { panic!("Expected heading") };
+        assert!(matches!(children[0], Node::Text { content: Cow::Borrowed("Hello"), .. }));
+        let Node::Element { children, .. } = &ast[1] else { panic!("Expected paragraph") };
+        assert!(matches!(&children[0], Node::Element { tag: Cow::Borrowed("Card"), .. }));
+        let Node::Element { children: card, .. } = &children[0] else { unreachable!() };
+        assert_eq!(text_content(card), "AT&T");
+        assert!(matches!(children[2], Node::Comment { content: Cow::Borrowed(" c "), .. }));
+
+        let owned: Vec<Node> = ast.into_iter().map(BorrowedNode::into_owned).collect();
+        assert_eq!(owned, parse(markdown, &options));
+    }
+
+    #[test]
+    fn test_parse_borrowed_url_schemes() {
+        let ast = parse_borrowed("[x](javascript:alert(1)) [y](/ok)", &TranspileOptions::default());
+        let owned: Vec<Node> = ast.into_iter().map(BorrowedNode::into_owned).collect();
+        assert_eq!(owned, parse("[x](javascript:alert(1)) [y](/ok)", &TranspileOptions::default()));
+        assert!(!serde_json::to_string(&owned).unwrap().contains("javascript"));
+    }
+
+    #[test]
+    fn test_extensions() {
+        let markdown = "# Title {#top .big}\n\n~~gone~~ \"quoted\"\n\n| a |\n| - |";
+        let ast = parse(markdown, &TranspileOptions::default());
+        assert_eq!(ast[0], Node::element("h1").child(Node::text("Title {#top .big}")));
+        assert!(find_node(&ast, "del").is_some() && find_node(&ast, "table").is_some());
+        assert!(text_content(&ast).contains("\u{201c}quoted\u{201d}"));
+
+        let options = TranspileOptions::builder()
+            .extensions(Extensions { heading_attributes: true, ..Extensions::none() })
+            .build();
+        let ast = parse(markdown, &options);
+        assert_eq!(ast[0], Node::element("h1").prop("id", "top").prop("className", "big").child(Node::text("Title")));
+        assert!(find_node(&ast, "del").is_none() && find_node(&ast, "table").is_none());
+        assert!(text_content(&ast).contains("~~gone~~ \"quoted\""));
+    }
+
+    #[test]
+    fn test_options_from_json() {
+        let json = r#"{
+            "allowed_tags": ["Card"],
+            "raw_text_elements": "Remove",
+            "keys": "IndexPath",
+            "extensions": { "smart_punctuation": false },
+            "base_url": "https://example.com/docs/",
+            "tag_map": { "h1": "Heading" }
+        }"#;
+        let options = TranspileOptions::from_json(json).unwrap();
+        assert_eq!(options.allowed_tags, ["Card"]);
+        assert_eq!(options.raw_text_elements, RawTextPolicy::Remove);
+        assert_eq!(options.keys, Some(KeyStrategy::IndexPath));
+        assert_eq!(options.extensions, Extensions { smart_punctuation: false, ..Default::default() });
+        assert_eq!(options.base_url.as_ref().map(Url::as_str), Some("https://example.com/docs/"));
+        assert_eq!(options.tag_map["h1"], "Heading");
+        assert!(!options.normalize);
+
+        let err = TranspileOptions::from_json(r#"{ "allowed_tag": [] }"#).unwrap_err();
+        assert!(err.to_string().contains("unknown field `allowed_tag`"), "{}", err);
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_options_from_toml() {
+        let toml = "allowed_tags = [\"Card\"]\nsoft_break = \"Br\"\n\n[footnote_names]\nid_prefix = \"note-\"\n";
+        let options = TranspileOptions::from_toml(toml).unwrap();
+        assert_eq!(options.allowed_tags, ["Card"]);
+        assert_eq!(options.soft_break, SoftBreak::Br);
+        assert_eq!(options.footnote_names, FootnoteNames { id_prefix: "note-".to_string(), ..Default::default() });
+    }
+
+    #[test]
+    fn test_soft_break() {
+        let markdown = "one\ntwo";
+        let lines = |soft_break| {
+            let options = TranspileOptions::builder().soft_break(soft_break).normalize(true).build();
+            parse(markdown, &options)[0].children().unwrap().clone()
+        };
+        assert_eq!(lines(SoftBreak::Newline), [Node::text("one\ntwo")]);
+        assert_eq!(lines(SoftBreak::Space), [Node::text("one two")]);
+        assert_eq!(lines(SoftBreak::Br), [Node::text("one"), Node::element("br"), Node::text("two")]);
+
+        let options = TranspileOptions::builder().soft_break(SoftBreak::Br).build();
+        let ast = parse("soft\nline  \nhard\\\nend", &options);
+        let breaks = ast[0].children().unwrap().iter().filter(|node| node.tag() == Some("br")).count();
+        assert_eq!(breaks, 3);
+        let ast = parse("line  \nhard", &TranspileOptions::default());
+        assert_eq!(ast[0].children().unwrap()[1], Node::element("br"));
+    }
+
+    #[test]
+    fn test_max_depth() {
+        fn depth(nodes: &[Node]) -> usize {
+            nodes.iter().map(|node| node.children().map_or(0, |children| 1 + depth(children))).max().unwrap_or(0)
+        }
+        let options = TranspileOptions::builder().allowed_tags(["div"]).max_depth(3).build();
+
+        let ast = parse(&format!("{} deep\n\nafter", ">".repeat(1000)), &options);
+        assert_eq!(depth(&ast), 3);
+        let quote = |child| Node::element("blockquote").child(child);
+        assert_eq!(ast[0], quote(quote(quote(Node::text("deep")))));
+        assert_eq!(ast[1], Node::element("p").child(Node::text("after")));
+
+        let html = format!("{}x{} y", "<div>".repeat(50), "</div>".repeat(50));
+        let ast = parse(&html, &options);
+        assert_eq!(depth(&ast), 3);
+        assert_eq!(text_content(&ast), "x y");
+    }
+
+    #[test]
+    fn test_options_builder() {
+        let options = TranspileOptions::builder()
+            .allowed_tags(["Card"])
+            .raw_text_elements(RawTextPolicy::Remove)
+            .normalize(true)
+            .transform(|nodes: &mut Vec<Node>| nodes.push(Node::text("end")))
+            .build();
+
+        assert_eq!(options.allowed_tags, ["Card"]);
+        assert_eq!(options.raw_text_elements, RawTextPolicy::Remove);
+        assert!(options.normalize && !options.source_spans && options.keys.is_none());
+        let ast = parse("<Card>x</Card>", &options);
+        assert_eq!(ast.last(), Some(&Node::text("end")));
+    }
 }
@@ -0,0 +1,112 @@
+//! Resource limits for untrusted input: `max_input_bytes` and
+//! `timeout_ms`.
+
+use serde::Serialize;
+use crate::{build_limited, finish_parse, preprocessed, Node, TranspileOptions};
+
+/// Events parsed between clock checks.
+const EVENTS_PER_CHECK: usize = 256;
+
+/// Which limit cut a parse short.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Truncation {
+    /// The input was longer than `max_input_bytes`.
+    InputSize,
+    /// Parsing ran past `timeout_ms`.
+    Timeout,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LimitedParse {
+    /// Everything parsed before the limit was hit, with open elements
+    /// closed.
+    pub nodes: Vec<Node>,
+    /// `None` if the whole input was parsed.
+    pub truncated: Option<Truncation>,
+}
+
+/// Like `parse`, but reports whether `max_input_bytes` or `timeout_ms`
+/// cut the document short.
+pub fn parse_limited(markdown: &str, options: &TranspileOptions) -> LimitedParse {
+    let markdown = preprocessed(markdown, options);
+    let (mut nodes, truncated) = build_limited(&markdown, options);
+    finish_parse(&mut nodes, options);
+    LimitedParse { nodes, truncated }
+}
+
+/// `markdown` cut to at most `max_bytes`, at the last line break if there
+/// is one, so the final block is not split mid-line.
+pub(crate) fn limit_input(markdown: &str, max_bytes: Option<usize>) -> (&str, Option<Truncation>) {
+    let Some(max) = max_bytes.filter(|&max| markdown.len() > max) else {
+        return (markdown, None);
+    };
+    let mut end = max;
+    while !markdown.is_char_boundary(end) {
+        end -= 1;
+    }
+    let head = &markdown[..end];
+    let head = head.rfind('\n').map_or(head, |newline| &head[..newline + 1]);
+    (head, Some(Truncation::InputSize))
+}
+
+/// The time a parse must stop by. There is no clock on
+/// `wasm32-unknown-unknown`, so the timeout is not enforced there.
+pub(crate) struct Deadline {
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+    at: Option<std::time::Instant>,
+    events: usize,
+}
+
+impl Deadline {
+    #[cfg_attr(all(target_arch = "wasm32", target_os = "unknown"), allow(unused_variables))]
+    pub(crate) fn new(timeout_ms: Option<u64>) -> Self {
+        Deadline {
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+            at: timeout_ms.and_then(|ms| std::time::Instant::now().checked_add(std::time::Duration::from_millis(ms))),
+            events: 0,
+        }
+    }
+
+    /// Counts one parser event; true once the deadline has passed.
+    pub(crate) fn passed(&mut self) -> bool {
+        self.events += 1;
+        if !self.events.is_multiple_of(EVENTS_PER_CHECK) {
+            return false;
+        }
+        #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+        if let Some(at) = self.at {
+            return std::time::Instant::now() >= at;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_max_input_bytes() {
+        let options = TranspileOptions::builder().max_input_bytes(12).build();
+        let limited = parse_limited("# Title\n\nSome *long* paragraph", &options);
+        assert_eq!(limited.nodes, [Node::element("h1").child(Node::text("Title"))]);
+        assert_eq!(limited.truncated, Some(Truncation::InputSize));
+
+        assert_eq!(limit_input("aé", Some(2)), ("a", Some(Truncation::InputSize)));
+        let whole = parse_limited("# Title", &options);
+        assert_eq!(whole.truncated, None);
+    }
+
+    #[test]
+    fn test_timeout() {
+        let options = TranspileOptions::builder().timeout_ms(0).build();
+        let markdown = "- *item* `code`\n".repeat(1000);
+        let limited = parse_limited(&markdown, &options);
+        assert_eq!(limited.truncated, Some(Truncation::Timeout));
+        // Cut off mid-list, with the open list closed.
+        assert_eq!(limited.nodes.len(), 1);
+        let items = limited.nodes[0].children().unwrap();
+        assert!(!items.is_empty() && items.len() < 1000);
+    }
+}
@@ -0,0 +1,278 @@
+//! Hooks rewriting link destinations and image sources while parsing.
+
+use std::collections::HashMap;
+use std::fmt;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+lazy_static! {
+    /// A URL scheme (`https:`, `mailto:`, ...).
+    pub(crate) static ref SCHEME_RE: Regex = Regex::new(r"^[A-Za-z][A-Za-z0-9+.-]*:").unwrap();
+}
+
+/// What a link destination points at, judged from its text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkKind {
+    /// `#section`, including footnote references.
+    Anchor,
+    /// A path with no scheme (`guide/setup.md`, `/about`).
+    Relative,
+    /// `http:`, `https:` or a scheme-relative `//host/...` URL.
+    External,
+    /// `mailto:`.
+    Email,
+    /// Any other scheme (`tel:`, `doc:42`, ...).
+    Other,
+}
+
+impl LinkKind {
+    pub fn of(url: &str) -> LinkKind {
+        let scheme = SCHEME_RE.find(url).map(|m| m.as_str().to_ascii_lowercase());
+        match scheme.as_deref() {
+            Some("http:" | "https:") => LinkKind::External,
+            Some("mailto:") => LinkKind::Email,
+            Some(_) => LinkKind::Other,
+            None if url.starts_with('#') => LinkKind::Anchor,
+            None if url.starts_with("//") => LinkKind::External,
+            None => LinkKind::Relative,
+        }
+    }
+}
+
+/// What becomes of a link or image whose URL has a given scheme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+pub enum SchemeAction {
+    Allow,
+    /// Replace the element with its text (a link's content, an image's
+    /// `alt`).
+    Text,
+    /// Remove the element and its content.
+    Drop,
+}
+
+/// Schemes allowed in link `href`s and image `src`s, checked on the URL
+/// as emitted (after the rewriters). URLs without a scheme are always
+/// allowed. By default only `http`, `https` and `mailto` are, and links
+/// with any other scheme (`javascript:`, `data:`, ...) become text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+#[serde(default)]
+pub struct UrlSchemes {
+    /// Lowercase scheme names, without the colon.
+    pub schemes: HashMap<String, SchemeAction>,
+    /// The action for schemes not in `schemes`.
+    pub other: SchemeAction,
+}
+
+impl Default for UrlSchemes {
+    fn default() -> Self {
+        UrlSchemes::any()
+            .with("http", SchemeAction::Allow)
+            .with("https", SchemeAction::Allow)
+            .with("mailto", SchemeAction::Allow)
+            .other(SchemeAction::Text)
+    }
+}
+
+impl UrlSchemes {
+    /// Every scheme allowed.
+    pub fn any() -> Self {
+        UrlSchemes { schemes: HashMap::new(), other: SchemeAction::Allow }
+    }
+
+    pub fn with(mut self, scheme: &str, action: SchemeAction) -> Self {
+        self.schemes.insert(scheme.to_ascii_lowercase(), action);
+        self
+    }
+
+    pub fn other(mut self, action: SchemeAction) -> Self {
+        self.other = action;
+        self
+    }
+
+    /// The action for `url`. Tabs and line breaks, which browsers ignore
+    /// inside a scheme, are removed first.
+    pub(crate) fn action(&self, url: &str) -> SchemeAction {
+        let url: String = url.trim_start_matches(|c: char| c <= ' ').chars().filter(|c| !matches!(c, '\t' | '\n' | '\r')).collect();
+        match SCHEME_RE.find(&url) {
+            Some(scheme) => {
+                let scheme = scheme.as_str().trim_end_matches(':').to_ascii_lowercase();
+                self.schemes.get(&scheme).copied().unwrap_or(self.other)
+            }
+            None => SchemeAction::Allow,
+        }
+    }
+}
+
+/// Maps a link `href` (from markdown or allowed HTML) to the one emitted.
+/// Runs before `base_url` resolution. Any `Fn(&str, LinkKind) -> String`
+/// closure is a rewriter.
+pub trait LinkRewriter: Send + Sync {
+    fn rewrite(&self, url: &str, kind: LinkKind) -> String;
+}
+
+impl<F> LinkRewriter for F
+where
+    F: Fn(&str, LinkKind) -> String + Send + Sync,
+{
+    fn rewrite(&self, url: &str, kind: LinkKind) -> String {
+        self(url, kind)
+    }
+}
+
+impl fmt::Debug for dyn LinkRewriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("LinkRewriter")
+    }
+}
+
+/// Maps an image `src`, once resolved against `base_url`, to the one
+/// emitted (a CDN or resizer URL), optionally with a `srcSet`. Any
+/// `Fn(&str) -> String` closure is a rewriter that adds no `srcSet`.
+pub trait ImageRewriter: Send + Sync {
+    fn rewrite(&self, src: &str) -> String;
+
+    /// The `srcSet` for the image, if any; given the same `src` as `rewrite`.
+    fn src_set(&self, _src: &str) -> Option<String> {
+        None
+    }
+}
+
+impl<F> ImageRewriter for F
+where
+    F: Fn(&str) -> String + Send + Sync,
+{
+    fn rewrite(&self, src: &str) -> String {
+        self(src)
+    }
+}
+
+impl fmt::Debug for dyn ImageRewriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ImageRewriter")
+    }
+}
+
+/// An `ImageRewriter` filling `{src}` and `{width}` into a URL template,
+/// e.g. `https://img.example.com/{src}?w={width}`. `src` uses the largest
+/// width and `srcSet` lists one URL per width.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageTemplate {
+    pub template: String,
+    pub widths: Vec<u32>,
+}
+
+impl ImageTemplate {
+    pub fn new(template: impl Into<String>, widths: impl IntoIterator<Item = u32>) -> Self {
+        let mut widths: Vec<u32> = widths.into_iter().collect();
+        widths.sort_unstable();
+        ImageTemplate { template: template.into(), widths }
+    }
+
+    fn url(&self, src: &str, width: Option<u32>) -> String {
+        let width = width.map_or(String::new(), |w| w.to_string());
+        self.template.replace("{src}", src).replace("{width}", &width)
+    }
+}
+
+impl ImageRewriter for ImageTemplate {
+    fn rewrite(&self, src: &str) -> String {
+        self.url(src, self.widths.last().copied())
+    }
+
+    fn src_set(&self, src: &str) -> Option<String> {
+        if self.widths.is_empty() {
+            return None;
+        }
+        let entries: Vec<String> = self.widths.iter().map(|&w| format!("{} {}w", self.url(src, Some(w)), w)).collect();
+        Some(entries.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, Node, TranspileOptions, Url};
+
+    #[test]
+    fn test_link_kind() {
+        assert_eq!(LinkKind::of("#top"), LinkKind::Anchor);
+        assert_eq!(LinkKind::of("guide/a.md"), LinkKind::Relative);
+        assert_eq!(LinkKind::of("HTTPS://x.org"), LinkKind::External);
+        assert_eq!(LinkKind::of("//cdn.x.org/a"), LinkKind::External);
+        assert_eq!(LinkKind::of("mailto:a@x.org"), LinkKind::Email);
+        assert_eq!(LinkKind::of("doc:42"), LinkKind::Other);
+    }
+
+    #[test]
+    fn test_url_schemes() {
+        let markdown = "[a](https://x.org) [b](javascript:alert(1)) ![c](data:image/png;base64,AA) <a href=\" java\tscript:x\">d</a> [e](tel:123) [f](/rel)";
+        let options = TranspileOptions::builder().allowed_tags(["a"]).build();
+        assert_eq!(crate::text_content(&parse(markdown, &options)), "a b c d e f");
+        let links = crate::find_all(&parse(markdown, &options), |node| node.tag() == Some("a")).len();
+        assert_eq!(links, 2);
+
+        let schemes = UrlSchemes::default().with("tel", SchemeAction::Allow).other(SchemeAction::Drop);
+        let options = TranspileOptions::builder().allowed_tags(["a"]).url_schemes(schemes).build();
+        let ast = parse(markdown, &options);
+        assert_eq!(crate::text_content(&ast), "a    e f");
+        assert_eq!(crate::find_all(&ast, |node| node.tag() == Some("a")).len(), 3);
+    }
+
+    #[test]
+    fn test_url_schemes_attribute_case() {
+        let options = TranspileOptions::builder()
+            .allowed_tags(["a", "img"])
+            .base_url(Url::parse("https://example.com/").unwrap())
+            .build();
+        let ast = parse("<a HREF=\"javascript:alert(1)\">x</a> <img Src=\"data:x\" ALT=\"i\" /> <a Href=\"/ok\">y</a>", &options);
+
+        assert!(!crate::render_html(&ast).contains("javascript"));
+        assert_eq!(crate::text_content(&ast), "x i y");
+        let link = crate::find(&ast, |node| node.tag() == Some("a")).unwrap();
+        assert_eq!(link, &Node::element("a").prop("Href", "https://example.com/ok").child(Node::text("y")));
+    }
+
+    #[test]
+    fn test_link_rewriter() {
+        let options = TranspileOptions::builder()
+            .allowed_tags(["a"])
+            .base_url(Url::parse("https://example.com/").unwrap())
+            .link_rewriter(|url: &str, kind| match kind {
+                LinkKind::External => format!("{}?utm_source=docs", url),
+                LinkKind::Other => url.replace("doc:", "/docs/"),
+                _ => url.to_string(),
+            })
+            .build();
+        let ast = parse("[a](https://x.org) <a href=\"doc:42\">b</a> [c](#top) ![i](https://x.org/i.png)", &options);
+
+        let links: Vec<_> = ast[0].children().unwrap().iter()
+            .filter_map(|node| match node {
+                Node::Element { props, .. } => props.get("href").or(props.get("src"))?.as_str(),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(links, ["https://x.org?utm_source=docs", "https://example.com/docs/42", "#top", "https://x.org/i.png"]);
+    }
+
+    #[test]
+    fn test_image_rewriter() {
+        let options = TranspileOptions::builder()
+            .base_url(Url::parse("https://example.com/").unwrap())
+            .image_rewriter(ImageTemplate::new("https://cdn.example.com/resize?url={src}&w={width}", [960, 480]))
+            .build();
+        let ast = parse("![a](img/a.png)", &options);
+        let img = &ast[0].children().unwrap()[0];
+        assert_eq!(img, &Node::element("img")
+            .prop("src", "https://cdn.example.com/resize?url=https://example.com/img/a.png&w=960")
+            .prop("alt", "a")
+            .prop("srcSet", "https://cdn.example.com/resize?url=https://example.com/img/a.png&w=480 480w, \
+                https://cdn.example.com/resize?url=https://example.com/img/a.png&w=960 960w"));
+
+        let options = TranspileOptions::builder().image_rewriter(|src: &str| format!("/cdn/{}", src)).build();
+        let ast = parse("![a](a.png)", &options);
+        assert_eq!(ast[0].children().unwrap()[0], Node::element("img").prop("src", "/cdn/a.png").prop("alt", "a"));
+    }
+}
@@ -0,0 +1,486 @@
+//! Serializes the AST back to CommonMark/GFM.
+//!
+//! Elements produced by the markdown parser are written as markdown syntax;
+//! anything else (allowed custom tags, raw HTML elements) is written as
+//! inline or block HTML so it survives a round trip through `parse`.
+
+use crate::Node;
+
+pub(crate) const BLOCK_TAGS: &[&str] = &[
+    "p", "h1", "h2", "h3", "h4", "h5", "h6", "blockquote", "pre", "ul", "ol", "li", "hr",
+    "table", "thead", "tbody", "tr", "td", "th", "div",
+];
+
+/// Renders `nodes` as markdown text.
+pub fn to_markdown(nodes: &[Node]) -> String {
+    let nodes = splice_fragments(nodes);
+    let mut out = blocks(&nodes, "\n\n");
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+/// Copies `nodes` with every fragment replaced by its children, so the
+/// renderers below only see elements and text.
+fn splice_fragments(nodes: &[Node]) -> Vec<Node> {
+    let mut out = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        match node {
+            Node::Fragment { children } => out.extend(splice_fragments(children)),
+            Node::Element { tag, props, children, span, content_id } => out.push(Node::Element {
+                tag: tag.clone(),
+                props: props.clone(),
+                children: splice_fragments(children),
+                span: *span,
+                content_id: content_id.clone(),
+            }),
+            Node::Text { .. } | Node::Comment { .. } | Node::Raw { .. } => out.push(node.clone()),
+        }
+    }
+    out
+}
+
+/// Known block elements, and any other element wrapping blocks.
+fn is_block(node: &Node) -> bool {
+    match node {
+        Node::Element { tag, children, .. } => {
+            BLOCK_TAGS.contains(&tag.as_str()) || children.iter().any(is_block)
+        }
+        _ => false,
+    }
+}
+
+fn prop<'a>(node: &'a Node, name: &str) -> Option<&'a str> {
+    match node {
+        Node::Element { props, .. } => props.get(name).and_then(|v| v.as_str()),
+        _ => None,
+    }
+}
+
+/// Renders a sequence of blocks; runs of inline nodes form one paragraph.
+fn blocks(nodes: &[Node], separator: &str) -> String {
+    let mut parts = Vec::new();
+    let mut run_start = None;
+    // Adjacent lists of the same kind need different markers, or they
+    // would merge into one list when parsed again.
+    let mut last_list: Option<(&str, bool)> = None;
+    for (i, node) in nodes.iter().enumerate() {
+        if is_block(node) {
+            if let Some(start) = run_start.take() {
+                parts.push(inline_run(&nodes[start..i]));
+                last_list = None;
+            }
+            match node {
+                Node::Element { tag, .. } if tag == "ul" || tag == "ol" => {
+                    let alternate = matches!(last_list, Some((last, alt)) if last == tag && !alt);
+                    parts.push(list(node, alternate));
+                    last_list = Some((tag, alternate));
+                }
+                _ => {
+                    parts.push(block(node));
+                    last_list = None;
+                }
+            }
+        } else if run_start.is_none() {
+            run_start = Some(i);
+        }
+    }
+    if let Some(start) = run_start {
+        parts.push(inline_run(&nodes[start..]));
+    }
+    parts.retain(|part| !part.is_empty());
+    parts.join(separator)
+}
+
+fn inline_run(nodes: &[Node]) -> String {
+    let mut out = String::new();
+    inline(nodes, &mut out, false);
+    out.trim_matches('\n').to_string()
+}
+
+fn block(node: &Node) -> String {
+    let Node::Element { tag, children, .. } = node else {
+        return inline_run(std::slice::from_ref(node));
+    };
+    match tag.as_str() {
+        "p" => inline_run(children),
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+            let level = tag[1..].parse::<usize>().unwrap_or(1);
+            format!("{} {}", "#".repeat(level), inline_run(children))
+        }
+        "blockquote" => prefix_lines(&blocks(children, "\n\n"), "> ", ">"),
+        "pre" => code_block(children),
+        "ul" | "ol" => list(node, false),
+        "hr" => "---".to_string(),
+        "table" => table(children),
+        "div" if prop(node, "className") == Some("footnote-definition") => {
+            let label = prop(node, "id").unwrap_or_default().trim_start_matches("fn-");
+            let body = indent_rest(&blocks(children, "\n\n"), "    ");
+            format!("[^{}]: {}", label, body)
+        }
+        _ => html_block(node),
+    }
+}
+
+fn code_block(children: &[Node]) -> String {
+    let code = children.iter().find(|n| matches!(n, Node::Element { tag, .. } if tag == "code"));
+    let (language, content) = match code {
+        Some(code @ Node::Element { children, .. }) => {
+            let language = prop(code, "className")
+                .and_then(|c| c.split_whitespace().find_map(|c| c.strip_prefix("language-")))
+                .unwrap_or_default();
+            (language, crate::text_content(children))
+        }
+        _ => ("", crate::text_content(children)),
+    };
+    let fence = "`".repeat(longest_run(&content, '`').max(2) + 1);
+    let newline = if content.is_empty() || content.ends_with('\n') { "" } else { "\n" };
+    format!("{}{}\n{}{}{}", fence, language, content, newline, fence)
+}
+
+/// `alternate` switches to the `*` bullet or `)` delimiter.
+fn list(node: &Node, alternate: bool) -> String {
+    let Node::Element { tag, props, children, .. } = node else {
+        return String::new();
+    };
+    let ordered = tag == "ol";
+    let mut number = props.get("start").and_then(|v| v.as_u64()).unwrap_or(1);
+    let loose = children.iter().any(|item| match item {
+        Node::Element { children, .. } => children.iter().any(|c| matches!(c, Node::Element { tag, .. } if tag == "p")),
+        _ => false,
+    });
+
+    let mut items = Vec::new();
+    for item in children {
+        let Node::Element { children, .. } = item else { continue };
+        let marker = match (ordered, alternate) {
+            (true, false) => format!("{}. ", number),
+            (true, true) => format!("{}) ", number),
+            (false, false) => "- ".to_string(),
+            (false, true) => "* ".to_string(),
+        };
+        number += 1;
+
+        let mut content = children.as_slice();
+        let mut task = "";
+        if let Some(first @ Node::Element { tag, props, .. }) = content.first() {
+            if tag == "input" && prop(first, "type") == Some("checkbox") {
+                let checked = props.get("checked").and_then(|v| v.as_bool()).unwrap_or(false);
+                task = if checked { "[x] " } else { "[ ] " };
+                content = &content[1..];
+            }
+        }
+        let body = blocks(content, if loose { "\n\n" } else { "\n" });
+        let body = indent_rest(body.trim_start_matches(' '), &" ".repeat(marker.len()));
+        items.push(format!("{}{}{}", marker, task, body).trim_end().to_string());
+    }
+    items.join(if loose { "\n\n" } else { "\n" })
+}
+
+fn table(children: &[Node]) -> String {
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    for section in children {
+        let Node::Element { tag, children, .. } = section else { continue };
+        match tag.as_str() {
+            "thead" if children.iter().all(|c| !matches!(c, Node::Element { tag, .. } if tag == "tr")) => {
+                rows.push(children.iter().map(table_cell).collect());
+            }
+            "thead" | "tbody" => {
+                for row in children {
+                    if let Node::Element { children, .. } = row {
+                        rows.push(children.iter().map(table_cell).collect());
+                    }
+                }
+            }
+            "tr" => rows.push(children.iter().map(table_cell).collect()),
+            _ => {}
+        }
+    }
+    let Some(columns) = rows.iter().map(Vec::len).max() else {
+        return String::new();
+    };
+    let line = |cells: &[String]| {
+        let mut padded: Vec<&str> = cells.iter().map(String::as_str).collect();
+        padded.resize(columns, "");
+        format!("| {} |", padded.join(" | "))
+    };
+    let mut lines = vec![line(&rows[0]), line(&vec!["---".to_string(); columns])];
+    lines.extend(rows[1..].iter().map(|row| line(row)));
+    lines.join("\n")
+}
+
+fn table_cell(cell: &Node) -> String {
+    let mut out = String::new();
+    if let Node::Element { children, .. } = cell {
+        inline(children, &mut out, true);
+    }
+    out.replace('\n', " ")
+}
+
+fn html_block(node: &Node) -> String {
+    let Node::Element { tag, children, .. } = node else {
+        return String::new();
+    };
+    if children.is_empty() {
+        return format!("<{}{} />", tag, attributes(node));
+    }
+    if children.iter().any(is_block) {
+        format!("<{}{}>\n\n{}\n\n</{}>", tag, attributes(node), blocks(children, "\n\n"), tag)
+    } else {
+        let mut out = String::new();
+        inline(std::slice::from_ref(node), &mut out, false);
+        out
+    }
+}
+
+fn attributes(node: &Node) -> String {
+    let Node::Element { props, .. } = node else {
+        return String::new();
+    };
+    let mut keys: Vec<&String> = props.keys().filter(|k| *k != "data-sourcepos").collect();
+    keys.sort();
+    let mut out = String::new();
+    for key in keys {
+        match &props[key] {
+            serde_json::Value::Bool(true) => out.push_str(&format!(" {}", key)),
+            serde_json::Value::Bool(false) | serde_json::Value::Null => {}
+            serde_json::Value::String(value) => out.push_str(&format!(" {}=\"{}\"", key, value.replace('"', "&quot;"))),
+            other => out.push_str(&format!(" {}=\"{}\"", key, other.to_string().replace('"', "&quot;"))),
+        }
+    }
+    out
+}
+
+fn inline(nodes: &[Node], out: &mut String, in_table: bool) {
+    // Adjacent text nodes are escaped together, since entity references
+    // and list numbers can be split across them.
+    let mut text = String::new();
+    for (i, node) in nodes.iter().enumerate() {
+        let (tag, children) = match node {
+            Node::Text { content, .. } => {
+                text.push_str(content);
+                continue;
+            }
+            Node::Element { tag, children, .. } => (tag.as_str(), children),
+            Node::Comment { content, .. } => {
+                escape_text(&std::mem::take(&mut text), out, in_table);
+                out.push_str(&format!("<!--{}-->", content));
+                continue;
+            }
+            Node::Raw { content, format, .. } => {
+                escape_text(&std::mem::take(&mut text), out, in_table);
+                // Only HTML survives as markdown source; other formats have
+                // no syntax to come back from.
+                if format == "html" {
+                    out.push_str(content);
+                }
+                continue;
+            }
+            Node::Fragment { children } => {
+                escape_text(&std::mem::take(&mut text), out, in_table);
+                inline(children, out, in_table);
+                continue;
+            }
+        };
+        escape_text(&std::mem::take(&mut text), out, in_table);
+        match tag {
+            "em" | "strong" | "del" => {
+                let marker = match tag {
+                    "em" => "*",
+                    "strong" => "**",
+                    _ => "~~",
+                };
+                out.push_str(marker);
+                inline(children, out, in_table);
+                out.push_str(marker);
+            }
+            "code" => code_span(&crate::text_content(children), out),
+            "a" => {
+                out.push('[');
+                inline(children, out, in_table);
+                out.push(']');
+                destination(prop(node, "href").unwrap_or_default(), prop(node, "title"), out);
+            }
+            "img" => {
+                out.push_str("![");
+                escape_text(prop(node, "alt").unwrap_or_default(), out, in_table);
+                out.push(']');
+                destination(prop(node, "src").unwrap_or_default(), prop(node, "title"), out);
+            }
+            "sup" if children.iter().any(|c| prop(c, "className") == Some("footnote-ref")) => {
+                out.push_str(&format!("[^{}]", crate::text_content(children)));
+            }
+            // A backslash break does nothing at the end of a block and drops
+            // the leading spaces of the next line, so those keep the tag.
+            "br" if !in_table && !out.is_empty() && !out.ends_with('\n') && nodes.get(i + 1).is_some_and(starts_line) => {
+                out.push_str("\\\n");
+            }
+            "input" if prop(node, "type") == Some("checkbox") => {}
+            _ if children.is_empty() => out.push_str(&format!("<{}{} />", tag, attributes(node))),
+            _ => {
+                out.push_str(&format!("<{}{}>", tag, attributes(node)));
+                inline(children, out, in_table);
+                out.push_str(&format!("</{}>", tag));
+            }
+        }
+    }
+    escape_text(&text, out, in_table);
+}
+
+/// True when `node` can follow a hard break: it is not text starting with
+/// whitespace.
+fn starts_line(node: &Node) -> bool {
+    match node {
+        Node::Text { content, .. } => !content.starts_with([' ', '\t', '\n']),
+        _ => true,
+    }
+}
+
+fn destination(url: &str, title: Option<&str>, out: &mut String) {
+    out.push('(');
+    if url.is_empty() || url.contains([' ', '(', ')', '<', '>']) {
+        out.push_str(&format!("<{}>", url.replace('<', "%3C").replace('>', "%3E")));
+    } else {
+        out.push_str(url);
+    }
+    if let Some(title) = title {
+        out.push_str(&format!(" \"{}\"", title.replace('"', "\\\"")));
+    }
+    out.push(')');
+}
+
+fn code_span(code: &str, out: &mut String) {
+    let fence = "`".repeat(longest_run(code, '`') + 1);
+    let pad = if code.starts_with('`') || code.ends_with('`') || code.starts_with(' ') && code.ends_with(' ') && !code.trim().is_empty() {
+        " "
+    } else {
+        ""
+    };
+    out.push_str(&format!("{}{}{}{}{}", fence, pad, code, pad, fence));
+}
+
+fn escape_text(text: &str, out: &mut String, in_table: bool) {
+    for (i, c) in text.char_indices() {
+        let line_start = out.is_empty() || out.ends_with('\n');
+        match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '~' => out.push('\\'),
+            '|' if in_table => out.push('\\'),
+            '#' | '>' | '-' | '+' | '=' if line_start => out.push('\\'),
+            '&' if looks_like_entity(&text[i + 1..]) => out.push('\\'),
+            '.' | ')' if ends_with_list_number(out) => out.push('\\'),
+            _ => {}
+        }
+        out.push(c);
+    }
+}
+
+/// True when the current line of `out` consists only of digits, so a
+/// following `.` or `)` would start an ordered list.
+fn ends_with_list_number(out: &str) -> bool {
+    let line = out.rsplit('\n').next().unwrap_or_default();
+    !line.is_empty() && line.len() <= 9 && line.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn looks_like_entity(rest: &str) -> bool {
+    match rest.find(';') {
+        Some(end) if end > 0 && end <= 32 => rest[..end].chars().all(|c| c.is_ascii_alphanumeric() || c == '#'),
+        _ => false,
+    }
+}
+
+fn longest_run(text: &str, c: char) -> usize {
+    let mut longest = 0;
+    let mut current = 0;
+    for ch in text.chars() {
+        if ch == c {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+    longest
+}
+
+fn prefix_lines(text: &str, prefix: &str, empty_prefix: &str) -> String {
+    text.lines()
+        .map(|line| if line.is_empty() { empty_prefix.to_string() } else { format!("{}{}", prefix, line) })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Indents every line after the first.
+fn indent_rest(text: &str, indent: &str) -> String {
+    let mut lines = text.lines();
+    let mut out = lines.next().unwrap_or_default().to_string();
+    for line in lines {
+        out.push('\n');
+        if !line.is_empty() {
+            out.push_str(indent);
+            out.push_str(line);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{diff, parse, TranspileOptions};
+
+    fn assert_round_trip(markdown: &str, options: &TranspileOptions) {
+        // Text node boundaries depend on escaping, so compare normalized trees.
+        let options = TranspileOptions { normalize: true, ..options.clone() };
+        let ast = parse(markdown, &options);
+        let written = to_markdown(&ast);
+        let reparsed = parse(&written, &options);
+        let patches = diff(&ast, &reparsed);
+        assert!(patches.is_empty(), "Round trip changed the AST:\n{}\n{:?}", written, patches);
+    }
+
+    #[test]
+    fn test_round_trip_blocks() {
+        let markdown = "# Title\n\nSome *emphasis*, **strong**, ~~gone~~ and `co`de`.\n\n> Quote with [a link](https://example.com \"Title\")\n> over two lines\n\n```rust\nfn main() {}\n```\n\n---\n\n![Alt text](img.png)\n";
+        assert_round_trip(markdown, &TranspileOptions::default());
+    }
+
+    #[test]
+    fn test_round_trip_lists_and_tables() {
+        let markdown = "3. three\n4. four\n   - nested\n\n- [x] done\n- [ ] todo\n\n* loose\n\n* list\n\n| A | B |\n| - | - |\n| 1 | a \\| b |\n";
+        assert_round_trip(markdown, &TranspileOptions::default());
+    }
+
+    #[test]
+    fn test_round_trip_escaping_and_html() {
+        let markdown = "1\\. not a list \\*literal\\* \\# and AT&T &amp;amp;\n\n<Note kind=\"tip\">\n\nInside **note**\n\n</Note>\n\nFootnote[^a].\n\n[^a]: The note.\n";
+        let options = TranspileOptions { allowed_tags: vec!["Note".to_string()], ..Default::default() };
+        assert_round_trip(markdown, &options);
+    }
+
+    #[test]
+    fn test_round_trip_breaks() {
+        let options = TranspileOptions::builder().soft_break(crate::SoftBreak::Br).build();
+        assert_round_trip("soft\nline  \nhard\\\n*em*\\\n`code`", &options);
+        let options = TranspileOptions::builder().allowed_tags(["br"]).build();
+        assert_round_trip("a<br>b<br> c *d<br>*<br>\n\n| x<br>y |\n| - |\n", &options);
+
+        let ast = parse("one  \ntwo <br>three", &options);
+        assert_eq!(to_markdown(&ast), "one\\\ntwo \\\nthree\n");
+    }
+
+    #[test]
+    fn test_to_markdown_output() {
+        let ast = parse("# Hi\n\n- a\n- b", &TranspileOptions::default());
+        assert_eq!(to_markdown(&ast), "# Hi\n\n- a\n- b\n");
+    }
+
+    #[test]
+    fn test_to_markdown_fragment() {
+        let ast = vec![Node::fragment([
+            Node::element("h1").child(Node::text("Hi")),
+            Node::element("p").child(Node::fragment([Node::text("a "), Node::element("em").child(Node::text("b"))])),
+        ])];
+        assert_eq!(to_markdown(&ast), "# Hi\n\na *b*\n");
+    }
+}
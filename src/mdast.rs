@@ -0,0 +1,220 @@
+//! Conversion to mdast, the markdown syntax tree of unified/remark.
+
+use indexmap::IndexMap;
+use serde_json::{json, Map, Value};
+use crate::{text_content, FootnoteNames, Node};
+
+/// Elements whose children are phrasing content in mdast.
+const PHRASING_PARENTS: &[&str] = &["p", "h1", "h2", "h3", "h4", "h5", "h6", "em", "strong", "del", "a", "td", "th"];
+
+/// Converts the AST to an mdast `root`
+/// (`{"type": "heading", "depth": 1, "children": [...]}` and so on), for
+/// remark and rehype plugins. Markdown elements map to their mdast nodes,
+/// footnotes by the default `FootnoteNames`; other elements, such as
+/// allowed HTML and custom components, become `mdxJsxFlowElement` or
+/// `mdxJsxTextElement` nodes, comments and raw HTML become `html`, and
+/// other raw nodes `code` with their format as `lang`. Table alignment and
+/// positions are not carried over.
+pub fn to_mdast(nodes: &[Node]) -> Value {
+    json!({ "type": "root", "children": convert_all(nodes, false) })
+}
+
+fn convert_all(nodes: &[Node], phrasing: bool) -> Vec<Value> {
+    let mut converted = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        convert(node, phrasing, &mut converted);
+    }
+    converted
+}
+
+fn convert(node: &Node, phrasing: bool, out: &mut Vec<Value>) {
+    match node {
+        Node::Element { tag, props, children, .. } => out.push(element(tag, props, children, phrasing)),
+        Node::Text { content, .. } if !phrasing && content.trim().is_empty() => {}
+        Node::Text { content, .. } => out.push(json!({ "type": "text", "value": content })),
+        Node::Fragment { children } => {
+            for child in children {
+                convert(child, phrasing, out);
+            }
+        }
+        Node::Comment { content, .. } => out.push(json!({ "type": "html", "value": format!("<!--{}-->", content) })),
+        Node::Raw { content, format, .. } if format == "html" => out.push(json!({ "type": "html", "value": content })),
+        Node::Raw { content, format, .. } => out.push(json!({ "type": "code", "lang": format, "value": content })),
+    }
+}
+
+fn element(tag: &str, props: &IndexMap<String, Value>, children: &[Node], phrasing: bool) -> Value {
+    let names = FootnoteNames::default();
+    let prop = |key: &str| props.get(key).and_then(Value::as_str);
+    let class = prop("className").or_else(|| prop("class")).unwrap_or_default();
+    let inner = || convert_all(children, PHRASING_PARENTS.contains(&tag));
+    match tag {
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+            json!({ "type": "heading", "depth": tag[1..].parse::<u8>().unwrap_or(1), "children": inner() })
+        }
+        "p" => json!({ "type": "paragraph", "children": inner() }),
+        "blockquote" => json!({ "type": "blockquote", "children": inner() }),
+        "em" => json!({ "type": "emphasis", "children": inner() }),
+        "strong" => json!({ "type": "strong", "children": inner() }),
+        "del" => json!({ "type": "delete", "children": inner() }),
+        "hr" => json!({ "type": "thematicBreak" }),
+        "br" => json!({ "type": "break" }),
+        "ul" | "ol" => {
+            let start = props.get("start").and_then(Value::as_u64).unwrap_or(1);
+            let mut list = json!({ "type": "list", "ordered": tag == "ol", "spread": false, "children": inner() });
+            if tag == "ol" {
+                list["start"] = start.into();
+            }
+            list
+        }
+        "li" => list_item(children),
+        "pre" => match children {
+            [Node::Element { tag, props, children, .. }] if tag == "code" => {
+                let class = props.get("className").or_else(|| props.get("class")).and_then(Value::as_str).unwrap_or_default();
+                let lang = class.split_whitespace().find_map(|class| class.strip_prefix("language-"));
+                let value = text_content(children);
+                json!({ "type": "code", "lang": lang, "meta": null, "value": value.strip_suffix('\n').unwrap_or(&value) })
+            }
+            _ => json!({ "type": "code", "lang": null, "meta": null, "value": text_content(children) }),
+        },
+        "code" => json!({ "type": "inlineCode", "value": text_content(children) }),
+        "sup" => match children {
+            [Node::Element { tag, props, .. }] if tag == "a" && props.get("className").and_then(Value::as_str) == Some(&names.ref_class) => {
+                let label = props.get("href").and_then(Value::as_str).and_then(|href| href.strip_prefix('#'))
+                    .map(|href| href.strip_prefix(names.id_prefix.as_str()).unwrap_or(href))
+                    .unwrap_or_default();
+                json!({ "type": "footnoteReference", "identifier": label.to_lowercase(), "label": label })
+            }
+            _ => jsx(tag, props, children, phrasing),
+        },
+        "div" if class == names.definition_class => {
+            let label = prop("id").map(|id| id.strip_prefix(names.id_prefix.as_str()).unwrap_or(id)).unwrap_or_default();
+            json!({ "type": "footnoteDefinition", "identifier": label.to_lowercase(), "label": label, "children": inner() })
+        }
+        "a" if prop("href").is_some() => {
+            json!({ "type": "link", "url": prop("href"), "title": prop("title"), "children": inner() })
+        }
+        "img" if prop("src").is_some() => {
+            json!({ "type": "image", "url": prop("src"), "title": prop("title"), "alt": prop("alt").unwrap_or_default() })
+        }
+        "table" => {
+            let mut rows = Vec::new();
+            collect_rows(children, &mut rows);
+            let columns = rows.first().and_then(|row| row["children"].as_array()).map_or(0, Vec::len);
+            json!({ "type": "table", "align": vec![Value::Null; columns], "children": rows })
+        }
+        _ => jsx(tag, props, children, phrasing),
+    }
+}
+
+/// A `listItem`, checked if it starts with a task list checkbox (directly
+/// or in its first paragraph).
+fn list_item(children: &[Node]) -> Value {
+    let is_checkbox = |node: &Node| matches!(node, Node::Element { tag, props, .. }
+        if tag == "input" && props.get("type").and_then(Value::as_str) == Some("checkbox"));
+    let checked_of = |node: &Node| match node {
+        Node::Element { props, .. } => Some(props.get("checked").and_then(Value::as_bool).unwrap_or(false)),
+        _ => None,
+    };
+    let mut children = children.to_vec();
+    let mut checked = None;
+    if children.first().is_some_and(is_checkbox) {
+        checked = checked_of(&children.remove(0));
+    } else if let Some(Node::Element { tag, children: inner, .. }) = children.first_mut() {
+        if tag == "p" && inner.first().is_some_and(is_checkbox) {
+            checked = checked_of(&inner.remove(0));
+        }
+    }
+    // A tight item's text sits directly in the `li`; mdast wants a paragraph.
+    let block = children.iter().any(|child| matches!(child, Node::Element { tag, .. } if is_block(tag)));
+    let children = match block {
+        true => convert_all(&children, false),
+        false => vec![json!({ "type": "paragraph", "children": convert_all(&children, true) })],
+    };
+    json!({ "type": "listItem", "spread": false, "checked": checked, "children": children })
+}
+
+fn is_block(tag: &str) -> bool {
+    matches!(tag, "p" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "blockquote" | "ul" | "ol" | "pre" | "hr" | "table" | "div")
+}
+
+fn collect_rows(nodes: &[Node], rows: &mut Vec<Value>) {
+    for node in nodes {
+        match node {
+            Node::Element { tag, children, .. } if tag == "tr" => {
+                let cells = children.iter().filter_map(|cell| match cell {
+                    Node::Element { tag, children, .. } if tag == "td" || tag == "th" => {
+                        Some(json!({ "type": "tableCell", "children": convert_all(children, true) }))
+                    }
+                    _ => None,
+                });
+                rows.push(json!({ "type": "tableRow", "children": cells.collect::<Vec<_>>() }));
+            }
+            node => {
+                if let Some(children) = node.children() {
+                    collect_rows(children, rows);
+                }
+            }
+        }
+    }
+}
+
+/// An MDX JSX element, in text or flow form depending on where it sits.
+fn jsx(tag: &str, props: &IndexMap<String, Value>, children: &[Node], phrasing: bool) -> Value {
+    let attributes: Vec<Value> = props.iter().map(|(name, value)| {
+        let value = match value {
+            Value::String(value) => Value::String(value.clone()),
+            Value::Bool(true) => Value::Null,
+            value => json!({ "type": "mdxJsxAttributeValueExpression", "value": value.to_string() }),
+        };
+        json!({ "type": "mdxJsxAttribute", "name": name, "value": value })
+    }).collect();
+    let mut node = Map::new();
+    node.insert("type".into(), if phrasing { "mdxJsxTextElement" } else { "mdxJsxFlowElement" }.into());
+    node.insert("name".into(), tag.into());
+    node.insert("attributes".into(), attributes.into());
+    node.insert("children".into(), convert_all(children, phrasing).into());
+    Value::Object(node)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_to_mdast() {
+        let markdown = "# Hi *there*\n\n- [x] done\n- `code`[^1]\n\n```rs\nx\n```\n\n[^1]: Note.";
+        let mdast = to_mdast(&parse(markdown, &TranspileOptions::default()));
+        assert_eq!(mdast, json!({ "type": "root", "children": [
+            { "type": "heading", "depth": 1, "children": [
+                { "type": "text", "value": "Hi " },
+                { "type": "emphasis", "children": [{ "type": "text", "value": "there" }] },
+            ] },
+            { "type": "list", "ordered": false, "spread": false, "children": [
+                { "type": "listItem", "spread": false, "checked": true, "children": [
+                    { "type": "paragraph", "children": [{ "type": "text", "value": "done" }] },
+                ] },
+                { "type": "listItem", "spread": false, "checked": null, "children": [
+                    { "type": "paragraph", "children": [
+                        { "type": "inlineCode", "value": "code" },
+                        { "type": "footnoteReference", "identifier": "1", "label": "1" },
+                    ] },
+                ] },
+            ] },
+            { "type": "code", "lang": "rs", "meta": null, "value": "x" },
+            { "type": "footnoteDefinition", "identifier": "1", "label": "1", "children": [
+                { "type": "paragraph", "children": [{ "type": "text", "value": "Note." }] },
+            ] },
+        ] }));
+
+        let options = TranspileOptions::builder().allowed_tags(["Badge"]).build();
+        let mdast = to_mdast(&parse("A <Badge tone=\"ok\">B</Badge>", &options));
+        assert_eq!(mdast["children"][0]["children"][1], json!({
+            "type": "mdxJsxTextElement",
+            "name": "Badge",
+            "attributes": [{ "type": "mdxJsxAttribute", "name": "tone", "value": "ok" }],
+            "children": [{ "type": "text", "value": "B" }],
+        }));
+    }
+}
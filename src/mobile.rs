@@ -0,0 +1,146 @@
+//! UniFFI bindings: `parse_markdown` with typed options and nodes for the
+//! generated Swift and Kotlin code. See `scripts/generate-bindings.sh`.
+
+use serde_json::Value;
+use crate::{parse, Node, Span, TranspileOptions};
+
+/// The subset of `TranspileOptions` exposed to Swift and Kotlin.
+#[derive(Debug, Clone, PartialEq, Eq, Default, uniffi::Record)]
+pub struct MarkdownOptions {
+    #[uniffi(default = [])]
+    pub allowed_tags: Vec<String>,
+    #[uniffi(default = false)]
+    pub source_spans: bool,
+    #[uniffi(default = false)]
+    pub heading_ids: bool,
+    #[uniffi(default = None)]
+    pub base_url: Option<String>,
+    #[uniffi(default = None)]
+    pub id_prefix: Option<String>,
+    #[uniffi(default = None)]
+    pub max_depth: Option<u32>,
+    #[uniffi(default = None)]
+    pub max_input_bytes: Option<u64>,
+    #[uniffi(default = None)]
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Record)]
+pub struct SourceSpan {
+    pub start: u64,
+    pub end: u64,
+    pub line: u64,
+    pub column: u64,
+}
+
+/// A prop value; arrays and objects (such as `style`) are kept as JSON.
+#[derive(Debug, Clone, PartialEq, uniffi::Enum)]
+pub enum PropValue {
+    Null,
+    Bool { value: bool },
+    Number { value: f64 },
+    String { value: String },
+    Json { json: String },
+}
+
+#[derive(Debug, Clone, PartialEq, uniffi::Record)]
+pub struct Prop {
+    pub name: String,
+    pub value: PropValue,
+}
+
+/// `Node`, with props in order as a list.
+#[derive(Debug, Clone, PartialEq, uniffi::Enum)]
+pub enum MarkdownNode {
+    Element { tag: String, props: Vec<Prop>, children: Vec<MarkdownNode>, span: Option<SourceSpan> },
+    Text { content: String, span: Option<SourceSpan> },
+    Fragment { children: Vec<MarkdownNode> },
+    Comment { content: String, span: Option<SourceSpan> },
+    Raw { content: String, format: String, span: Option<SourceSpan> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error, uniffi::Error)]
+pub enum MarkdownError {
+    #[error("invalid base URL: {message}")]
+    InvalidBaseUrl { message: String },
+}
+
+#[uniffi::export]
+pub fn parse_markdown(markdown: String, options: MarkdownOptions) -> Result<Vec<MarkdownNode>, MarkdownError> {
+    let base_url = match &options.base_url {
+        Some(url) => Some(url::Url::parse(url).map_err(|e| MarkdownError::InvalidBaseUrl { message: e.to_string() })?),
+        None => None,
+    };
+    let options = TranspileOptions {
+        allowed_tags: options.allowed_tags,
+        source_spans: options.source_spans,
+        heading_ids: options.heading_ids,
+        base_url,
+        id_prefix: options.id_prefix,
+        max_depth: options.max_depth.map(|depth| depth as usize),
+        max_input_bytes: options.max_input_bytes.map(|bytes| bytes as usize),
+        timeout_ms: options.timeout_ms,
+        ..Default::default()
+    };
+    Ok(parse(&markdown, &options).iter().map(MarkdownNode::from).collect())
+}
+
+impl From<&Span> for SourceSpan {
+    fn from(span: &Span) -> Self {
+        SourceSpan { start: span.start as u64, end: span.end as u64, line: span.line as u64, column: span.column as u64 }
+    }
+}
+
+impl From<&Value> for PropValue {
+    fn from(value: &Value) -> Self {
+        match value {
+            Value::Null => PropValue::Null,
+            Value::Bool(value) => PropValue::Bool { value: *value },
+            Value::Number(number) => PropValue::Number { value: number.as_f64().unwrap_or(f64::NAN) },
+            Value::String(value) => PropValue::String { value: value.clone() },
+            json => PropValue::Json { json: json.to_string() },
+        }
+    }
+}
+
+impl From<&Node> for MarkdownNode {
+    fn from(node: &Node) -> Self {
+        let span = |span: &Option<Span>| span.as_ref().map(SourceSpan::from);
+        let children = |children: &[Node]| children.iter().map(MarkdownNode::from).collect();
+        match node {
+            Node::Element { tag, props, children: nodes, span: s, .. } => MarkdownNode::Element {
+                tag: tag.clone(),
+                props: props.iter().map(|(name, value)| Prop { name: name.clone(), value: value.into() }).collect(),
+                children: children(nodes),
+                span: span(s),
+            },
+            Node::Text { content, span: s } => MarkdownNode::Text { content: content.clone(), span: span(s) },
+            Node::Fragment { children: nodes } => MarkdownNode::Fragment { children: children(nodes) },
+            Node::Comment { content, span: s } => MarkdownNode::Comment { content: content.clone(), span: span(s) },
+            Node::Raw { content, format, span: s } => {
+                MarkdownNode::Raw { content: content.clone(), format: format.clone(), span: span(s) }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_markdown() {
+        let options = MarkdownOptions { base_url: Some("https://x.org/".into()), ..Default::default() };
+        let ast = parse_markdown("[a](b)".into(), options).unwrap();
+        let MarkdownNode::Element { children, .. } = &ast[0] else { panic!("expected p") };
+        assert_eq!(children[0], MarkdownNode::Element {
+            tag: "a".into(),
+            props: vec![Prop { name: "href".into(), value: PropValue::String { value: "https://x.org/b".into() } }],
+            children: vec![MarkdownNode::Text { content: "a".into(), span: None }],
+            span: None,
+        });
+
+        let options = MarkdownOptions { base_url: Some("nope".into()), ..Default::default() };
+        assert!(matches!(parse_markdown("x".into(), options), Err(MarkdownError::InvalidBaseUrl { .. })));
+    }
+}
@@ -0,0 +1,36 @@
+//! MessagePack encoding of the AST, for moving it across the WASM and JNI
+//! boundaries as binary instead of JSON text.
+//!
+//! Nodes are written as maps with the same field names as the JSON form,
+//! so any MessagePack decoder yields the familiar structure.
+
+use crate::Node;
+
+pub use rmp_serde::decode::Error as MsgpackError;
+
+/// Encodes `nodes` as MessagePack.
+pub fn to_msgpack(nodes: &[Node]) -> Vec<u8> {
+    rmp_serde::to_vec_named(nodes).expect("AST serialization is infallible")
+}
+
+/// Decodes nodes written by `to_msgpack`.
+pub fn from_msgpack(bytes: &[u8]) -> Result<Vec<Node>, MsgpackError> {
+    rmp_serde::from_slice(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_msgpack_round_trip() {
+        let options = TranspileOptions { source_spans: true, ..Default::default() };
+        let ast = parse("# Hi\n\n- [x] done <!-- c -->\n\n| a |\n| - |\n| 1 |", &options);
+        let bytes = to_msgpack(&ast);
+
+        assert_eq!(from_msgpack(&bytes).unwrap(), ast);
+        assert!(bytes.len() < serde_json::to_string(&ast).unwrap().len());
+        assert!(from_msgpack(&bytes[..bytes.len() / 2]).is_err());
+    }
+}
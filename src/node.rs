@@ -0,0 +1,49 @@
+//! Node.js native addon (N-API): `parse` and `parseAsync` returning the
+//! AST as plain JS objects. Built with `napi build --features node`.
+
+use napi::bindgen_prelude::{AsyncTask, Error, Result, Status};
+use napi::{Env, JsUnknown, Task};
+use napi_derive::napi;
+use serde_json::Value;
+use crate::TranspileOptions;
+
+fn options(options: Option<Value>) -> Result<TranspileOptions> {
+    serde_json::from_value(options.unwrap_or_else(|| Value::Object(Default::default())))
+        .map_err(|e| Error::new(Status::InvalidArg, e.to_string()))
+}
+
+fn parse_value(markdown: &str, options: &TranspileOptions) -> Result<Value> {
+    serde_json::to_value(crate::parse(markdown, options)).map_err(|e| Error::from_reason(e.to_string()))
+}
+
+/// Parses `markdown` on the calling thread. `options` is a
+/// `TranspileOptions` object with the Rust field names.
+#[napi]
+pub fn parse(markdown: String, options: Option<Value>) -> Result<Value> {
+    parse_value(&markdown, &self::options(options)?)
+}
+
+pub struct ParseTask {
+    markdown: String,
+    options: TranspileOptions,
+}
+
+impl Task for ParseTask {
+    type Output = Value;
+    type JsValue = JsUnknown;
+
+    fn compute(&mut self) -> Result<Value> {
+        parse_value(&self.markdown, &self.options)
+    }
+
+    fn resolve(&mut self, env: Env, output: Value) -> Result<JsUnknown> {
+        env.to_js_value(&output)
+    }
+}
+
+/// Like `parse`, but parses on the libuv threadpool and returns a
+/// `Promise`.
+#[napi(ts_return_type = "Promise<unknown>")]
+pub fn parse_async(markdown: String, options: Option<Value>) -> Result<AsyncTask<ParseTask>> {
+    Ok(AsyncTask::new(ParseTask { markdown, options: self::options(options)? }))
+}
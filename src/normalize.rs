@@ -0,0 +1,84 @@
+//! AST normalization: fewer, larger text nodes, no empty wrappers and no
+//! fragments.
+
+use crate::Node;
+
+/// Formatting elements that render nothing without children or props.
+const DROPPABLE_TAGS: &[&str] = &["p", "em", "strong", "del", "span"];
+
+/// Splices fragments into their parent, merges adjacent text siblings and
+/// removes empty formatting elements, recursively. Merged text keeps a span
+/// covering all of its parts.
+pub fn normalize(nodes: &mut Vec<Node>) {
+    let mut merged: Vec<Node> = Vec::with_capacity(nodes.len());
+    for node in nodes.drain(..) {
+        push_normalized(&mut merged, node);
+    }
+    *nodes = merged;
+}
+
+fn push_normalized(merged: &mut Vec<Node>, mut node: Node) {
+    match &mut node {
+        Node::Fragment { children } => {
+            for child in children.drain(..) {
+                push_normalized(merged, child);
+            }
+            return;
+        }
+        Node::Element { tag, props, children, .. } => {
+            normalize(children);
+            if children.is_empty() && props.is_empty() && DROPPABLE_TAGS.contains(&tag.as_str()) {
+                return;
+            }
+        }
+        Node::Text { .. } | Node::Comment { .. } | Node::Raw { .. } => {}
+    }
+    match (merged.last_mut(), node) {
+        (
+            Some(Node::Text { content: last, span: last_span }),
+            Node::Text { content, span },
+        ) => {
+            last.push_str(&content);
+            if let (Some(last_span), Some(span)) = (last_span.as_mut(), span) {
+                last_span.end = last_span.end.max(span.end);
+            }
+        }
+        (_, node) => merged.push(node),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_normalize_merges_text() {
+        let options = TranspileOptions { source_spans: true, ..Default::default() };
+        let mut ast = parse("one\ntwo &amp; three", &options);
+        normalize(&mut ast);
+
+        if let Node::Element { children, .. } = &ast[0] {
+            assert_eq!(children.len(), 1);
+            if let Node::Text { content, span } = &children[0] {
+                assert_eq!(content, "one\ntwo & three");
+                assert_eq!(span.unwrap().start, 0);
+                assert_eq!(span.unwrap().end, 19);
+            }
+        }
+    }
+
+    #[test]
+    fn test_normalize_drops_empty_elements() {
+        let mut nodes = vec![
+            Node::fragment([Node::element("p")]),
+            Node::element("hr"),
+            Node::text("a"),
+            Node::fragment([Node::element("em"), Node::text("b")]),
+        ];
+        normalize(&mut nodes);
+
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(nodes[1], Node::text("ab"));
+    }
+}
@@ -0,0 +1,314 @@
+//! Notion API block objects.
+
+use serde_json::{json, Map, Value};
+use crate::markdown::BLOCK_TAGS;
+use crate::{text_content, Node};
+
+/// The longest `content` Notion accepts in one rich text object.
+const MAX_TEXT: usize = 2000;
+
+/// Code block languages Notion knows under another name.
+const LANGUAGE_ALIASES: &[(&str, &str)] = &[
+    ("js", "javascript"),
+    ("jsx", "javascript"),
+    ("ts", "typescript"),
+    ("tsx", "typescript"),
+    ("py", "python"),
+    ("rb", "ruby"),
+    ("rs", "rust"),
+    ("sh", "shell"),
+    ("bash", "shell"),
+    ("zsh", "shell"),
+    ("yml", "yaml"),
+    ("md", "markdown"),
+    ("cs", "c#"),
+    ("csharp", "c#"),
+    ("cpp", "c++"),
+    ("kt", "kotlin"),
+    ("golang", "go"),
+    ("text", "plain text"),
+    ("txt", "plain text"),
+];
+
+/// Code block languages Notion accepts.
+const LANGUAGES: &[&str] = &[
+    "abap", "arduino", "bash", "basic", "c", "clojure", "coffeescript", "c++", "c#", "css", "dart", "diff", "docker",
+    "elixir", "elm", "erlang", "flow", "fortran", "f#", "gherkin", "glsl", "go", "graphql", "groovy", "haskell", "html",
+    "java", "javascript", "json", "julia", "kotlin", "latex", "less", "lisp", "livescript", "lua", "makefile",
+    "markdown", "markup", "matlab", "mermaid", "nix", "objective-c", "ocaml", "pascal", "perl", "php", "plain text",
+    "powershell", "prolog", "protobuf", "python", "r", "reason", "ruby", "rust", "sass", "scala", "scheme", "scss",
+    "shell", "sql", "swift", "typescript", "vb.net", "verilog", "vhdl", "visual basic", "webassembly", "xml", "yaml",
+];
+
+/// Converts `nodes` to Notion block objects for the `children` of an
+/// append-block-children request: `paragraph`, `heading_1`..`heading_3`
+/// (deeper headings become `heading_3`), `bulleted_list_item`,
+/// `numbered_list_item`, `to_do`, `quote`, `code`, `divider`, external
+/// `image`s and `table`s, with nested list items as `children`. Inline
+/// formatting becomes rich text `annotations` and links `text.link`;
+/// comments and raw content are dropped.
+pub fn to_notion(nodes: &[Node]) -> Value {
+    Value::Array(blocks(nodes))
+}
+
+fn is_block(node: &Node) -> bool {
+    match node {
+        Node::Element { tag, children, .. } => BLOCK_TAGS.contains(&tag.as_str()) || children.iter().any(is_block),
+        Node::Fragment { children } => children.iter().any(is_block),
+        _ => false,
+    }
+}
+
+fn block(kind: &str, content: Value) -> Value {
+    json!({ "object": "block", "type": kind, kind: content })
+}
+
+fn language(name: Option<&str>) -> &'static str {
+    let name = name.unwrap_or_default().to_ascii_lowercase();
+    let name = LANGUAGE_ALIASES.iter().find(|&&(alias, _)| alias == name).map_or(name.as_str(), |&(_, notion)| notion);
+    LANGUAGES.iter().find(|&&language| language == name).copied().unwrap_or("plain text")
+}
+
+fn blocks(nodes: &[Node]) -> Vec<Value> {
+    let mut blocks = Vec::new();
+    let mut run = Vec::new();
+    for node in nodes {
+        if is_block(node) {
+            flush(&mut run, &mut blocks);
+            convert(node, &mut blocks);
+        } else {
+            run.push(node.clone());
+        }
+    }
+    flush(&mut run, &mut blocks);
+    blocks
+}
+
+fn flush(run: &mut Vec<Node>, blocks: &mut Vec<Value>) {
+    let text = rich_text(run);
+    if !text.is_empty() {
+        blocks.push(block("paragraph", json!({ "rich_text": text })));
+    }
+    images(run, blocks);
+    run.clear();
+}
+
+fn convert(node: &Node, out: &mut Vec<Value>) {
+    let Node::Element { tag, children, .. } = node else {
+        if let Node::Fragment { children } = node {
+            out.extend(blocks(children));
+        }
+        return;
+    };
+    match tag.as_str() {
+        "p" => {
+            let text = rich_text(children);
+            if !text.is_empty() {
+                out.push(block("paragraph", json!({ "rich_text": text })));
+            }
+            images(children, out);
+        }
+        "h1" => out.push(block("heading_1", json!({ "rich_text": rich_text(children) }))),
+        "h2" => out.push(block("heading_2", json!({ "rich_text": rich_text(children) }))),
+        "h3" | "h4" | "h5" | "h6" => out.push(block("heading_3", json!({ "rich_text": rich_text(children) }))),
+        "blockquote" => {
+            // The quote's text is its first paragraph; the rest nest inside.
+            let mut inner = blocks(children);
+            let first = match inner.first() {
+                Some(first) if first["type"] == "paragraph" => inner.remove(0)["paragraph"]["rich_text"].take(),
+                _ => json!([]),
+            };
+            let mut quote = json!({ "rich_text": first });
+            if !inner.is_empty() {
+                quote["children"] = inner.into();
+            }
+            out.push(block("quote", quote));
+        }
+        "ul" | "ol" => {
+            for item in children {
+                let Node::Element { tag: item_tag, children, .. } = item else { continue };
+                if item_tag == "li" {
+                    out.push(list_item(tag == "ol", children));
+                }
+            }
+        }
+        "pre" => {
+            let name = match children.as_slice() {
+                [Node::Element { tag, props, .. }] if tag == "code" => {
+                    let class = props.get("className").or_else(|| props.get("class")).and_then(Value::as_str).unwrap_or_default();
+                    class.split_whitespace().find_map(|class| class.strip_prefix("language-"))
+                }
+                _ => None,
+            };
+            let code = text_content(children);
+            let code = code.strip_suffix('\n').unwrap_or(&code);
+            out.push(block("code", json!({ "rich_text": chunks(code, None, &Map::new()), "language": language(name) })));
+        }
+        "hr" => out.push(block("divider", json!({}))),
+        "table" => {
+            let mut rows = Vec::new();
+            table_rows(children, &mut rows);
+            let width = rows.first().and_then(|row| row["table_row"]["cells"].as_array()).map_or(0, Vec::len);
+            let header = crate::find(children, |node| node.tag() == Some("th")).is_some();
+            out.push(block("table", json!({
+                "table_width": width,
+                "has_column_header": header,
+                "has_row_header": false,
+                "children": rows,
+            })));
+        }
+        _ => out.extend(blocks(children)),
+    }
+}
+
+/// Image blocks for the images in a paragraph, which Notion cannot show
+/// inline; their `alt` text becomes the caption.
+fn images(nodes: &[Node], out: &mut Vec<Value>) {
+    for node in nodes {
+        match node {
+            Node::Element { tag, props, .. } if tag == "img" => {
+                let Some(src) = props.get("src").and_then(Value::as_str) else { continue };
+                let alt = props.get("alt").and_then(Value::as_str).unwrap_or_default();
+                let caption = if alt.is_empty() { Vec::new() } else { chunks(alt, None, &Map::new()) };
+                out.push(block("image", json!({ "type": "external", "external": { "url": src }, "caption": caption })));
+            }
+            node => {
+                if let Some(children) = node.children() {
+                    images(children, out);
+                }
+            }
+        }
+    }
+}
+
+/// A list item; its first paragraph (or inline content) is its text and
+/// the remaining blocks, nested lists included, its `children`.
+fn list_item(ordered: bool, children: &[Node]) -> Value {
+    let checked = children.iter().chain(children.iter().filter_map(Node::children).flatten()).find_map(|node| match node {
+        Node::Element { tag, props, .. } if tag == "input" => Some(props.get("checked").and_then(Value::as_bool).unwrap_or(false)),
+        _ => None,
+    });
+    let mut inner = blocks(children);
+    let text = match inner.first() {
+        Some(first) if first["type"] == "paragraph" => inner.remove(0)["paragraph"]["rich_text"].take(),
+        _ => json!([]),
+    };
+    let (kind, mut content) = match (checked, ordered) {
+        (Some(checked), _) => ("to_do", json!({ "rich_text": text, "checked": checked })),
+        (None, true) => ("numbered_list_item", json!({ "rich_text": text })),
+        (None, false) => ("bulleted_list_item", json!({ "rich_text": text })),
+    };
+    if !inner.is_empty() {
+        content["children"] = inner.into();
+    }
+    block(kind, content)
+}
+
+fn table_rows(nodes: &[Node], rows: &mut Vec<Value>) {
+    for node in nodes {
+        match node {
+            Node::Element { tag, children, .. } if tag == "tr" => {
+                let cells: Vec<Value> = children.iter().filter_map(|cell| match cell {
+                    Node::Element { tag, children, .. } if tag == "td" || tag == "th" => Some(rich_text(children).into()),
+                    _ => None,
+                }).collect();
+                rows.push(json!({ "object": "block", "type": "table_row", "table_row": { "cells": cells } }));
+            }
+            node => {
+                if let Some(children) = node.children() {
+                    table_rows(children, rows);
+                }
+            }
+        }
+    }
+}
+
+/// Rich text objects for inline content, with leading and trailing
+/// whitespace trimmed.
+fn rich_text(nodes: &[Node]) -> Vec<Value> {
+    let mut texts = Vec::new();
+    for node in nodes {
+        inline(node, None, &Map::new(), &mut texts);
+    }
+    if let Some(first) = texts.first_mut() {
+        let content = first["text"]["content"].as_str().unwrap_or_default().trim_start().to_string();
+        first["text"]["content"] = content.into();
+    }
+    if let Some(last) = texts.last_mut() {
+        let content = last["text"]["content"].as_str().unwrap_or_default().trim_end().to_string();
+        last["text"]["content"] = content.into();
+    }
+    texts.retain(|text| text["text"]["content"] != "");
+    texts
+}
+
+fn inline(node: &Node, link: Option<&str>, annotations: &Map<String, Value>, out: &mut Vec<Value>) {
+    match node {
+        Node::Text { content, .. } => out.extend(chunks(&content.replace('\n', " "), link, annotations)),
+        Node::Element { tag, props, children, .. } => {
+            let mut annotations = annotations.clone();
+            let mut link = link;
+            match tag.as_str() {
+                "br" => return out.extend(chunks("\n", link, &annotations)),
+                "img" | "input" => return,
+                "strong" | "b" => _ = annotations.insert("bold".into(), true.into()),
+                "em" | "i" => _ = annotations.insert("italic".into(), true.into()),
+                "del" | "s" => _ = annotations.insert("strikethrough".into(), true.into()),
+                "u" => _ = annotations.insert("underline".into(), true.into()),
+                "code" => _ = annotations.insert("code".into(), true.into()),
+                "a" => link = props.get("href").and_then(Value::as_str).or(link),
+                _ => {}
+            }
+            for child in children {
+                inline(child, link, &annotations, out);
+            }
+        }
+        Node::Fragment { children } => {
+            for child in children {
+                inline(child, link, annotations, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Text objects for `content`, split to Notion's length limit.
+fn chunks(content: &str, link: Option<&str>, annotations: &Map<String, Value>) -> Vec<Value> {
+    let chars: Vec<char> = content.chars().collect();
+    chars.chunks(MAX_TEXT).map(|chunk| {
+        let mut text = json!({ "type": "text", "text": { "content": chunk.iter().collect::<String>(), "link": link.map(|url| json!({ "url": url })) } });
+        if !annotations.is_empty() {
+            text["annotations"] = annotations.clone().into();
+        }
+        text
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_to_notion() {
+        let markdown = "# Hi\n\nA **b** [c](https://c.io)\n\n![cat](https://c.io/cat.png)\n\n- [x] done\n  - nested\n\n```js\nx\n```";
+        let text = |content: &str| json!({ "type": "text", "text": { "content": content, "link": null } });
+        assert_eq!(to_notion(&parse(markdown, &TranspileOptions::default())), json!([
+            { "object": "block", "type": "heading_1", "heading_1": { "rich_text": [text("Hi")] } },
+            { "object": "block", "type": "paragraph", "paragraph": { "rich_text": [
+                text("A "),
+                { "type": "text", "text": { "content": "b", "link": null }, "annotations": { "bold": true } },
+                text(" "),
+                { "type": "text", "text": { "content": "c", "link": { "url": "https://c.io" } } },
+            ] } },
+            { "object": "block", "type": "image", "image": {
+                "type": "external", "external": { "url": "https://c.io/cat.png" }, "caption": [text("cat")],
+            } },
+            { "object": "block", "type": "to_do", "to_do": { "rich_text": [text("done")], "checked": true, "children": [
+                { "object": "block", "type": "bulleted_list_item", "bulleted_list_item": { "rich_text": [text("nested")] } },
+            ] } },
+            { "object": "block", "type": "code", "code": { "rich_text": [text("x")], "language": "javascript" } },
+        ]));
+        assert_eq!(chunks(&"a".repeat(2001), None, &Map::new()).len(), 2);
+    }
+}
@@ -0,0 +1,80 @@
+//! Heading outline for sidebars and document maps.
+
+use serde::Serialize;
+use crate::slug::UniqueSlugs;
+use crate::visitor::{visit, Flow, Visitor};
+use crate::{text_content, Node, Span};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Heading {
+    /// 1 to 6.
+    pub level: u8,
+    pub text: String,
+    /// The heading's `id` prop if it has one, otherwise a GitHub-style
+    /// slug of `text`, unique within the outline.
+    pub slug: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub span: Option<Span>,
+}
+
+/// Lists the headings in `nodes` in document order, including those
+/// nested in other blocks.
+pub fn outline(nodes: &[Node]) -> Vec<Heading> {
+    let mut collector = Collector::default();
+    visit(nodes, &mut collector);
+    collector.headings
+}
+
+/// The level of an `h1`-`h6` element.
+pub(crate) fn heading_level(node: &Node) -> Option<u8> {
+    match node {
+        Node::Element { tag, .. } => match tag.as_bytes() {
+            [b'h', level @ b'1'..=b'6'] => Some(level - b'0'),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[derive(Default)]
+struct Collector {
+    headings: Vec<Heading>,
+    slugger: UniqueSlugs<'static>,
+}
+
+impl Visitor for Collector {
+    fn enter(&mut self, node: &Node) -> Flow {
+        let (Some(level), Node::Element { props, children, span, .. }) = (heading_level(node), node) else {
+            return Flow::Continue;
+        };
+        let text = text_content(children);
+        let slug = match props.get("id").and_then(|v| v.as_str()) {
+            Some(id) => id.to_string(),
+            None => self.slugger.slug(&text),
+        };
+        self.headings.push(Heading { level, text, slug, span: *span });
+        Flow::SkipChildren
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_outline() {
+        let options = TranspileOptions { source_spans: true, ..Default::default() };
+        let ast = parse("# Intro\n\ntext\n\n## Setup *fast*\n\n> ### Note\n\n## Setup fast", &options);
+
+        let headings = outline(&ast);
+        let summary: Vec<_> = headings.iter().map(|h| (h.level, h.text.as_str(), h.slug.as_str())).collect();
+        assert_eq!(summary, [
+            (1, "Intro", "intro"),
+            (2, "Setup fast", "setup-fast"),
+            (3, "Note", "note"),
+            (2, "Setup fast", "setup-fast-1"),
+        ]);
+        assert_eq!(headings[0].span, Some(Span { start: 0, end: 8, line: 1, column: 1 }));
+    }
+}
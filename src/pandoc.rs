@@ -0,0 +1,325 @@
+//! Export to Pandoc's JSON AST, for converting to DOCX, LaTeX, EPUB and
+//! the rest with `pandoc -f json`.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use indexmap::IndexMap;
+use serde_json::{json, Value};
+use crate::{text_content, FootnoteNames, Node};
+
+/// The pandoc-types version the output follows.
+const API_VERSION: [u32; 3] = [1, 23, 1];
+
+const BLOCK_TAGS: &[&str] = &[
+    "p", "h1", "h2", "h3", "h4", "h5", "h6", "blockquote", "ul", "ol", "li", "pre", "hr", "table", "div", "section",
+    "article", "aside", "header", "footer", "nav", "main", "figure", "details", "dl",
+];
+
+/// Converts the AST to a Pandoc document (`{"pandoc-api-version": ...,
+/// "meta": {}, "blocks": [...]}`). Markdown elements map to their Pandoc
+/// blocks and inlines, and footnotes (by the default `FootnoteNames`)
+/// become `Note`s at their references. Other elements become a `Div` or
+/// `Span` with the tag as class and string props as attributes; raw HTML
+/// and comments become `RawBlock`/`RawInline` `html`, other raw nodes code
+/// with their format as class.
+pub fn to_pandoc(nodes: &[Node]) -> Value {
+    let names = FootnoteNames::default();
+    let mut notes = HashMap::new();
+    collect_notes(nodes, &names, &mut notes);
+    let writer = Writer { names, notes, expanding: RefCell::default() };
+    json!({ "pandoc-api-version": API_VERSION, "meta": {}, "blocks": writer.blocks(nodes) })
+}
+
+/// Footnote definitions by label.
+fn collect_notes<'n>(nodes: &'n [Node], names: &FootnoteNames, notes: &mut HashMap<String, &'n [Node]>) {
+    for node in nodes {
+        if let Some(label) = definition_label(node, names) {
+            notes.insert(label.to_string(), node.children().map_or(&[][..], Vec::as_slice));
+        } else if let Some(children) = node.children() {
+            collect_notes(children, names, notes);
+        }
+    }
+}
+
+fn definition_label<'n>(node: &'n Node, names: &FootnoteNames) -> Option<&'n str> {
+    let Node::Element { tag, props, .. } = node else { return None };
+    if tag != "div" || class(props) != names.definition_class {
+        return None;
+    }
+    props.get("id")?.as_str()?.strip_prefix(names.id_prefix.as_str())
+}
+
+fn class(props: &IndexMap<String, Value>) -> &str {
+    props.get("className").or_else(|| props.get("class")).and_then(Value::as_str).unwrap_or_default()
+}
+
+/// A Pandoc `Attr`: identifier, classes and string key-value pairs.
+fn attr(props: &IndexMap<String, Value>, extra_class: Option<&str>) -> Value {
+    let id = props.get("id").and_then(Value::as_str).unwrap_or_default();
+    let classes: Vec<&str> = extra_class.into_iter().chain(class(props).split_whitespace()).collect();
+    let pairs: Vec<(&str, &str)> = props.iter()
+        .filter(|(key, _)| !matches!(key.as_str(), "id" | "className" | "class" | "key"))
+        .filter_map(|(key, value)| Some((key.as_str(), value.as_str()?)))
+        .collect();
+    json!([id, classes, pairs])
+}
+
+fn is_block(node: &Node) -> bool {
+    match node {
+        Node::Element { tag, .. } => BLOCK_TAGS.contains(&tag.as_str()),
+        Node::Fragment { children } => children.iter().any(is_block),
+        Node::Raw { content, .. } => content.ends_with('\n'),
+        Node::Text { .. } | Node::Comment { .. } => false,
+    }
+}
+
+struct Writer<'n> {
+    names: FootnoteNames,
+    notes: HashMap<String, &'n [Node]>,
+    /// Labels of the notes being inlined, so cyclic references stop recursing.
+    expanding: RefCell<HashSet<String>>,
+}
+
+impl Writer<'_> {
+    /// Blocks for `nodes`, with runs of inline content wrapped in `Plain`.
+    fn blocks(&self, nodes: &[Node]) -> Vec<Value> {
+        let mut blocks = Vec::new();
+        let mut run = Vec::new();
+        for node in nodes {
+            if is_block(node) || matches!(node, Node::Comment { .. }) {
+                self.flush(&mut run, &mut blocks);
+                self.block(node, &mut blocks);
+            } else {
+                self.inline(node, &mut run);
+            }
+        }
+        self.flush(&mut run, &mut blocks);
+        blocks
+    }
+
+    fn flush(&self, run: &mut Vec<Value>, blocks: &mut Vec<Value>) {
+        while matches!(run.last(), Some(inline) if inline["t"] == "Space" || inline["t"] == "SoftBreak") {
+            run.pop();
+        }
+        if !run.is_empty() {
+            blocks.push(json!({ "t": "Plain", "c": std::mem::take(run) }));
+        }
+    }
+
+    fn block(&self, node: &Node, out: &mut Vec<Value>) {
+        let (tag, props, children) = match node {
+            Node::Element { tag, props, children, .. } => (tag.as_str(), props, children),
+            Node::Fragment { children } => return out.extend(self.blocks(children)),
+            Node::Comment { content, .. } => return out.push(json!({ "t": "RawBlock", "c": ["html", format!("<!--{}-->", content)] })),
+            Node::Raw { content, format, .. } if format == "html" => return out.push(json!({ "t": "RawBlock", "c": ["html", content] })),
+            Node::Raw { content, format, .. } => return out.push(json!({ "t": "CodeBlock", "c": [["", [format], []], content] })),
+            Node::Text { .. } => return,
+        };
+        if definition_label(node, &self.names).is_some() {
+            return;
+        }
+        out.push(match tag {
+            "p" => json!({ "t": "Para", "c": self.inlines(children) }),
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                json!({ "t": "Header", "c": [tag[1..].parse::<u8>().unwrap_or(1), attr(props, None), self.inlines(children)] })
+            }
+            "blockquote" => json!({ "t": "BlockQuote", "c": self.blocks(children) }),
+            "hr" => json!({ "t": "HorizontalRule" }),
+            "ul" => json!({ "t": "BulletList", "c": self.items(children) }),
+            "ol" => {
+                let start = props.get("start").and_then(Value::as_i64).unwrap_or(1);
+                json!({ "t": "OrderedList", "c": [[start, { "t": "Decimal" }, { "t": "Period" }], self.items(children)] })
+            }
+            "pre" => match children.as_slice() {
+                [Node::Element { tag, props, children, .. }] if tag == "code" => {
+                    let language = class(props).split_whitespace().filter_map(|class| class.strip_prefix("language-"));
+                    let code = text_content(children);
+                    json!({ "t": "CodeBlock", "c": [["", language.collect::<Vec<_>>(), []], code.strip_suffix('\n').unwrap_or(&code)] })
+                }
+                _ => json!({ "t": "CodeBlock", "c": [attr(props, None), text_content(children)] }),
+            },
+            "table" => self.table(props, children),
+            "div" => json!({ "t": "Div", "c": [attr(props, None), self.blocks(children)] }),
+            _ => json!({ "t": "Div", "c": [attr(props, Some(tag)), self.blocks(children)] }),
+        });
+    }
+
+    fn items(&self, children: &[Node]) -> Vec<Vec<Value>> {
+        children.iter().filter_map(|child| match child {
+            Node::Element { tag, children, .. } if tag == "li" => Some(self.blocks(children)),
+            _ => None,
+        }).collect()
+    }
+
+    fn table(&self, props: &IndexMap<String, Value>, children: &[Node]) -> Value {
+        let mut head = Vec::new();
+        let mut body = Vec::new();
+        for section in children {
+            let Node::Element { tag, children: rows, .. } = section else { continue };
+            let rows = rows.iter().filter_map(|row| match row {
+                Node::Element { tag, children, .. } if tag == "tr" => Some(self.row(children)),
+                _ => None,
+            });
+            match tag.as_str() {
+                "thead" => head.extend(rows),
+                "tr" => body.push(self.row(section.children().map_or(&[][..], Vec::as_slice))),
+                _ => body.extend(rows),
+            }
+        }
+        let columns = head.first().or(body.first()).and_then(|row| row[1].as_array()).map_or(0, Vec::len);
+        let specs = vec![json!([{ "t": "AlignDefault" }, { "t": "ColWidthDefault" }]); columns];
+        let empty = json!(["", [], []]);
+        json!({ "t": "Table", "c": [
+            attr(props, None),
+            [null, []],
+            specs,
+            [empty, head],
+            [[empty, 0, [], body]],
+            [empty, []],
+        ] })
+    }
+
+    fn row(&self, cells: &[Node]) -> Value {
+        let cells: Vec<Value> = cells.iter().filter_map(|cell| match cell {
+            Node::Element { tag, children, .. } if tag == "td" || tag == "th" => {
+                Some(json!([["", [], []], { "t": "AlignDefault" }, 1, 1, self.blocks(children)]))
+            }
+            _ => None,
+        }).collect();
+        json!([["", [], []], cells])
+    }
+
+    fn inlines(&self, nodes: &[Node]) -> Vec<Value> {
+        let mut inlines = Vec::new();
+        for node in nodes {
+            self.inline(node, &mut inlines);
+        }
+        inlines
+    }
+
+    fn inline(&self, node: &Node, out: &mut Vec<Value>) {
+        let (tag, props, children) = match node {
+            Node::Element { tag, props, children, .. } => (tag.as_str(), props, children),
+            Node::Text { content, .. } => return text(content, out),
+            Node::Fragment { children } => {
+                for child in children {
+                    self.inline(child, out);
+                }
+                return;
+            }
+            Node::Comment { content, .. } => return out.push(json!({ "t": "RawInline", "c": ["html", format!("<!--{}-->", content)] })),
+            Node::Raw { content, format, .. } if format == "html" => return out.push(json!({ "t": "RawInline", "c": ["html", content] })),
+            Node::Raw { content, format, .. } => return out.push(json!({ "t": "Code", "c": [["", [format], []], content] })),
+        };
+        let string = |key: &str| props.get(key).and_then(Value::as_str).unwrap_or_default();
+        out.push(match tag {
+            "em" | "i" => json!({ "t": "Emph", "c": self.inlines(children) }),
+            "strong" | "b" => json!({ "t": "Strong", "c": self.inlines(children) }),
+            "del" | "s" => json!({ "t": "Strikeout", "c": self.inlines(children) }),
+            "u" => json!({ "t": "Underline", "c": self.inlines(children) }),
+            "sub" => json!({ "t": "Subscript", "c": self.inlines(children) }),
+            "sup" => match self.note(children) {
+                Some(note) => note,
+                None => json!({ "t": "Superscript", "c": self.inlines(children) }),
+            },
+            "code" => json!({ "t": "Code", "c": [attr(props, None), text_content(children)] }),
+            "br" => json!({ "t": "LineBreak" }),
+            "a" => json!({ "t": "Link", "c": [attr(&without(props, &["href", "title"]), None), self.inlines(children), [string("href"), string("title")]] }),
+            "img" => {
+                let mut alt = Vec::new();
+                text(string("alt"), &mut alt);
+                json!({ "t": "Image", "c": [attr(&without(props, &["src", "alt", "title"]), None), alt, [string("src"), string("title")]] })
+            }
+            "input" if string("type") == "checkbox" => {
+                let checked = props.get("checked").and_then(Value::as_bool).unwrap_or(false);
+                json!({ "t": "Str", "c": if checked { "\u{2612}" } else { "\u{2610}" } })
+            }
+            "span" => json!({ "t": "Span", "c": [attr(props, None), self.inlines(children)] }),
+            _ => json!({ "t": "Span", "c": [attr(props, Some(tag)), self.inlines(children)] }),
+        });
+    }
+
+    /// The `Note` for a footnote reference's `sup` children.
+    fn note(&self, children: &[Node]) -> Option<Value> {
+        let [Node::Element { tag, props, .. }] = children else { return None };
+        if tag != "a" || class(props) != self.names.ref_class {
+            return None;
+        }
+        let label = props.get("href")?.as_str()?.strip_prefix('#')?.strip_prefix(self.names.id_prefix.as_str())?;
+        let content = self.notes.get(label)?;
+        if !self.expanding.borrow_mut().insert(label.to_string()) {
+            return Some(json!({ "t": "Superscript", "c": [{ "t": "Str", "c": text_content(children) }] }));
+        }
+        let blocks = self.blocks(content);
+        self.expanding.borrow_mut().remove(label);
+        Some(json!({ "t": "Note", "c": blocks }))
+    }
+}
+
+fn without(props: &IndexMap<String, Value>, keys: &[&str]) -> IndexMap<String, Value> {
+    props.iter().filter(|(key, _)| !keys.contains(&key.as_str())).map(|(k, v)| (k.clone(), v.clone())).collect()
+}
+
+/// `Str`s for words, `Space`s and `SoftBreak`s between them.
+fn text(content: &str, out: &mut Vec<Value>) {
+    let mut word = String::new();
+    let mut pending: Option<&str> = None;
+    for c in content.chars() {
+        if c.is_whitespace() {
+            if !word.is_empty() {
+                out.push(json!({ "t": "Str", "c": std::mem::take(&mut word) }));
+            }
+            if c == '\n' || pending.is_none() {
+                pending = Some(if c == '\n' { "SoftBreak" } else { "Space" });
+            }
+            continue;
+        }
+        if let Some(kind) = pending.take() {
+            out.push(json!({ "t": kind }));
+        }
+        word.push(c);
+    }
+    if !word.is_empty() {
+        out.push(json!({ "t": "Str", "c": word }));
+    }
+    if let Some(kind) = pending {
+        out.push(json!({ "t": kind }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_to_pandoc() {
+        let markdown = "## Hi *you*\n\n- a b[^n]\n\n```sh\nls\n```\n\n[^n]: Note.";
+        let document = to_pandoc(&parse(markdown, &TranspileOptions::default()));
+        assert_eq!(document, json!({ "pandoc-api-version": [1, 23, 1], "meta": {}, "blocks": [
+            { "t": "Header", "c": [2, ["", [], []], [
+                { "t": "Str", "c": "Hi" }, { "t": "Space" }, { "t": "Emph", "c": [{ "t": "Str", "c": "you" }] },
+            ]] },
+            { "t": "BulletList", "c": [[{ "t": "Plain", "c": [
+                { "t": "Str", "c": "a" }, { "t": "Space" }, { "t": "Str", "c": "b" },
+                { "t": "Note", "c": [{ "t": "Para", "c": [{ "t": "Str", "c": "Note." }] }] },
+            ] }]] },
+            { "t": "CodeBlock", "c": [["", ["sh"], []], "ls"] },
+        ] }));
+    }
+
+    #[test]
+    fn test_to_pandoc_cyclic_notes() {
+        let marker = |text: &str| json!({ "t": "Superscript", "c": [{ "t": "Str", "c": text }] });
+        let document = to_pandoc(&parse("x[^a]\n\n[^a]: see [^a]", &TranspileOptions::default()));
+        let note = &document["blocks"][0]["c"][1];
+        assert_eq!(note["t"], "Note");
+        assert_eq!(note["c"][0]["c"][2], marker("a"));
+
+        let markdown = "x[^a]\n\n[^a]: b[^b]\n\n[^b]: a[^a]";
+        let document = to_pandoc(&parse(markdown, &TranspileOptions::default()));
+        let inner = &document["blocks"][0]["c"][1]["c"][0]["c"][1];
+        assert_eq!(inner["t"], "Note");
+        assert_eq!(inner["c"][0]["c"][1], marker("a"));
+    }
+}
@@ -0,0 +1,130 @@
+//! Partial options for layering per-document overrides over a base
+//! `TranspileOptions`.
+
+use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Deserializer};
+use crate::{
+    CodeHandler, Construct, Extensions, FootnoteMode, FootnoteNames, KeyStrategy, PropStyle, Quotes, RawTextPolicy,
+    SoftBreak, TranspileOptions, UrlSchemes, Whitespace,
+};
+
+/// `TranspileOptions` with every field optional, e.g. from frontmatter or
+/// API parameters; see `TranspileOptions::merge`. For fields that are
+/// themselves optional, `null` clears the base value. Hooks cannot be
+/// overridden this way.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+#[non_exhaustive]
+pub struct PartialOptions {
+    pub allowed_tags: Option<Vec<String>>,
+    pub raw_text_elements: Option<RawTextPolicy>,
+    pub source_spans: Option<bool>,
+    pub sourcepos: Option<bool>,
+    pub normalize: Option<bool>,
+    pub content_ids: Option<bool>,
+    #[serde(deserialize_with = "nullable")]
+    pub keys: Option<Option<KeyStrategy>>,
+    pub extensions: Option<Extensions>,
+    pub disabled_constructs: Option<HashSet<Construct>>,
+    pub soft_break: Option<SoftBreak>,
+    pub whitespace: Option<Whitespace>,
+    pub quotes: Option<Quotes>,
+    pub typography: Option<HashMap<String, String>>,
+    pub prop_style: Option<PropStyle>,
+    pub footnote_names: Option<FootnoteNames>,
+    pub footnotes: Option<FootnoteMode>,
+    pub tag_map: Option<HashMap<String, String>>,
+    pub class_map: Option<HashMap<String, String>>,
+    pub code_handlers: Option<HashMap<String, CodeHandler>>,
+    #[serde(deserialize_with = "nullable")]
+    pub base_url: Option<Option<url::Url>>,
+    pub md_links_as_routes: Option<bool>,
+    pub url_schemes: Option<UrlSchemes>,
+    #[serde(deserialize_with = "nullable")]
+    pub id_prefix: Option<Option<String>>,
+    #[serde(deserialize_with = "nullable")]
+    pub max_depth: Option<Option<usize>>,
+    #[serde(deserialize_with = "nullable")]
+    pub max_input_bytes: Option<Option<usize>>,
+    #[serde(deserialize_with = "nullable")]
+    pub timeout_ms: Option<Option<u64>>,
+    pub heading_ids: Option<bool>,
+}
+
+/// A present field, `null` included, is `Some`; an absent one keeps the
+/// `None` default.
+fn nullable<'de, T: Deserialize<'de>, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Option<T>>, D::Error> {
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+impl PartialOptions {
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    #[cfg(feature = "toml")]
+    pub fn from_toml(toml: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(toml)
+    }
+}
+
+impl TranspileOptions {
+    /// These options with the fields set in `overrides` replaced. Maps
+    /// (`tag_map`, `class_map`, `code_handlers`, `typography`) are merged
+    /// key by key; everything else, nested option structs included, is
+    /// replaced whole. Hooks are kept from `self`.
+    pub fn merge(&self, overrides: &PartialOptions) -> TranspileOptions {
+        let mut merged = self.clone();
+        let o = overrides.clone();
+        macro_rules! replace {
+            ($($field:ident),*) => {
+                $(if let Some(value) = o.$field {
+                    merged.$field = value;
+                })*
+            };
+        }
+        macro_rules! extend {
+            ($($field:ident),*) => {
+                $(if let Some(entries) = o.$field {
+                    merged.$field.extend(entries);
+                })*
+            };
+        }
+        replace!(
+            allowed_tags, raw_text_elements, source_spans, sourcepos, normalize, content_ids, keys, extensions,
+            disabled_constructs, soft_break, whitespace, quotes, prop_style, footnote_names, footnotes, base_url,
+            md_links_as_routes, url_schemes, id_prefix, max_depth, max_input_bytes, timeout_ms, heading_ids
+        );
+        extend!(typography, tag_map, class_map, code_handlers);
+        merged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge() {
+        let base = TranspileOptions::builder()
+            .allowed_tags(["Card"])
+            .map_tag("h1", "Heading")
+            .max_depth(64)
+            .transform(|_: &mut Vec<crate::Node>| {})
+            .build();
+        let overrides = PartialOptions::from_json(r#"{
+            "soft_break": "Br",
+            "tag_map": { "a": "Link" },
+            "max_depth": null
+        }"#).unwrap();
+        let merged = base.merge(&overrides);
+        assert_eq!(merged.allowed_tags, ["Card"]);
+        assert_eq!(merged.soft_break, SoftBreak::Br);
+        assert_eq!(merged.tag_map.len(), 2);
+        assert_eq!(merged.max_depth, None);
+        assert_eq!(merged.transforms.len(), 1);
+        assert_eq!(base.merge(&PartialOptions::default()).max_depth, Some(64));
+
+        assert!(PartialOptions::from_json(r#"{ "allowed_tag": [] }"#).is_err());
+    }
+}
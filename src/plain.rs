@@ -0,0 +1,178 @@
+//! Plain-text extraction, for search indexing and meta descriptions.
+//!
+//! Blocks are separated by a blank line, list items, table rows and line
+//! breaks by a newline, and table cells by a tab. Markup, comments and raw
+//! content are dropped; images contribute their `alt` text.
+//! `to_formatted_text` lays text out for reading instead.
+
+use crate::ansi::Layout;
+use crate::markdown::BLOCK_TAGS;
+use crate::Node;
+
+#[derive(Debug, Clone)]
+pub struct PlainTextOptions {
+    /// Keep the text of code blocks and inline code.
+    pub code: bool,
+    /// Keep footnote reference markers and footnote definitions.
+    pub footnotes: bool,
+}
+
+impl Default for PlainTextOptions {
+    fn default() -> Self {
+        PlainTextOptions { code: true, footnotes: false }
+    }
+}
+
+/// Layout for `to_formatted_text`.
+#[derive(Debug, Clone)]
+pub struct TextLayout {
+    /// Column to wrap text at; `None` keeps each paragraph on one line.
+    pub width: Option<usize>,
+    /// Number links `[1]` and list their URLs at the end, instead of
+    /// writing each URL after its link.
+    pub link_references: bool,
+}
+
+impl Default for TextLayout {
+    fn default() -> Self {
+        TextLayout { width: Some(72), link_references: true }
+    }
+}
+
+/// Formats `nodes` as readable plain text, for email text parts and
+/// notifications: wrapped paragraphs, `•` bullets and numbered lists with
+/// indented continuation lines, `>` quotes, underlined `h1`/`h2`, indented
+/// code blocks, space-aligned tables and `[x]` task boxes. Comments and
+/// raw content are dropped; images show their `alt` text.
+pub fn to_formatted_text(nodes: &[Node], layout: &TextLayout) -> String {
+    let references = layout.link_references.then(Vec::new);
+    Layout { ansi: false, width: layout.width, references }.render(nodes)
+}
+
+/// Extracts the text of `nodes` with the default options.
+pub fn to_plain_text(nodes: &[Node]) -> String {
+    to_plain_text_with(nodes, &PlainTextOptions::default())
+}
+
+/// Extracts the text of `nodes`.
+pub fn to_plain_text_with(nodes: &[Node], options: &PlainTextOptions) -> String {
+    let mut writer = Writer { out: String::new(), pending: "", options };
+    writer.nodes(nodes);
+    writer.out.truncate(writer.out.trim_end().len());
+    writer.out
+}
+
+struct Writer<'o> {
+    out: String,
+    /// Separator to write before the next text; the longest one requested
+    /// since the last text wins.
+    pending: &'static str,
+    options: &'o PlainTextOptions,
+}
+
+impl Writer<'_> {
+    fn nodes(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            self.node(node);
+        }
+    }
+
+    fn node(&mut self, node: &Node) {
+        match node {
+            Node::Element { tag, props, children, .. } => {
+                let class = props.get("className").and_then(|v| v.as_str());
+                if !self.options.footnotes && matches!(class, Some("footnote-ref" | "footnote-definition")) {
+                    return;
+                }
+                if !self.options.code && (tag == "code" || tag == "pre") {
+                    return;
+                }
+                let separator = match tag.as_str() {
+                    "br" | "li" | "tr" | "thead" | "tbody" => "\n",
+                    "td" | "th" => "\t",
+                    tag if BLOCK_TAGS.contains(&tag) => "\n\n",
+                    _ => "",
+                };
+                self.separate(separator);
+                match props.get("alt").and_then(|v| v.as_str()) {
+                    Some(alt) if tag == "img" => self.text(alt),
+                    _ => self.nodes(children),
+                }
+                // Cells are only separated from each other.
+                if separator != "\t" {
+                    self.separate(separator);
+                }
+            }
+            Node::Text { content, .. } => self.text(content),
+            Node::Fragment { children } => self.nodes(children),
+            Node::Comment { .. } | Node::Raw { .. } => {}
+        }
+    }
+
+    fn separate(&mut self, separator: &'static str) {
+        if separator.len() > self.pending.len() {
+            self.pending = separator;
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        if !self.out.is_empty() && !self.pending.is_empty() {
+            self.out.truncate(self.out.trim_end_matches([' ', '\n']).len());
+            self.out.push_str(self.pending);
+        }
+        self.pending = "";
+        if self.out.is_empty() {
+            self.out.push_str(text.trim_start());
+        } else {
+            self.out.push_str(text);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_to_plain_text() {
+        let markdown = "# Title\n\nSome *text* with `code`.[^1]\n\n- one\n- [x] two\n\n| a | b |\n| - | - |\n| 1 | 2 |\n\n![alt](/i.png)<!-- c -->\n\n```\nlet x;\n```\n\n[^1]: Note.";
+        let ast = parse(markdown, &TranspileOptions::default());
+
+        assert_eq!(
+            to_plain_text(&ast),
+            "Title\n\nSome text with code.\n\none\ntwo\n\na\tb\n1\t2\n\nalt\n\nlet x;"
+        );
+
+        let options = PlainTextOptions { code: false, footnotes: true };
+        let text = to_plain_text_with(&ast, &options);
+        assert!(text.starts_with("Title\n\nSome text with .1\n\n"), "{:?}", text);
+        assert!(text.ends_with("alt\n\nNote."), "{:?}", text);
+    }
+
+    #[test]
+    fn test_to_formatted_text() {
+        let markdown = "Title\n=====\n\nSome [long](https://a.io) text that wraps, see [also](https://b.io) and [again](https://a.io).\n\n- one\n- [x] two\n\n> quoted";
+        let ast = parse(markdown, &TranspileOptions::default());
+        assert_eq!(to_formatted_text(&ast, &TextLayout { width: Some(30), link_references: true }), [
+            "Title",
+            "=====",
+            "",
+            "Some long[1] text that wraps,",
+            "see also[2] and again[1].",
+            "",
+            "\u{2022} one",
+            "\u{2022} [x] two",
+            "",
+            "> quoted",
+            "",
+            "[1] https://a.io",
+            "[2] https://b.io",
+        ].join("\n"));
+        assert!(to_formatted_text(&ast, &TextLayout { width: None, link_references: false })
+            .contains("Some long (https://a.io) text that wraps, see also (https://b.io)"));
+    }
+}
@@ -0,0 +1,172 @@
+//! Named `TranspileOptions` presets for common integrations.
+
+use std::sync::Arc;
+use lazy_static::lazy_static;
+use regex::Regex;
+use crate::visitor::{walk, Flow, VisitorMut};
+use crate::outline::heading_level;
+use crate::{normalize, Extensions, Node, RawTextPolicy, SoftBreak, TranspileOptions};
+
+lazy_static! {
+    /// A bare `http(s)` URL; trailing punctuation is trimmed afterwards.
+    static ref URL_RE: Regex = Regex::new(r"https?://[^\s<>]+").unwrap();
+}
+
+impl TranspileOptions {
+    /// GitHub's dialect: tables, footnotes, strikethrough and task lists,
+    /// straight quotes, and headings with GitHub-style `id`s.
+    pub fn gfm() -> Self {
+        TranspileOptions {
+            extensions: Extensions { smart_punctuation: false, ..Extensions::default() },
+            heading_ids: true,
+            ..Default::default()
+        }
+    }
+
+    /// Plain CommonMark, with every extension off.
+    pub fn commonmark() -> Self {
+        TranspileOptions { extensions: Extensions::none(), ..Default::default() }
+    }
+
+    /// Chat messages: every line break is a `br`, headings render as
+    /// paragraphs, images as their alt text, bare `http(s)` URLs become
+    /// links and script/style elements are removed.
+    pub fn chat() -> Self {
+        TranspileOptions {
+            extensions: Extensions { strikethrough: true, ..Extensions::none() },
+            soft_break: SoftBreak::Br,
+            raw_text_elements: RawTextPolicy::Remove,
+            normalize: true,
+            transforms: vec![Arc::new(chat_message)],
+            ..Default::default()
+        }
+    }
+
+    /// Documentation sites: `# Heading {#id}` attributes, slugged ids on
+    /// the other headings (for `outline` tables of contents and deep
+    /// links) and normalized text.
+    pub fn docs() -> Self {
+        TranspileOptions {
+            extensions: Extensions { heading_attributes: true, ..Extensions::default() },
+            heading_ids: true,
+            normalize: true,
+            ..Default::default()
+        }
+    }
+}
+
+fn chat_message(nodes: &mut Vec<Node>) {
+    autolink(nodes);
+    walk(nodes, &mut ChatMessage);
+    normalize(nodes);
+}
+
+struct ChatMessage;
+
+impl VisitorMut for ChatMessage {
+    fn enter(&mut self, node: &mut Node) -> Flow {
+        if heading_level(node).is_some() {
+            if let Node::Element { tag, .. } = node {
+                *tag = "p".to_string();
+            }
+        }
+        match node {
+            Node::Element { tag, props, span, .. } if tag == "img" => {
+                let alt = props.get("alt").and_then(|v| v.as_str()).unwrap_or_default();
+                *node = Node::Text { content: alt.to_string(), span: *span };
+                Flow::SkipChildren
+            }
+            Node::Element { tag, .. } if matches!(tag.as_str(), "a" | "code" | "pre") => Flow::Continue,
+            Node::Element { children, .. } => {
+                autolink(children);
+                Flow::Continue
+            }
+            _ => Flow::Continue,
+        }
+    }
+}
+
+/// Links bare URLs in `nodes`' own text, leaving text inside links and
+/// code alone.
+fn autolink(nodes: &mut Vec<Node>) {
+    if !nodes.iter().any(|node| matches!(node, Node::Text { content, .. } if URL_RE.is_match(content))) {
+        return;
+    }
+    let mut linked = Vec::with_capacity(nodes.len());
+    for node in nodes.drain(..) {
+        let Node::Text { content, span } = node else {
+            linked.push(node);
+            continue;
+        };
+        let mut rest = content.as_str();
+        while let Some(found) = URL_RE.find(rest) {
+            let url = trim_url(found.as_str());
+            if found.start() > 0 {
+                linked.push(Node::Text { content: rest[..found.start()].to_string(), span });
+            }
+            let mut link = Node::element("a").prop("href", url).child(Node::Text { content: url.to_string(), span });
+            if let Some(span) = span {
+                link = link.with_span(span);
+            }
+            linked.push(link);
+            rest = &rest[found.start() + url.len()..];
+        }
+        if !rest.is_empty() {
+            linked.push(Node::Text { content: rest.to_string(), span });
+        }
+    }
+    *nodes = linked;
+}
+
+/// Drops trailing punctuation from a matched URL, keeping a closing
+/// parenthesis that balances one inside it (`/wiki/Rust_(language)`).
+fn trim_url(mut url: &str) -> &str {
+    loop {
+        url = url.trim_end_matches(['.', ',', ';', ':', '!', '?', '\'', '"']);
+        if !url.ends_with(')') || url.matches(')').count() <= url.matches('(').count() {
+            return url;
+        }
+        url = &url[..url.len() - 1];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_presets() {
+        let markdown = "# Title {#top}\n\n\"Hi\" | a |\n\n| a |\n| - |";
+        let gfm = parse(markdown, &TranspileOptions::gfm());
+        assert_eq!(gfm[0], Node::element("h1").prop("id", "title-top").child(Node::text("Title {#top}")));
+        assert!(crate::find(&gfm, |node| node.tag() == Some("table")).is_some());
+
+        let docs = parse(markdown, &TranspileOptions::docs());
+        assert_eq!(docs[0], Node::element("h1").prop("id", "top").child(Node::text("Title")));
+        assert!(crate::text_content(&docs).contains("\u{201c}Hi\u{201d}"));
+
+        let commonmark = parse(markdown, &TranspileOptions::commonmark());
+        assert!(crate::find(&commonmark, |node| node.tag() == Some("table")).is_none());
+    }
+
+    #[test]
+    fn test_chat_preset() {
+        let markdown = "# Hey\nsee https://example.com/a_(b), ok?\n![cat](/cat.png) [x](https://x.org) `http://code`";
+        let ast = parse(markdown, &TranspileOptions::chat());
+        let link = Node::element("a").prop("href", "https://example.com/a_(b)").child(Node::text("https://example.com/a_(b)"));
+        assert_eq!(ast, [
+            Node::element("p").child(Node::text("Hey")),
+            Node::element("p").with_children([
+                Node::text("see "),
+                link,
+                Node::text(", ok?"),
+                Node::element("br"),
+                Node::text("cat "),
+                Node::element("a").prop("href", "https://x.org").child(Node::text("x")),
+                Node::text(" "),
+                Node::element("code").child(Node::text("http://code")),
+            ]),
+        ]);
+    }
+}
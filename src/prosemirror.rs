@@ -0,0 +1,266 @@
+//! ProseMirror document JSON, for loading markdown into Tiptap and other
+//! ProseMirror editors.
+
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use crate::markdown::BLOCK_TAGS;
+use crate::{text_content, Node};
+
+/// Node and mark type names for each tag. Elements whose tag is in
+/// neither map are unwrapped, keeping their content. The default follows
+/// Tiptap's StarterKit and table extension, whose attribute names
+/// (`level`, `start`, `language`, `src`/`alt`/`title`, `href`/`title`) are
+/// used whatever the type names.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProsemirrorSchema {
+    /// Node types by tag (`p` to `paragraph`, `h1`..`h6` to `heading`).
+    pub nodes: HashMap<String, String>,
+    /// Mark types by tag (`strong` to `bold`, `a` to `link`).
+    pub marks: HashMap<String, String>,
+    /// The node type inline content is wrapped in where blocks are expected,
+    /// such as in tight list items.
+    pub paragraph: String,
+}
+
+impl Default for ProsemirrorSchema {
+    fn default() -> Self {
+        let pairs = |pairs: &[(&str, &str)]| pairs.iter().map(|&(tag, name)| (tag.to_string(), name.to_string())).collect();
+        ProsemirrorSchema {
+            nodes: pairs(&[
+                ("p", "paragraph"),
+                ("h1", "heading"),
+                ("h2", "heading"),
+                ("h3", "heading"),
+                ("h4", "heading"),
+                ("h5", "heading"),
+                ("h6", "heading"),
+                ("blockquote", "blockquote"),
+                ("ul", "bulletList"),
+                ("ol", "orderedList"),
+                ("li", "listItem"),
+                ("pre", "codeBlock"),
+                ("hr", "horizontalRule"),
+                ("br", "hardBreak"),
+                ("img", "image"),
+                ("table", "table"),
+                ("tr", "tableRow"),
+                ("th", "tableHeader"),
+                ("td", "tableCell"),
+            ]),
+            marks: pairs(&[
+                ("strong", "bold"),
+                ("b", "bold"),
+                ("em", "italic"),
+                ("i", "italic"),
+                ("del", "strike"),
+                ("s", "strike"),
+                ("code", "code"),
+                ("a", "link"),
+                ("u", "underline"),
+            ]),
+            paragraph: "paragraph".to_string(),
+        }
+    }
+}
+
+/// Converts `nodes` to a ProseMirror `doc` with the default (Tiptap)
+/// schema.
+pub fn to_prosemirror(nodes: &[Node]) -> Value {
+    to_prosemirror_with(nodes, &ProsemirrorSchema::default())
+}
+
+/// Converts `nodes` to a ProseMirror `doc`: block elements become nodes,
+/// inline formatting becomes marks on `text` nodes, and comments and raw
+/// content are dropped.
+pub fn to_prosemirror_with(nodes: &[Node], schema: &ProsemirrorSchema) -> Value {
+    json!({ "type": "doc", "content": Writer { schema }.blocks(nodes) })
+}
+
+fn is_block(node: &Node) -> bool {
+    match node {
+        Node::Element { tag, children, .. } => BLOCK_TAGS.contains(&tag.as_str()) || children.iter().any(is_block),
+        Node::Fragment { children } => children.iter().any(is_block),
+        _ => false,
+    }
+}
+
+fn node(kind: &str, attrs: Map<String, Value>, content: Vec<Value>) -> Value {
+    let mut node = Map::new();
+    node.insert("type".into(), kind.into());
+    if !attrs.is_empty() {
+        node.insert("attrs".into(), attrs.into());
+    }
+    if !content.is_empty() {
+        node.insert("content".into(), content.into());
+    }
+    Value::Object(node)
+}
+
+struct Writer<'s> {
+    schema: &'s ProsemirrorSchema,
+}
+
+impl Writer<'_> {
+    /// Block nodes for `nodes`, with runs of inline content wrapped in
+    /// paragraphs.
+    fn blocks(&self, nodes: &[Node]) -> Vec<Value> {
+        let mut blocks = Vec::new();
+        let mut run = Vec::new();
+        for child in nodes {
+            if is_block(child) {
+                self.flush(&mut run, &mut blocks);
+                self.block(child, &mut blocks);
+            } else {
+                self.inline(child, &[], &mut run);
+            }
+        }
+        self.flush(&mut run, &mut blocks);
+        blocks
+    }
+
+    fn flush(&self, run: &mut Vec<Value>, blocks: &mut Vec<Value>) {
+        let blank = |inline: &Value| inline["type"] == "text" && inline["text"].as_str().is_some_and(|text| text.trim().is_empty());
+        if run.iter().all(blank) {
+            run.clear();
+            return;
+        }
+        blocks.push(node(&self.schema.paragraph, Map::new(), std::mem::take(run)));
+    }
+
+    fn block(&self, child: &Node, out: &mut Vec<Value>) {
+        let Node::Element { tag, props, children, .. } = child else {
+            if let Node::Fragment { children } = child {
+                out.extend(self.blocks(children));
+            }
+            return;
+        };
+        let Some(kind) = self.schema.nodes.get(tag) else {
+            return out.extend(self.blocks(children));
+        };
+        let mut attrs = Map::new();
+        let content = match tag.as_str() {
+            "p" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                if tag != "p" {
+                    attrs.insert("level".into(), tag[1..].parse::<u8>().unwrap_or(1).into());
+                }
+                self.inlines(children, &[])
+            }
+            "ol" => {
+                attrs.insert("start".into(), props.get("start").cloned().unwrap_or_else(|| 1.into()));
+                self.blocks(children)
+            }
+            "pre" => {
+                let class = children.first().and_then(|code| match code {
+                    Node::Element { props, .. } => props.get("className").or_else(|| props.get("class"))?.as_str(),
+                    _ => None,
+                });
+                let language = class.and_then(|class| class.split_whitespace().find_map(|c| c.strip_prefix("language-")));
+                attrs.insert("language".into(), language.into());
+                let code = text_content(children);
+                let code = code.strip_suffix('\n').unwrap_or(&code);
+                match code.is_empty() {
+                    true => Vec::new(),
+                    false => vec![json!({ "type": "text", "text": code })],
+                }
+            }
+            "table" => {
+                let mut rows = Vec::new();
+                self.rows(children, &mut rows);
+                rows
+            }
+            _ => self.blocks(children),
+        };
+        out.push(node(kind, attrs, content));
+    }
+
+    /// `tr` nodes, wherever they sit under a `table`.
+    fn rows(&self, nodes: &[Node], rows: &mut Vec<Value>) {
+        for child in nodes {
+            match child {
+                Node::Element { tag, .. } if tag == "tr" => self.block(child, rows),
+                _ => {
+                    if let Some(children) = child.children() {
+                        self.rows(children, rows);
+                    }
+                }
+            }
+        }
+    }
+
+    fn inlines(&self, nodes: &[Node], marks: &[Value]) -> Vec<Value> {
+        let mut inlines = Vec::new();
+        for child in nodes {
+            self.inline(child, marks, &mut inlines);
+        }
+        inlines
+    }
+
+    fn inline(&self, child: &Node, marks: &[Value], out: &mut Vec<Value>) {
+        match child {
+            Node::Text { content, .. } if !content.is_empty() => {
+                let mut text = json!({ "type": "text", "text": content });
+                if !marks.is_empty() {
+                    text["marks"] = marks.into();
+                }
+                out.push(text);
+            }
+            Node::Element { tag, props, children, .. } => {
+                if let Some(mark) = self.schema.marks.get(tag) {
+                    let mut attrs = Map::new();
+                    if tag == "a" {
+                        attrs.insert("href".into(), props.get("href").cloned().unwrap_or_default());
+                        attrs.insert("title".into(), props.get("title").cloned().unwrap_or_default());
+                    }
+                    let mut marks = marks.to_vec();
+                    marks.push(node(mark, attrs, Vec::new()));
+                    out.extend(self.inlines(children, &marks));
+                } else if let Some(kind) = self.schema.nodes.get(tag).filter(|_| tag == "img" || tag == "br") {
+                    let mut attrs = Map::new();
+                    if tag == "img" {
+                        for key in ["src", "alt", "title"] {
+                            attrs.insert(key.into(), props.get(key).cloned().unwrap_or_default());
+                        }
+                    }
+                    out.push(node(kind, attrs, Vec::new()));
+                    out.extend(self.inlines(children, marks));
+                } else {
+                    out.extend(self.inlines(children, marks));
+                }
+            }
+            Node::Fragment { children } => out.extend(self.inlines(children, marks)),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_to_prosemirror() {
+        let markdown = "## Hi\n\nA **[b](/x)** c\n\n- item\n\n```js\nx\n```";
+        assert_eq!(to_prosemirror(&parse(markdown, &TranspileOptions::default())), json!({ "type": "doc", "content": [
+            { "type": "heading", "attrs": { "level": 2 }, "content": [{ "type": "text", "text": "Hi" }] },
+            { "type": "paragraph", "content": [
+                { "type": "text", "text": "A " },
+                { "type": "text", "text": "b", "marks": [{ "type": "bold" }, { "type": "link", "attrs": { "href": "/x", "title": null } }] },
+                { "type": "text", "text": " c" },
+            ] },
+            { "type": "bulletList", "content": [{ "type": "listItem", "content": [
+                { "type": "paragraph", "content": [{ "type": "text", "text": "item" }] },
+            ] }] },
+            { "type": "codeBlock", "attrs": { "language": "js" }, "content": [{ "type": "text", "text": "x" }] },
+        ] }));
+
+        let mut schema = ProsemirrorSchema::default();
+        schema.nodes.insert("h2".into(), "title".into());
+        schema.marks.insert("strong".into(), "strong".into());
+        let doc = to_prosemirror_with(&parse("## Hi\n\n**b**", &TranspileOptions::default()), &schema);
+        assert_eq!(doc["content"][0]["type"], "title");
+        assert_eq!(doc["content"][1]["content"][0]["marks"][0]["type"], "strong");
+    }
+}
@@ -0,0 +1,112 @@
+//! Python bindings: `md2jsx.parse(markdown, allowed_tags=None, **options)`
+//! returning the AST as dicts and lists. Built with maturin (see
+//! `pyproject.toml`).
+
+// `#[pyfunction]` expands to an `Into<PyErr>` conversion on `PyResult`.
+#![allow(clippy::useless_conversion)]
+
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyFloat, PyList, PyString, PyTuple};
+use serde_json::{Map, Number, Value};
+use crate::TranspileOptions;
+
+/// Parses `markdown` into a list of node dicts. Keyword arguments are
+/// `TranspileOptions` fields by their Rust names (`source_spans=True`,
+/// `soft_break="Br"`); unknown ones raise `ValueError`.
+#[pyfunction]
+#[pyo3(signature = (markdown, allowed_tags = None, **options))]
+fn parse(
+    py: Python<'_>,
+    markdown: &str,
+    allowed_tags: Option<Vec<String>>,
+    options: Option<&Bound<'_, PyDict>>,
+) -> PyResult<PyObject> {
+    let mut fields = match options {
+        Some(options) => match to_value(options.as_any())? {
+            Value::Object(fields) => fields,
+            _ => unreachable!("a dict converts to an object"),
+        },
+        None => Map::new(),
+    };
+    if let Some(allowed_tags) = allowed_tags {
+        fields.insert("allowed_tags".to_string(), allowed_tags.into());
+    }
+    let options: TranspileOptions =
+        serde_json::from_value(Value::Object(fields)).map_err(|e| PyValueError::new_err(e.to_string()))?;
+    let ast = py.allow_threads(|| serde_json::to_value(crate::parse(markdown, &options)));
+    let ast = ast.map_err(|e| PyValueError::new_err(e.to_string()))?;
+    Ok(to_py(py, &ast))
+}
+
+fn to_value(object: &Bound<'_, PyAny>) -> PyResult<Value> {
+    if object.is_none() {
+        Ok(Value::Null)
+    } else if let Ok(value) = object.downcast::<PyBool>() {
+        Ok(Value::Bool(value.is_true()))
+    } else if let Ok(value) = object.extract::<i64>() {
+        Ok(value.into())
+    } else if let Ok(value) = object.downcast::<PyFloat>() {
+        Ok(Number::from_f64(value.value()).map_or(Value::Null, Value::Number))
+    } else if let Ok(value) = object.downcast::<PyString>() {
+        Ok(Value::String(value.to_str()?.to_string()))
+    } else if let Ok(dict) = object.downcast::<PyDict>() {
+        let mut map = Map::new();
+        for (key, value) in dict {
+            map.insert(key.extract::<String>()?, to_value(&value)?);
+        }
+        Ok(Value::Object(map))
+    } else if object.is_instance_of::<PyList>() || object.is_instance_of::<PyTuple>() {
+        object.iter()?.map(|item| to_value(&item?)).collect::<PyResult<_>>().map(Value::Array)
+    } else {
+        Err(PyTypeError::new_err(format!("unsupported option value: {}", object.get_type().name()?)))
+    }
+}
+
+fn to_py(py: Python<'_>, value: &Value) -> PyObject {
+    match value {
+        Value::Null => py.None(),
+        Value::Bool(value) => value.into_py(py),
+        Value::Number(number) => match number.as_i64() {
+            Some(value) => value.into_py(py),
+            None => number.as_f64().unwrap_or(f64::NAN).into_py(py),
+        },
+        Value::String(value) => value.into_py(py),
+        Value::Array(items) => PyList::new_bound(py, items.iter().map(|item| to_py(py, item))).into_py(py),
+        Value::Object(map) => {
+            let dict = PyDict::new_bound(py);
+            for (key, value) in map {
+                dict.set_item(key, to_py(py, value)).expect("str keys are hashable");
+            }
+            dict.into_py(py)
+        }
+    }
+}
+
+#[pymodule]
+fn md2jsx(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(parse, module)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let module = PyModule::new_bound(py, "md2jsx").unwrap();
+            md2jsx(&module).unwrap();
+            let locals = PyDict::new_bound(py);
+            locals.set_item("md2jsx", module).unwrap();
+
+            let ast = py.eval_bound("md2jsx.parse('# Hi <b>x</b>', ['b'], source_spans=True)", None, Some(&locals)).unwrap();
+            assert_eq!(ast.get_item(0).unwrap().get_item("tag").unwrap().extract::<String>().unwrap(), "h1");
+            assert_eq!(ast.get_item(0).unwrap().get_item("span").unwrap().get_item("line").unwrap().extract::<i64>().unwrap(), 1);
+
+            let error = py.eval_bound("md2jsx.parse('x', nope=1)", None, Some(&locals)).unwrap_err();
+            assert!(error.is_instance_of::<PyValueError>(py));
+        });
+    }
+}
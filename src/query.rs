@@ -0,0 +1,457 @@
+//! CSS-selector-style queries over the AST.
+//!
+//! Supported syntax: type (`th`, `*`), class (`.note`, matched against
+//! `className`/`class`), id (`#intro`), attribute (`[href]`, `[href=x]`,
+//! `[href^=x]`, `[href$=x]`, `[href*=x]`, `[class~=x]`), descendant and
+//! child (`>`) combinators, and selector lists (`h1, h2`).
+
+use std::str::FromStr;
+use thiserror::Error;
+use crate::Node;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SelectorError {
+    #[error("empty selector")]
+    Empty,
+    #[error("unexpected character {0:?} at offset {1}")]
+    Unexpected(char, usize),
+    #[error("unterminated attribute selector")]
+    UnterminatedAttribute,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Combinator {
+    Descendant,
+    Child,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AttrOp {
+    Exists,
+    Equals,
+    Prefix,
+    Suffix,
+    Contains,
+    Word,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AttrSelector {
+    name: String,
+    op: AttrOp,
+    value: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Compound {
+    tag: Option<String>,
+    id: Option<String>,
+    classes: Vec<String>,
+    attrs: Vec<AttrSelector>,
+}
+
+/// One selector of a list: compounds joined by combinators, where
+/// `parts[i].0` relates `parts[i]` to `parts[i - 1]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Complex {
+    parts: Vec<(Combinator, Compound)>,
+}
+
+/// A parsed selector list, reusable across queries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selector {
+    alternatives: Vec<Complex>,
+}
+
+impl Selector {
+    pub fn parse(selector: &str) -> Result<Selector, SelectorError> {
+        let mut alternatives = Vec::new();
+        for part in split_list(selector) {
+            alternatives.push(parse_complex(part, selector)?);
+        }
+        Ok(Selector { alternatives })
+    }
+
+    /// Tests `node` given its ancestors, outermost first.
+    pub fn matches(&self, node: &Node, ancestors: &[&Node]) -> bool {
+        self.alternatives.iter().any(|complex| {
+            let last = complex.parts.len() - 1;
+            matches_at(complex, last, node, ancestors)
+        })
+    }
+
+    /// First match in document order.
+    pub fn find<'a>(&self, nodes: &'a [Node]) -> Option<&'a Node> {
+        self.find_all(nodes).into_iter().next()
+    }
+
+    /// All matches in document order.
+    pub fn find_all<'a>(&self, nodes: &'a [Node]) -> Vec<&'a Node> {
+        let mut found = Vec::new();
+        let mut ancestors = Vec::new();
+        self.collect(nodes, &mut ancestors, &mut |node, _| found.push(node), &mut Vec::new());
+        found
+    }
+
+    /// Index paths (child positions from the root) of all matches.
+    fn paths(&self, nodes: &[Node]) -> Vec<Vec<usize>> {
+        let mut paths = Vec::new();
+        let mut ancestors = Vec::new();
+        self.collect(nodes, &mut ancestors, &mut |_, path| paths.push(path.to_vec()), &mut Vec::new());
+        paths
+    }
+
+    fn collect<'a>(
+        &self,
+        nodes: &'a [Node],
+        ancestors: &mut Vec<&'a Node>,
+        found: &mut impl FnMut(&'a Node, &[usize]),
+        path: &mut Vec<usize>,
+    ) {
+        for (index, node) in nodes.iter().enumerate() {
+            path.push(index);
+            if self.matches(node, ancestors) {
+                found(node, path);
+            }
+            match node {
+                Node::Element { children, .. } => {
+                    ancestors.push(node);
+                    self.collect(children, ancestors, found, path);
+                    ancestors.pop();
+                }
+                // Fragments are invisible to selectors.
+                Node::Fragment { children } => self.collect(children, ancestors, found, path),
+                Node::Text { .. } | Node::Comment { .. } | Node::Raw { .. } => {}
+            }
+            path.pop();
+        }
+    }
+}
+
+impl FromStr for Selector {
+    type Err = SelectorError;
+
+    fn from_str(selector: &str) -> Result<Selector, SelectorError> {
+        Selector::parse(selector)
+    }
+}
+
+/// First node matching `selector`, in document order.
+pub fn query<'a>(nodes: &'a [Node], selector: &str) -> Result<Option<&'a Node>, SelectorError> {
+    Ok(Selector::parse(selector)?.find(nodes))
+}
+
+/// All nodes matching `selector`, in document order.
+pub fn query_all<'a>(nodes: &'a [Node], selector: &str) -> Result<Vec<&'a Node>, SelectorError> {
+    Ok(Selector::parse(selector)?.find_all(nodes))
+}
+
+/// Mutable reference to the first node matching `selector`.
+pub fn query_mut<'a>(nodes: &'a mut [Node], selector: &str) -> Result<Option<&'a mut Node>, SelectorError> {
+    let paths = Selector::parse(selector)?.paths(nodes);
+    Ok(paths.first().and_then(|path| node_at_mut(nodes, path)))
+}
+
+/// Calls `f` on every node matching `selector`, in document order. Matches
+/// are located before `f` runs, so nodes that `f` moves or removes are
+/// skipped.
+pub fn query_all_mut(
+    nodes: &mut [Node],
+    selector: &str,
+    mut f: impl FnMut(&mut Node),
+) -> Result<(), SelectorError> {
+    for path in Selector::parse(selector)?.paths(nodes) {
+        if let Some(node) = node_at_mut(nodes, &path) {
+            f(node);
+        }
+    }
+    Ok(())
+}
+
+fn node_at_mut<'a>(nodes: &'a mut [Node], path: &[usize]) -> Option<&'a mut Node> {
+    let (first, rest) = path.split_first()?;
+    let node = nodes.get_mut(*first)?;
+    if rest.is_empty() {
+        return Some(node);
+    }
+    node_at_mut(node.children_mut()?, rest)
+}
+
+fn matches_at(complex: &Complex, index: usize, node: &Node, ancestors: &[&Node]) -> bool {
+    let (combinator, compound) = &complex.parts[index];
+    if !matches_compound(compound, node) {
+        return false;
+    }
+    if index == 0 {
+        return true;
+    }
+    match combinator {
+        Combinator::Child => match ancestors.split_last() {
+            Some((parent, rest)) => matches_at(complex, index - 1, parent, rest),
+            None => false,
+        },
+        Combinator::Descendant => (0..ancestors.len())
+            .rev()
+            .any(|i| matches_at(complex, index - 1, ancestors[i], &ancestors[..i])),
+    }
+}
+
+fn matches_compound(compound: &Compound, node: &Node) -> bool {
+    let Node::Element { tag, props, .. } = node else {
+        return false;
+    };
+    if compound.tag.as_ref().is_some_and(|t| t != tag) {
+        return false;
+    }
+    let prop = |name: &str| props.get(name).map(prop_string);
+    if let Some(id) = &compound.id {
+        if prop("id").as_deref() != Some(id.as_str()) {
+            return false;
+        }
+    }
+    if !compound.classes.is_empty() {
+        let classes = prop("className").or_else(|| prop("class")).unwrap_or_default();
+        if !compound.classes.iter().all(|c| classes.split_whitespace().any(|have| have == c)) {
+            return false;
+        }
+    }
+    compound.attrs.iter().all(|attr| {
+        let Some(value) = prop(&attr.name) else {
+            return false;
+        };
+        match attr.op {
+            AttrOp::Exists => true,
+            AttrOp::Equals => value == attr.value,
+            AttrOp::Prefix => value.starts_with(&attr.value),
+            AttrOp::Suffix => value.ends_with(&attr.value),
+            AttrOp::Contains => value.contains(&attr.value),
+            AttrOp::Word => value.split_whitespace().any(|w| w == attr.value),
+        }
+    })
+}
+
+fn prop_string(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Bool(true) => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_'
+}
+
+/// Splits a selector list at the commas outside attribute selectors and
+/// quoted values.
+fn split_list(selector: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut quote = None;
+    let mut in_attr = false;
+    for (i, c) in selector.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '[') => in_attr = true,
+            (None, ']') => in_attr = false,
+            (None, ',') if !in_attr => {
+                parts.push(&selector[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&selector[start..]);
+    parts
+}
+
+fn parse_complex(part: &str, whole: &str) -> Result<Complex, SelectorError> {
+    // Offset of `part` in the full selector, for error positions.
+    let base = part.as_ptr() as usize - whole.as_ptr() as usize;
+    let chars: Vec<(usize, char)> = part.char_indices().collect();
+    let mut pos = 0;
+    let mut parts = Vec::new();
+    let mut combinator = Combinator::Descendant;
+
+    let ident = |pos: &mut usize| {
+        let start = *pos;
+        while *pos < chars.len() && is_ident_char(chars[*pos].1) {
+            *pos += 1;
+        }
+        chars[start..*pos].iter().map(|(_, c)| c).collect::<String>()
+    };
+
+    loop {
+        let mut saw_space = false;
+        while pos < chars.len() && chars[pos].1.is_whitespace() {
+            pos += 1;
+            saw_space = true;
+        }
+        if pos >= chars.len() {
+            break;
+        }
+        if chars[pos].1 == '>' {
+            if parts.is_empty() {
+                return Err(SelectorError::Unexpected('>', base + chars[pos].0));
+            }
+            combinator = Combinator::Child;
+            pos += 1;
+            continue;
+        }
+        if !parts.is_empty() && !saw_space && combinator == Combinator::Descendant {
+            return Err(SelectorError::Unexpected(chars[pos].1, base + chars[pos].0));
+        }
+
+        let mut compound = Compound::default();
+        let mut empty = true;
+        while pos < chars.len() {
+            let (offset, c) = chars[pos];
+            match c {
+                '*' if empty => pos += 1,
+                '.' | '#' => {
+                    pos += 1;
+                    let name = ident(&mut pos);
+                    if name.is_empty() {
+                        return Err(SelectorError::Unexpected(c, base + offset));
+                    }
+                    if c == '.' {
+                        compound.classes.push(name);
+                    } else {
+                        compound.id = Some(name);
+                    }
+                }
+                '[' => {
+                    let mut quote = None;
+                    let close = chars[pos..].iter().position(|&(_, c)| match quote {
+                        Some(q) => {
+                            if c == q {
+                                quote = None;
+                            }
+                            false
+                        }
+                        None => {
+                            if c == '"' || c == '\'' {
+                                quote = Some(c);
+                            }
+                            c == ']'
+                        }
+                    }).ok_or(SelectorError::UnterminatedAttribute)?;
+                    let inner: String = chars[pos + 1..pos + close].iter().map(|(_, c)| c).collect();
+                    compound.attrs.push(parse_attr(&inner));
+                    pos += close + 1;
+                }
+                c if is_ident_char(c) && empty => compound.tag = Some(ident(&mut pos)),
+                c if c.is_whitespace() || c == '>' => break,
+                c => return Err(SelectorError::Unexpected(c, base + offset)),
+            }
+            empty = false;
+        }
+        parts.push((combinator, compound));
+        combinator = Combinator::Descendant;
+    }
+
+    if parts.is_empty() || combinator == Combinator::Child {
+        return Err(SelectorError::Empty);
+    }
+    Ok(Complex { parts })
+}
+
+fn parse_attr(inner: &str) -> AttrSelector {
+    let ops = [
+        ("^=", AttrOp::Prefix),
+        ("$=", AttrOp::Suffix),
+        ("*=", AttrOp::Contains),
+        ("~=", AttrOp::Word),
+        ("=", AttrOp::Equals),
+    ];
+    for (token, op) in ops {
+        if let Some((name, value)) = inner.split_once(token) {
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"').and_then(|v| v.strip_suffix('"'))
+                .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+                .unwrap_or(value);
+            return AttrSelector {
+                name: name.trim().to_string(),
+                op,
+                value: value.to_string(),
+            };
+        }
+    }
+    AttrSelector {
+        name: inner.trim().to_string(),
+        op: AttrOp::Exists,
+        value: String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    fn tag(node: &Node) -> &str {
+        match node {
+            Node::Element { tag, .. } => tag,
+            _ => "",
+        }
+    }
+
+    #[test]
+    fn test_query_combinators() {
+        let markdown = "| A | B |\n| - | - |\n| 1 | 2 |\n\n- [x](https://a.com) **[y](/local)**";
+        let ast = parse(markdown, &TranspileOptions::default());
+
+        assert_eq!(query_all(&ast, "table > thead td").unwrap().len(), 2);
+        assert_eq!(query_all(&ast, "table td").unwrap().len(), 4);
+        assert_eq!(query_all(&ast, "table > td").unwrap().len(), 0);
+        assert_eq!(query_all(&ast, "li > a, strong > a").unwrap().len(), 2);
+        assert_eq!(query_all(&ast, "a[href^=\"https:\"]").unwrap().len(), 1);
+        assert_eq!(tag(query(&ast, "ul *").unwrap().unwrap()), "li");
+        assert!(query(&ast, "blink").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_query_class_and_id() {
+        let markdown = "<div class=\"note wide\" id=\"n1\">\n\ntext\n\n</div>";
+        let options = TranspileOptions { allowed_tags: vec!["div".to_string()], ..Default::default() };
+        let ast = parse(markdown, &options);
+
+        assert!(query(&ast, "div.note.wide#n1").unwrap().is_some());
+        assert!(query(&ast, "[class~=wide] > p").unwrap().is_some());
+        assert!(query(&ast, ".missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_query_quoted_values() {
+        let ast = parse("[x](/x \"a, b]\") [y](/y) *z*", &TranspileOptions::default());
+
+        let found = query_all(&ast, "a[title*=\",\"], em").unwrap();
+        assert_eq!(found.iter().map(|node| tag(node)).collect::<Vec<_>>(), ["a", "em"]);
+        assert_eq!(query_all(&ast, "a[title='a, b]'],a[href=\"/y\"]").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_query_mut() {
+        let mut ast = parse("[a](/a) and [b](/b)", &TranspileOptions::default());
+        query_all_mut(&mut ast, "a", |node| {
+            if let Node::Element { props, .. } = node {
+                props.insert("target".to_string(), "_blank".into());
+            }
+        }).unwrap();
+
+        assert_eq!(query_all(&ast, "a[target=_blank]").unwrap().len(), 2);
+        assert!(query_mut(&mut ast, "p > a").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_invalid_selectors() {
+        assert_eq!(Selector::parse(""), Err(SelectorError::Empty));
+        assert_eq!(Selector::parse("ul >"), Err(SelectorError::Empty));
+        assert_eq!(Selector::parse("> li"), Err(SelectorError::Unexpected('>', 0)));
+        assert_eq!(Selector::parse("a[href"), Err(SelectorError::UnterminatedAttribute));
+        assert!("a:hover".parse::<Selector>().is_err());
+    }
+}
@@ -0,0 +1,89 @@
+//! Locale quote styles for smart punctuation.
+
+use std::ops::Range;
+use serde::{Deserialize, Serialize};
+
+/// Opening and closing marks that smart punctuation turns straight quotes
+/// into. Apostrophes inside words (`don't`) stay `’` whatever the style.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+pub struct Quotes {
+    pub double: (String, String),
+    pub single: (String, String),
+}
+
+impl Default for Quotes {
+    fn default() -> Self {
+        Quotes::english()
+    }
+}
+
+impl Quotes {
+    pub fn new(double: (&str, &str), single: (&str, &str)) -> Self {
+        Quotes {
+            double: (double.0.to_string(), double.1.to_string()),
+            single: (single.0.to_string(), single.1.to_string()),
+        }
+    }
+
+    /// “double” and ‘single’.
+    pub fn english() -> Self {
+        Quotes::new(("\u{201c}", "\u{201d}"), ("\u{2018}", "\u{2019}"))
+    }
+
+    /// „double“ and ‚single‘.
+    pub fn german() -> Self {
+        Quotes::new(("\u{201e}", "\u{201c}"), ("\u{201a}", "\u{2018}"))
+    }
+
+    /// « double » and ‹ single ›, with no-break spaces inside the marks.
+    pub fn french() -> Self {
+        Quotes::new(("\u{ab}\u{a0}", "\u{a0}\u{bb}"), ("\u{2039}\u{a0}", "\u{a0}\u{203a}"))
+    }
+
+    /// «double» and ‹single›, as used in Switzerland.
+    pub fn swiss() -> Self {
+        Quotes::new(("\u{ab}", "\u{bb}"), ("\u{2039}", "\u{203a}"))
+    }
+
+    /// The replacement for a curly quote that smart punctuation produced
+    /// from `source[range]`, or `None` to keep it.
+    pub(crate) fn localize(&self, curly: &str, source: &str, range: Range<usize>) -> Option<&str> {
+        let mark = match curly {
+            "\u{201c}" => &self.double.0,
+            "\u{201d}" => &self.double.1,
+            "\u{2018}" => &self.single.0,
+            "\u{2019}" => {
+                let is_word = |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+                let apostrophe = is_word(source[..range.start].chars().next_back())
+                    && is_word(source.get(range.end..).and_then(|rest| rest.chars().next()));
+                if apostrophe {
+                    return None;
+                }
+                &self.single.1
+            }
+            _ => return None,
+        };
+        Some(mark.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, text_content, Quotes, TranspileOptions};
+
+    #[test]
+    fn test_quotes() {
+        let markdown = "\"Don't\" say 'hi'";
+        let text = |quotes: Quotes| text_content(&parse(markdown, &TranspileOptions::builder().quotes(quotes).build()));
+        assert_eq!(text(Quotes::english()), "\u{201c}Don\u{2019}t\u{201d} say \u{2018}hi\u{2019}");
+        assert_eq!(text(Quotes::german()), "\u{201e}Don\u{2019}t\u{201c} say \u{201a}hi\u{2018}");
+        assert_eq!(text(Quotes::french()), "\u{ab}\u{a0}Don\u{2019}t\u{a0}\u{bb} say \u{2039}\u{a0}hi\u{a0}\u{203a}");
+
+        let options = TranspileOptions::builder()
+            .quotes(Quotes::german())
+            .extensions(crate::Extensions { smart_punctuation: false, ..Default::default() })
+            .build();
+        assert_eq!(text_content(&parse(markdown, &options)), markdown);
+    }
+}
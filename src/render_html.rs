@@ -0,0 +1,120 @@
+//! Renders the AST as an HTML string, for server-side and non-React
+//! consumers.
+
+use serde_json::Value;
+use crate::elements::html_attribute;
+use crate::Node;
+
+/// Elements that have no end tag.
+pub(crate) const VOID_TAGS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track", "wbr",
+];
+
+/// Renders `nodes` as HTML. React prop names become attribute names
+/// (`className` to `class`, `htmlFor` to `for`), `true` props are written
+/// bare, and `false`/`null` props and React `key`s are left out. Text and
+/// attribute values are escaped; `raw` nodes of format `"html"` are
+/// written verbatim and other raw nodes as text. Top-level nodes are
+/// separated by newlines.
+pub fn render_html(nodes: &[Node]) -> String {
+    let mut out = String::new();
+    for (i, node) in nodes.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        write_node(node, &mut out);
+    }
+    out
+}
+
+fn write_node(node: &Node, out: &mut String) {
+    match node {
+        Node::Element { tag, props, children, .. } => {
+            out.push('<');
+            out.push_str(tag);
+            for (key, value) in props {
+                write_attribute(key, value, out);
+            }
+            out.push('>');
+            // An unclosed `<br>` in allowed HTML holds what followed it;
+            // that content goes after the tag.
+            for child in children {
+                write_node(child, out);
+            }
+            if VOID_TAGS.contains(&tag.as_str()) {
+                return;
+            }
+            out.push_str("</");
+            out.push_str(tag);
+            out.push('>');
+        }
+        Node::Text { content, .. } => escape(content, false, out),
+        Node::Fragment { children } => {
+            for child in children {
+                write_node(child, out);
+            }
+        }
+        Node::Comment { content, .. } => {
+            out.push_str("<!--");
+            // `--` cannot appear inside a comment.
+            out.push_str(&content.replace("--", "- -"));
+            out.push_str("-->");
+        }
+        Node::Raw { content, format, .. } if format == "html" => out.push_str(content),
+        Node::Raw { content, .. } => escape(content, false, out),
+    }
+}
+
+fn write_attribute(key: &str, value: &Value, out: &mut String) {
+    let name = html_attribute(key);
+    if key == "key" || name.is_empty() || name.contains(|c: char| c.is_whitespace() || "\"'<>/=".contains(c)) {
+        return;
+    }
+    let value = match value {
+        Value::Null | Value::Bool(false) => return,
+        Value::Bool(true) => {
+            out.push(' ');
+            out.push_str(name);
+            return;
+        }
+        Value::String(value) => value.clone(),
+        other => other.to_string(),
+    };
+    out.push(' ');
+    out.push_str(name);
+    out.push_str("=\"");
+    escape(&value, true, out);
+    out.push('"');
+}
+
+pub(crate) fn escape(text: &str, attribute: bool, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' if attribute => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_render_html() {
+        let markdown = "# A & B\n\nText<br>with [a \"link\"](/x?a=1&b=\"2\") and ![i](/i.png)\n\n- [x] done\n\n<!-- note -->";
+        let options = TranspileOptions::builder().allowed_tags(["br"]).build();
+        assert_eq!(render_html(&parse(markdown, &options)), "<h1>A &amp; B</h1>\n\
+            <p>Text<br>with <a href=\"/x?a=1&amp;b=&quot;2&quot;\">a \u{201c}link\u{201d}</a> and <img src=\"/i.png\" alt=\"i\"></p>\n\
+            <ul><li><input type=\"checkbox\" checked disabled>done</li></ul>\n\
+            <!-- note -->");
+
+        let node = Node::element("label").prop("htmlFor", "x").prop("className", "c").prop("key", "1").prop("hidden", false);
+        assert_eq!(render_html(&[node, Node::Raw { content: "<b>".into(), format: "html".into(), span: None }]),
+            "<label for=\"x\" class=\"c\"></label>\n<b>");
+    }
+}
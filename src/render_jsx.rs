@@ -0,0 +1,190 @@
+//! Renders the AST as JSX source, for build steps that compile markdown
+//! into `.jsx`/`.tsx` files.
+
+use indexmap::IndexMap;
+use serde_json::Value;
+use crate::elements::react_prop;
+use crate::Node;
+
+/// Renders `nodes` as one JSX expression: the element itself for a single
+/// node, otherwise a `<>` fragment with one child per line. Props use
+/// React names (`class` becomes `className`) and `style` strings become
+/// style objects. Text that JSX would reinterpret (braces, `<`, `&`, line
+/// breaks) is written as a string expression. `raw` HTML becomes a `div`
+/// with `dangerouslySetInnerHTML`.
+pub fn render_jsx(nodes: &[Node]) -> String {
+    let mut out = String::new();
+    match nodes {
+        [node] => write_node(node, &mut out),
+        nodes => {
+            out.push_str("<>\n");
+            for node in nodes {
+                out.push_str("  ");
+                write_node(node, &mut out);
+                out.push('\n');
+            }
+            out.push_str("</>");
+        }
+    }
+    out
+}
+
+fn write_node(node: &Node, out: &mut String) {
+    match node {
+        Node::Element { tag, props, children, .. } => {
+            out.push('<');
+            out.push_str(tag);
+            for (key, value) in jsx_props(props) {
+                write_prop(&key, &value, out);
+            }
+            if children.is_empty() {
+                out.push_str(" />");
+                return;
+            }
+            out.push('>');
+            for child in children {
+                write_node(child, out);
+            }
+            out.push_str("</");
+            out.push_str(tag);
+            out.push('>');
+        }
+        Node::Text { content, .. } => write_text(content, out),
+        Node::Fragment { children } => {
+            out.push_str("<>");
+            for child in children {
+                write_node(child, out);
+            }
+            out.push_str("</>");
+        }
+        Node::Comment { content, .. } => {
+            out.push_str("{/*");
+            out.push_str(&content.replace("*/", "* /"));
+            out.push_str("*/}");
+        }
+        Node::Raw { content, format, .. } if format == "html" => {
+            out.push_str("<div dangerouslySetInnerHTML={{ __html: ");
+            out.push_str(&js_string(content));
+            out.push_str(" }} />");
+        }
+        Node::Raw { content, .. } => write_text(content, out),
+    }
+}
+
+/// `props` under React names, with a `style` string parsed into an
+/// object and props React would reject (`null`) dropped.
+pub(crate) fn jsx_props(props: &IndexMap<String, Value>) -> Vec<(String, Value)> {
+    props.iter()
+        .filter(|(_, value)| !value.is_null())
+        .map(|(key, value)| match (key.as_str(), value) {
+            ("style", Value::String(css)) => ("style".to_string(), style_object(css)),
+            (key, value) => (react_prop(key).to_string(), value.clone()),
+        })
+        .collect()
+}
+
+/// `color: red; font-size: 2em` as `{"color": "red", "fontSize": "2em"}`.
+/// Custom properties (`--x`) keep their names.
+fn style_object(css: &str) -> Value {
+    let mut style = serde_json::Map::new();
+    for declaration in css.split(';') {
+        let Some((name, value)) = declaration.split_once(':') else { continue };
+        let (name, value) = (name.trim(), value.trim());
+        if name.is_empty() {
+            continue;
+        }
+        let name = match name.starts_with("--") {
+            true => name.to_string(),
+            false => camel_case(&name.to_ascii_lowercase()),
+        };
+        style.insert(name, value.into());
+    }
+    Value::Object(style)
+}
+
+fn camel_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut upper = false;
+    for c in name.chars() {
+        match c {
+            '-' if !out.is_empty() => upper = true,
+            '-' => {}
+            c if upper => {
+                out.push(c.to_ascii_uppercase());
+                upper = false;
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn write_prop(key: &str, value: &Value, out: &mut String) {
+    if key.is_empty() || !key.chars().all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | ':' | '$')) {
+        return;
+    }
+    out.push(' ');
+    out.push_str(key);
+    match value {
+        Value::Bool(true) => {}
+        Value::String(value) if !value.contains(['"', '{', '}', '&', '\n', '\r', '\\']) => {
+            out.push_str("=\"");
+            out.push_str(value);
+            out.push('"');
+        }
+        Value::String(value) => {
+            out.push_str("={");
+            out.push_str(&js_string(value));
+            out.push('}');
+        }
+        value => {
+            out.push_str("={");
+            out.push_str(&js_value(value));
+            out.push('}');
+        }
+    }
+}
+
+fn write_text(text: &str, out: &mut String) {
+    if text.contains(['{', '}', '<', '>', '&', '\n', '\r']) {
+        out.push('{');
+        out.push_str(&js_string(text));
+        out.push('}');
+    } else {
+        out.push_str(text);
+    }
+}
+
+/// A JavaScript string literal. JSON is valid JavaScript once the line
+/// and paragraph separators are escaped; `</` is broken up so the output
+/// can be inlined in a `<script>`.
+pub(crate) fn js_string(text: &str) -> String {
+    js_value(&Value::String(text.to_string()))
+}
+
+pub(crate) fn js_value(value: &Value) -> String {
+    value.to_string()
+        .replace('\u{2028}', "\\u2028")
+        .replace('\u{2029}', "\\u2029")
+        .replace("</", "<\\/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_render_jsx() {
+        let markdown = "# Hi {there}\n\n<label for=\"x\" class=\"c\" style=\"font-size: 2em; --gap: 1px\">a\nb</label>\n\n![i](/i.png)";
+        let options = TranspileOptions::builder().allowed_tags(["label"]).build();
+        assert_eq!(render_jsx(&parse(markdown, &options)), "<>\n\
+            \x20 <h1>{\"Hi {there}\"}</h1>\n\
+            \x20 <p><label htmlFor=\"x\" className=\"c\" style={{\"fontSize\":\"2em\",\"--gap\":\"1px\"}}>a{\"\\n\"}b</label></p>\n\
+            \x20 <p><img src=\"/i.png\" alt=\"i\" /></p>\n\
+            </>");
+
+        let node = Node::element("input").prop("checked", true).prop("disabled", false).prop("title", "say \"hi\"");
+        assert_eq!(render_jsx(&[node]), "<input checked disabled={false} title={\"say \\\"hi\\\"\"} />");
+    }
+}
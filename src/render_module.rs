@@ -0,0 +1,173 @@
+//! Compiles the AST into a JavaScript module exporting a React component,
+//! MDX-style, for build-time compilation from Rust.
+
+use std::collections::BTreeSet;
+use serde_json::Value;
+use crate::render_jsx::{js_string, js_value, jsx_props};
+use crate::Node;
+
+/// Which JSX runtime the generated module calls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JsxRuntime {
+    /// `React.createElement(type, props, ...children)`.
+    #[default]
+    Classic,
+    /// `_jsx`/`_jsxs` from `<import_source>/jsx-runtime`.
+    Automatic,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleOptions {
+    pub runtime: JsxRuntime,
+    /// Name of the exported default function.
+    pub component_name: String,
+    /// Package providing React (`"react"`, `"preact/compat"`, ...).
+    pub import_source: String,
+}
+
+impl Default for ModuleOptions {
+    fn default() -> Self {
+        ModuleOptions {
+            runtime: JsxRuntime::default(),
+            component_name: "Doc".to_string(),
+            import_source: "react".to_string(),
+        }
+    }
+}
+
+/// Generates an ES module whose default export renders `nodes`. Every tag
+/// is looked up in `props.components` first, so callers can swap in their
+/// own `h1` or provide the `CustomBox` an allowed tag refers to. Comments
+/// are left out.
+pub fn render_module(nodes: &[Node], options: &ModuleOptions) -> String {
+    let mut tags = BTreeSet::new();
+    collect_tags(nodes, &mut tags);
+    let components: Vec<String> = tags.iter().map(|tag| format!("{}: {}", js_string(tag), js_string(tag))).collect();
+
+    let mut out = String::new();
+    match options.runtime {
+        JsxRuntime::Classic => {
+            out.push_str(&format!("import React from {};\n\n", js_string(&options.import_source)));
+        }
+        JsxRuntime::Automatic => {
+            let runtime = format!("{}/jsx-runtime", options.import_source);
+            out.push_str(&format!("import {{ jsx as _jsx, jsxs as _jsxs, Fragment as _Fragment }} from {};\n\n", js_string(&runtime)));
+        }
+    }
+    out.push_str(&format!("export default function {}(props) {{\n", options.component_name));
+    out.push_str(&format!("  const _components = {{ {}{}...props.components }};\n", components.join(", "), if components.is_empty() { "" } else { ", " }));
+    let writer = Writer { runtime: options.runtime };
+    out.push_str("  return ");
+    out.push_str(&writer.call(writer.fragment(), &[], nodes, None));
+    out.push_str(";\n}\n");
+    out
+}
+
+fn collect_tags(nodes: &[Node], tags: &mut BTreeSet<String>) {
+    for node in nodes {
+        if let Node::Element { tag, .. } = node {
+            tags.insert(tag.clone());
+        }
+        if let Some(children) = node.children() {
+            collect_tags(children, tags);
+        }
+    }
+}
+
+struct Writer {
+    runtime: JsxRuntime,
+}
+
+impl Writer {
+    fn fragment(&self) -> String {
+        match self.runtime {
+            JsxRuntime::Classic => "React.Fragment".to_string(),
+            JsxRuntime::Automatic => "_Fragment".to_string(),
+        }
+    }
+
+    /// A call creating an element of type `kind` (a JavaScript expression).
+    fn call(&self, kind: String, props: &[(String, Value)], children: &[Node], key: Option<&Value>) -> String {
+        let children: Vec<String> = children.iter().filter_map(|child| self.node(child)).collect();
+        let props: Vec<String> = props.iter().map(|(key, value)| format!("{}: {}", js_string(key), js_value(value))).collect();
+        match self.runtime {
+            JsxRuntime::Classic => {
+                let mut args = vec![kind];
+                let mut props = props;
+                if let Some(key) = key {
+                    props.insert(0, format!("\"key\": {}", js_value(key)));
+                }
+                args.push(match props.is_empty() {
+                    true => "null".to_string(),
+                    false => format!("{{ {} }}", props.join(", ")),
+                });
+                args.extend(children);
+                format!("React.createElement({})", args.join(", "))
+            }
+            JsxRuntime::Automatic => {
+                let mut props = props;
+                let function = match children.len() {
+                    0 => "_jsx",
+                    1 => {
+                        props.push(format!("children: {}", children[0]));
+                        "_jsx"
+                    }
+                    _ => {
+                        props.push(format!("children: [{}]", children.join(", ")));
+                        "_jsxs"
+                    }
+                };
+                let key = key.map_or(String::new(), |key| format!(", {}", js_value(key)));
+                let props = match props.is_empty() {
+                    true => "{}".to_string(),
+                    false => format!("{{ {} }}", props.join(", ")),
+                };
+                format!("{}({}, {}{})", function, kind, props, key)
+            }
+        }
+    }
+
+    fn node(&self, node: &Node) -> Option<String> {
+        match node {
+            Node::Element { tag, props, children, .. } => {
+                let mut props = jsx_props(props);
+                let key = props.iter().position(|(name, _)| name == "key").map(|i| props.remove(i).1);
+                Some(self.call(format!("_components[{}]", js_string(tag)), &props, children, key.as_ref()))
+            }
+            Node::Text { content, .. } => Some(js_string(content)),
+            Node::Fragment { children } => Some(self.call(self.fragment(), &[], children, None)),
+            Node::Comment { .. } => None,
+            Node::Raw { content, format, .. } if format == "html" => {
+                let html = serde_json::json!({ "__html": content });
+                Some(self.call("\"div\"".to_string(), &[("dangerouslySetInnerHTML".to_string(), html)], &[], None))
+            }
+            Node::Raw { content, .. } => Some(js_string(content)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_module() {
+        let nodes = [
+            Node::element("h1").prop("key", "0").child(Node::text("Hi")),
+            Node::element("CustomBox").prop("class", "x").with_children([Node::text("a"), Node::element("br")]),
+        ];
+        assert_eq!(render_module(&nodes, &ModuleOptions::default()), "import React from \"react\";\n\n\
+            export default function Doc(props) {\n  \
+            const _components = { \"CustomBox\": \"CustomBox\", \"br\": \"br\", \"h1\": \"h1\", ...props.components };\n  \
+            return React.createElement(React.Fragment, null, \
+            React.createElement(_components[\"h1\"], { \"key\": \"0\" }, \"Hi\"), \
+            React.createElement(_components[\"CustomBox\"], { \"className\": \"x\" }, \"a\", React.createElement(_components[\"br\"], null)));\n}\n");
+
+        let options = ModuleOptions { runtime: JsxRuntime::Automatic, component_name: "Page".to_string(), ..Default::default() };
+        let module = render_module(&nodes, &options);
+        assert!(module.starts_with("import { jsx as _jsx, jsxs as _jsxs, Fragment as _Fragment } from \"react/jsx-runtime\";\n"));
+        assert!(module.contains("export default function Page(props) {"));
+        assert!(module.contains("return _jsxs(_Fragment, { children: [_jsx(_components[\"h1\"], { children: \"Hi\" }, \"0\"), \
+            _jsxs(_components[\"CustomBox\"], { \"className\": \"x\", children: [\"a\", _jsx(_components[\"br\"], {})] })] });"));
+    }
+}
@@ -0,0 +1,28 @@
+//! JSON Schema of the AST, for validating payloads and generating types
+//! outside Rust.
+
+use crate::Node;
+
+/// JSON Schema (draft 2020-12) describing the output of `parse`: an
+/// array of nodes.
+pub fn schema() -> serde_json::Value {
+    schemars::schema_for!(Vec<Node>).to_value()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema() {
+        let schema = schema();
+        let json = schema.to_string();
+
+        assert_eq!(schema["type"], "array");
+        for variant in ["element", "text", "fragment", "comment", "raw"] {
+            assert!(json.contains(&format!("\"const\":\"{}\"", variant)), "missing {}", variant);
+        }
+        assert!(schema["$defs"]["Span"].is_object());
+        assert_eq!(schema["items"]["$ref"], "#/$defs/Node");
+    }
+}
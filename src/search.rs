@@ -0,0 +1,81 @@
+//! Search index records, one per heading section, in the shape lunr- and
+//! pagefind-style indexes take.
+
+use serde::Serialize;
+use crate::outline::{heading_level, outline};
+use crate::plain::to_plain_text;
+use crate::Node;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchRecord {
+    /// Texts of the enclosing headings, outermost first, ending with the
+    /// section's own heading. Empty for content before the first heading.
+    pub heading_path: Vec<String>,
+    /// Plain text of the section body, up to the next top-level heading.
+    pub text: String,
+    /// The section heading's slug, as in `outline`.
+    pub anchor: Option<String>,
+}
+
+/// Splits `nodes` at every top-level heading into search records.
+pub fn search_index(nodes: &[Node]) -> Vec<SearchRecord> {
+    let headings = outline(nodes);
+    let mut next_heading = 0;
+    let mut path: Vec<(u8, String)> = Vec::new();
+    let mut records = Vec::new();
+    let mut current = SearchRecord { heading_path: Vec::new(), text: String::new(), anchor: None };
+    let mut body_start = 0;
+
+    for (i, node) in nodes.iter().enumerate() {
+        let Some(level) = heading_level(node) else {
+            // Keep slugs in step with `outline`, which also counts
+            // headings nested in other blocks.
+            next_heading += outline(std::slice::from_ref(node)).len();
+            continue;
+        };
+        current.text = to_plain_text(&nodes[body_start..i]);
+        if current.anchor.is_some() || !current.text.is_empty() {
+            records.push(current);
+        }
+        let heading = &headings[next_heading];
+        next_heading += 1;
+        path.retain(|(outer, _)| *outer < level);
+        path.push((level, heading.text.clone()));
+        current = SearchRecord {
+            heading_path: path.iter().map(|(_, text)| text.clone()).collect(),
+            text: String::new(),
+            anchor: Some(heading.slug.clone()),
+        };
+        body_start = i + 1;
+    }
+    current.text = to_plain_text(&nodes[body_start..]);
+    if current.anchor.is_some() || !current.text.is_empty() {
+        records.push(current);
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_search_index() {
+        let markdown = "Intro.\n\n# Guide\n\nStart here.\n\n> ## Quoted\n\n## Install\n\nRun *it*.\n\n# FAQ";
+        let ast = parse(markdown, &TranspileOptions::default());
+        let records = search_index(&ast);
+
+        let summary: Vec<_> = records.iter()
+            .map(|r| (r.heading_path.join(" > "), r.text.as_str(), r.anchor.as_deref()))
+            .collect();
+        assert_eq!(summary, [
+            (String::new(), "Intro.", None),
+            ("Guide".to_string(), "Start here.\n\nQuoted", Some("guide")),
+            ("Guide > Install".to_string(), "Run it.", Some("install")),
+            ("FAQ".to_string(), "", Some("faq")),
+        ]);
+        assert!(search_index(&[]).is_empty());
+    }
+}
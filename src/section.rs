@@ -0,0 +1,60 @@
+//! Grouping of top-level nodes into heading sections.
+
+use crate::outline::heading_level;
+use crate::Node;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Section<'a> {
+    /// `None` for the content before the first heading.
+    pub heading: Option<&'a Node>,
+    /// The nodes up to the next heading that starts a section.
+    pub body: &'a [Node],
+}
+
+/// Splits `nodes` at every top-level heading of `level` or above (`h1` to
+/// `h{level}`). Deeper headings stay in the body of their section.
+///
+/// Leading content before the first such heading becomes a section without
+/// a heading; it is left out when empty.
+pub fn split_sections(nodes: &[Node], level: u8) -> Vec<Section<'_>> {
+    let starts_section = |node: &Node| heading_level(node).is_some_and(|l| l <= level);
+    let mut sections = Vec::new();
+    let mut start = 0;
+    let mut heading = None;
+    for (i, node) in nodes.iter().enumerate() {
+        if starts_section(node) {
+            if heading.is_some() || i > 0 {
+                sections.push(Section { heading, body: &nodes[start..i] });
+            }
+            heading = Some(node);
+            start = i + 1;
+        }
+    }
+    if heading.is_some() || start < nodes.len() {
+        sections.push(Section { heading, body: &nodes[start..] });
+    }
+    sections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_split_sections() {
+        let ast = parse("Intro\n\n# A\n\na\n\n## A.1\n\n# B\n\n## B.1\n\nb", &TranspileOptions::default());
+
+        let sections = split_sections(&ast, 1);
+        assert_eq!(sections.len(), 3);
+        assert_eq!(sections[0], Section { heading: None, body: &ast[..1] });
+        assert_eq!(sections[1], Section { heading: Some(&ast[1]), body: &ast[2..4] });
+        assert_eq!(sections[2], Section { heading: Some(&ast[4]), body: &ast[5..] });
+
+        let sections = split_sections(&ast[1..], 2);
+        let bodies: Vec<_> = sections.iter().map(|s| s.body.len()).collect();
+        assert_eq!(bodies, [1, 0, 0, 1]);
+        assert!(sections.iter().all(|s| s.heading.is_some()));
+        assert!(split_sections(&[], 1).is_empty());
+    }
+}
@@ -0,0 +1,240 @@
+//! Serialization of the AST with a configurable node shape, for clients
+//! whose schema predates md2jsx.
+//!
+//! `SerializeConfig::wrap` returns a `Serialize` value, so any serde
+//! format can be used:
+//!
+//! ```
+//! use md2jsx::{parse, SerializeConfig, TagCase, TranspileOptions};
+//!
+//! let nodes = parse("Hi", &TranspileOptions::default());
+//! let config = SerializeConfig {
+//!     tag_field: "nodeType".to_string(),
+//!     tag_case: TagCase::Upper,
+//!     empty_props: false,
+//! };
+//! let json = serde_json::to_string(&config.wrap(&nodes)).unwrap();
+//! assert_eq!(json, r#"[{"nodeType":"ELEMENT","tag":"p","children":[{"nodeType":"TEXT","content":"Hi"}]}]"#);
+//! ```
+
+use std::io::{self, Write};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde_json::{Map, Value};
+use crate::Node;
+
+/// Formatting of `to_json`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JsonStyle {
+    /// No whitespace, keys in field order.
+    #[default]
+    Compact,
+    /// Two-space indentation, keys in field order.
+    Pretty,
+    /// No whitespace, object keys (including props) sorted at every
+    /// level, so equal trees always give the same bytes; for snapshots
+    /// and content hashes.
+    Canonical,
+}
+
+/// Serializes `nodes` in the default shape.
+pub fn to_json(nodes: &[Node], style: JsonStyle) -> String {
+    match style {
+        JsonStyle::Compact => serde_json::to_string(nodes),
+        JsonStyle::Pretty => serde_json::to_string_pretty(nodes),
+        JsonStyle::Canonical => {
+            let value = serde_json::to_value(nodes).expect("nodes serialize to JSON");
+            serde_json::to_string(&sort_keys(value))
+        }
+    }
+    .expect("nodes serialize to JSON")
+}
+
+/// Writes each top-level node as one line of JSON (NDJSON), so a reader
+/// can handle blocks as they arrive. Nothing is buffered beyond the
+/// current node; wrap `writer` in a `BufWriter` for unbuffered sinks.
+pub fn write_ndjson<W: Write>(nodes: &[Node], mut writer: W) -> io::Result<()> {
+    for node in nodes {
+        serde_json::to_writer(&mut writer, node)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()
+}
+
+fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = map.into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(entries.into_iter().map(|(key, value)| (key, sort_keys(value))).collect::<Map<_, _>>())
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(sort_keys).collect()),
+        value => value,
+    }
+}
+
+/// How node kinds are spelled in the discriminator field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TagCase {
+    /// `"element"`, as in the default serialization.
+    #[default]
+    Lower,
+    /// `"ELEMENT"`.
+    Upper,
+    /// `"Element"`.
+    Pascal,
+}
+
+#[derive(Debug, Clone)]
+pub struct SerializeConfig {
+    /// Name of the field holding the node kind.
+    pub tag_field: String,
+    pub tag_case: TagCase,
+    /// Write `props` on elements that have none.
+    pub empty_props: bool,
+}
+
+/// The default shape: `{"type": "element", "props": {}, ...}`.
+impl Default for SerializeConfig {
+    fn default() -> Self {
+        SerializeConfig {
+            tag_field: "type".to_string(),
+            tag_case: TagCase::Lower,
+            empty_props: true,
+        }
+    }
+}
+
+impl SerializeConfig {
+    /// Wraps `nodes` for serialization in this shape.
+    pub fn wrap<'a>(&'a self, nodes: &'a [Node]) -> Configured<'a> {
+        Configured { nodes, config: self }
+    }
+
+    fn kind(&self, kind: &'static str) -> String {
+        match self.tag_case {
+            TagCase::Lower => kind.to_string(),
+            TagCase::Upper => kind.to_uppercase(),
+            TagCase::Pascal => kind[..1].to_uppercase() + &kind[1..],
+        }
+    }
+}
+
+/// Nodes paired with a `SerializeConfig`; see `SerializeConfig::wrap`.
+#[derive(Clone, Copy)]
+pub struct Configured<'a> {
+    nodes: &'a [Node],
+    config: &'a SerializeConfig,
+}
+
+impl Serialize for Configured<'_> {
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        serializer.collect_seq(self.nodes.iter().map(|node| ConfiguredNode { node, config: self.config }))
+    }
+}
+
+struct ConfiguredNode<'a> {
+    node: &'a Node,
+    config: &'a SerializeConfig,
+}
+
+impl Serialize for ConfiguredNode<'_> {
+    fn serialize<Ser: Serializer>(&self, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        let config = self.config;
+        let children = |nodes| Configured { nodes, config };
+        let mut map = serializer.serialize_map(None)?;
+        let span = match self.node {
+            Node::Element { tag, props, children: nodes, span, content_id } => {
+                map.serialize_entry(&config.tag_field, &config.kind("element"))?;
+                map.serialize_entry("tag", tag)?;
+                if config.empty_props || !props.is_empty() {
+                    map.serialize_entry("props", props)?;
+                }
+                map.serialize_entry("children", &children(nodes))?;
+                if let Some(span) = span {
+                    map.serialize_entry("span", span)?;
+                }
+                if let Some(id) = content_id {
+                    map.serialize_entry("__id", id)?;
+                }
+                return map.end();
+            }
+            Node::Text { content, span } => {
+                map.serialize_entry(&config.tag_field, &config.kind("text"))?;
+                map.serialize_entry("content", content)?;
+                span
+            }
+            Node::Fragment { children: nodes } => {
+                map.serialize_entry(&config.tag_field, &config.kind("fragment"))?;
+                map.serialize_entry("children", &children(nodes))?;
+                &None
+            }
+            Node::Comment { content, span } => {
+                map.serialize_entry(&config.tag_field, &config.kind("comment"))?;
+                map.serialize_entry("content", content)?;
+                span
+            }
+            Node::Raw { content, format, span } => {
+                map.serialize_entry(&config.tag_field, &config.kind("raw"))?;
+                map.serialize_entry("content", content)?;
+                map.serialize_entry("format", format)?;
+                span
+            }
+        };
+        if let Some(span) = span {
+            map.serialize_entry("span", span)?;
+        }
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_default_config_matches_derived() {
+        let options = TranspileOptions { source_spans: true, content_ids: true, ..Default::default() };
+        let ast = parse("# Hi <!-- c -->\n\n[a](/x)\n\n<script>x</script>", &options);
+
+        let configured = serde_json::to_string(&SerializeConfig::default().wrap(&ast)).unwrap();
+        assert_eq!(configured, serde_json::to_string(&ast).unwrap());
+    }
+
+    #[test]
+    fn test_serialize_config() {
+        let ast = vec![Node::fragment([Node::element("br"), Node::comment("c")])];
+        let config = SerializeConfig {
+            tag_field: "kind".to_string(),
+            tag_case: TagCase::Pascal,
+            empty_props: false,
+        };
+
+        let json = serde_json::to_string(&config.wrap(&ast)).unwrap();
+        assert_eq!(json, r#"[{"kind":"Fragment","children":[{"kind":"Element","tag":"br","children":[]},{"kind":"Comment","content":"c"}]}]"#);
+    }
+
+    #[test]
+    fn test_to_json() {
+        let a = vec![Node::element("a").prop("href", "/x").prop("title", "t").child(Node::text("x"))];
+        let b = vec![Node::element("a").prop("title", "t").prop("href", "/x").child(Node::text("x"))];
+
+        assert_eq!(to_json(&a, JsonStyle::Compact), serde_json::to_string(&a).unwrap());
+        assert!(to_json(&a, JsonStyle::Pretty).contains("\n  {\n    \"type\": \"element\""));
+        assert_eq!(to_json(&a, JsonStyle::Canonical), to_json(&b, JsonStyle::Canonical));
+        assert_eq!(
+            to_json(&a, JsonStyle::Canonical),
+            r#"[{"children":[{"content":"x","type":"text"}],"props":{"href":"/x","title":"t"},"tag":"a","type":"element"}]"#
+        );
+    }
+
+    #[test]
+    fn test_write_ndjson() {
+        let ast = parse("# A\n\nb", &TranspileOptions::default());
+        let mut out = Vec::new();
+        write_ndjson(&ast, &mut out).unwrap();
+
+        let lines: Vec<Node> = String::from_utf8(out).unwrap().lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines, ast);
+    }
+}
@@ -0,0 +1,205 @@
+//! Slate editor value output.
+
+use serde_json::{json, Map, Value};
+use crate::markdown::BLOCK_TAGS;
+use crate::{text_content, Node};
+
+/// Element types by tag, after Slate's rich text example. Other elements
+/// use their tag as `type`.
+const TYPES: &[(&str, &str)] = &[
+    ("p", "paragraph"),
+    ("h1", "heading-one"),
+    ("h2", "heading-two"),
+    ("h3", "heading-three"),
+    ("h4", "heading-four"),
+    ("h5", "heading-five"),
+    ("h6", "heading-six"),
+    ("blockquote", "block-quote"),
+    ("ul", "bulleted-list"),
+    ("ol", "numbered-list"),
+    ("li", "list-item"),
+    ("pre", "code-block"),
+    ("hr", "thematic-break"),
+    ("img", "image"),
+    ("a", "link"),
+    ("table", "table"),
+    ("tr", "table-row"),
+    ("th", "table-cell"),
+    ("td", "table-cell"),
+];
+
+/// Text marks by tag, set as `true` on the leaves inside.
+const MARKS: &[(&str, &str)] = &[
+    ("strong", "bold"),
+    ("b", "bold"),
+    ("em", "italic"),
+    ("i", "italic"),
+    ("u", "underline"),
+    ("del", "strikethrough"),
+    ("s", "strikethrough"),
+    ("code", "code"),
+];
+
+/// Converts `nodes` to a Slate value: elements with a `type` and
+/// `children`, text leaves with boolean marks (`{"text": "b", "bold":
+/// true}`). Links keep their `url` and images (void elements) their `url`
+/// and `alt`; code blocks hold their code as one leaf with `language`, and
+/// `br` becomes a newline. Other props are copied onto the element, and
+/// comments and raw content are dropped. Inline content next to blocks is
+/// wrapped in a paragraph.
+pub fn to_slate(nodes: &[Node]) -> Value {
+    Value::Array(blocks(nodes))
+}
+
+fn is_block(node: &Node) -> bool {
+    match node {
+        Node::Element { tag, children, .. } => BLOCK_TAGS.contains(&tag.as_str()) || children.iter().any(is_block),
+        Node::Fragment { children } => children.iter().any(is_block),
+        _ => false,
+    }
+}
+
+fn blocks(nodes: &[Node]) -> Vec<Value> {
+    let mut blocks = Vec::new();
+    let mut run = Vec::new();
+    for node in nodes {
+        if is_block(node) {
+            flush(&mut run, &mut blocks);
+            block(node, &mut blocks);
+        } else {
+            inline(node, &Map::new(), &mut run);
+        }
+    }
+    flush(&mut run, &mut blocks);
+    blocks
+}
+
+fn flush(run: &mut Vec<Value>, blocks: &mut Vec<Value>) {
+    if run.iter().all(|leaf| leaf["text"].as_str().is_some_and(|text| text.trim().is_empty())) {
+        run.clear();
+        return;
+    }
+    blocks.push(element("paragraph", Map::new(), std::mem::take(run)));
+}
+
+/// An element; Slate requires at least one child, so void and empty
+/// elements get an empty leaf.
+fn element(kind: &str, mut properties: Map<String, Value>, mut children: Vec<Value>) -> Value {
+    if children.is_empty() {
+        children.push(json!({ "text": "" }));
+    }
+    properties.insert("type".into(), kind.into());
+    properties.insert("children".into(), children.into());
+    Value::Object(properties)
+}
+
+fn kind(tag: &str) -> &str {
+    TYPES.iter().find(|&&(t, _)| t == tag).map_or(tag, |&(_, kind)| kind)
+}
+
+fn properties(tag: &str, props: &indexmap::IndexMap<String, Value>) -> Map<String, Value> {
+    let mut properties = Map::new();
+    for (key, value) in props {
+        let key = match (tag, key.as_str()) {
+            (_, "key") => continue,
+            ("a", "href") | ("img", "src") => "url",
+            (_, key) => key,
+        };
+        properties.insert(key.to_string(), value.clone());
+    }
+    properties
+}
+
+fn block(node: &Node, out: &mut Vec<Value>) {
+    let Node::Element { tag, props, children, .. } = node else {
+        if let Node::Fragment { children } = node {
+            out.extend(blocks(children));
+        }
+        return;
+    };
+    let mut properties = properties(tag, props);
+    let children = match tag.as_str() {
+        "pre" => {
+            let code = match children.as_slice() {
+                [Node::Element { tag, props, .. }] if tag == "code" => {
+                    let class = props.get("className").or_else(|| props.get("class")).and_then(Value::as_str).unwrap_or_default();
+                    let language = class.split_whitespace().find_map(|class| class.strip_prefix("language-"));
+                    properties.insert("language".into(), language.into());
+                    text_content(children)
+                }
+                _ => text_content(children),
+            };
+            vec![json!({ "text": code.strip_suffix('\n').unwrap_or(&code) })]
+        }
+        "hr" => Vec::new(),
+        _ if children.iter().any(is_block) => blocks(children),
+        _ => {
+            let mut leaves = Vec::new();
+            for child in children {
+                inline(child, &Map::new(), &mut leaves);
+            }
+            leaves
+        }
+    };
+    out.push(element(kind(tag), properties, children));
+}
+
+fn inline(node: &Node, marks: &Map<String, Value>, out: &mut Vec<Value>) {
+    match node {
+        Node::Text { content, .. } => {
+            let mut leaf = marks.clone();
+            leaf.insert("text".into(), content.as_str().into());
+            out.push(Value::Object(leaf));
+        }
+        Node::Element { tag, props, children, .. } => {
+            if let Some(&(_, mark)) = MARKS.iter().find(|&&(t, _)| t == tag) {
+                let mut marks = marks.clone();
+                marks.insert(mark.into(), true.into());
+                for child in children {
+                    inline(child, &marks, out);
+                }
+            } else if tag == "br" {
+                inline(&Node::text("\n"), marks, out);
+            } else if tag == "input" {
+                // Task list checkboxes have no Slate equivalent here.
+            } else {
+                let mut leaves = Vec::new();
+                for child in children {
+                    inline(child, marks, &mut leaves);
+                }
+                out.push(element(kind(tag), properties(tag, props), leaves));
+            }
+        }
+        Node::Fragment { children } => {
+            for child in children {
+                inline(child, marks, out);
+            }
+        }
+        Node::Comment { .. } | Node::Raw { .. } => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_to_slate() {
+        let markdown = "# Hi\n\nA **b *c*** [d](/x) ![e](/e.png)\n\n- item\n\n---";
+        assert_eq!(to_slate(&parse(markdown, &TranspileOptions::default())), json!([
+            { "type": "heading-one", "children": [{ "text": "Hi" }] },
+            { "type": "paragraph", "children": [
+                { "text": "A " },
+                { "text": "b ", "bold": true },
+                { "text": "c", "bold": true, "italic": true },
+                { "text": " " },
+                { "type": "link", "url": "/x", "children": [{ "text": "d" }] },
+                { "text": " " },
+                { "type": "image", "url": "/e.png", "alt": "e", "children": [{ "text": "" }] },
+            ] },
+            { "type": "bulleted-list", "children": [{ "type": "list-item", "children": [{ "text": "item" }] }] },
+            { "type": "thematic-break", "children": [{ "text": "" }] },
+        ]));
+    }
+}
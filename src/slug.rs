@@ -0,0 +1,150 @@
+//! Heading slugs: the GitHub-compatible algorithm by default, or any
+//! `Slugger` a site's existing anchors were made with.
+
+use std::collections::HashMap;
+use std::fmt;
+use crate::outline::heading_level;
+use crate::visitor::{walk, Flow, VisitorMut};
+use crate::{text_content, Node};
+
+/// Turns heading text into an anchor. Repeats within a document are told
+/// apart by `dedupe`. Any `Fn(&str) -> String` closure is a slugger that
+/// dedupes GitHub-style.
+pub trait Slugger: Send + Sync {
+    fn slug(&self, text: &str) -> String;
+
+    /// The anchor for the `count`th repeat (from 1) of `slug`.
+    fn dedupe(&self, slug: &str, count: usize) -> String {
+        format!("{}-{}", slug, count)
+    }
+}
+
+impl<F> Slugger for F
+where
+    F: Fn(&str) -> String + Send + Sync,
+{
+    fn slug(&self, text: &str) -> String {
+        self(text)
+    }
+}
+
+impl fmt::Debug for dyn Slugger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Slugger")
+    }
+}
+
+/// Slugs the way GitHub does: lowercase, punctuation dropped, spaces as
+/// hyphens, and `-1`, `-2`, ... appended to repeats.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GithubSlugger;
+
+impl Slugger for GithubSlugger {
+    fn slug(&self, text: &str) -> String {
+        text.trim()
+            .to_lowercase()
+            .chars()
+            .filter_map(|c| match c {
+                ' ' => Some('-'),
+                c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Hands out slugs that are unique within one document.
+pub(crate) struct UniqueSlugs<'s> {
+    slugger: &'s dyn Slugger,
+    seen: HashMap<String, usize>,
+}
+
+impl<'s> UniqueSlugs<'s> {
+    pub(crate) fn new(slugger: &'s dyn Slugger) -> Self {
+        UniqueSlugs { slugger, seen: HashMap::new() }
+    }
+
+    pub(crate) fn slug(&mut self, text: &str) -> String {
+        let base = self.slugger.slug(text);
+        let mut slug = base.clone();
+        while self.seen.contains_key(&slug) {
+            let count = self.seen.get_mut(&base).expect("base slug was seen first");
+            *count += 1;
+            slug = self.slugger.dedupe(&base, *count);
+        }
+        self.seen.insert(slug.clone(), 0);
+        slug
+    }
+}
+
+impl Default for UniqueSlugs<'_> {
+    fn default() -> Self {
+        UniqueSlugs::new(&GithubSlugger)
+    }
+}
+
+/// Gives every heading without an `id` one slugged from its text.
+pub(crate) fn assign_heading_ids(nodes: &mut [Node], slugger: &dyn Slugger) {
+    walk(nodes, &mut HeadingIds(UniqueSlugs::new(slugger)));
+}
+
+struct HeadingIds<'s>(UniqueSlugs<'s>);
+
+impl VisitorMut for HeadingIds<'_> {
+    fn enter(&mut self, node: &mut Node) -> Flow {
+        if heading_level(node).is_none() {
+            return Flow::Continue;
+        }
+        if let Node::Element { props, children, .. } = node {
+            if !props.contains_key("id") {
+                let slug = self.0.slug(&text_content(children));
+                props.insert("id".to_string(), slug.into());
+            }
+        }
+        Flow::SkipChildren
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{outline, parse, TranspileOptions};
+
+    #[test]
+    fn test_slugger() {
+        let mut slugger = UniqueSlugs::default();
+        assert_eq!(slugger.slug("Hello, World!"), "hello-world");
+        assert_eq!(slugger.slug("Hello World"), "hello-world-1");
+        assert_eq!(slugger.slug("hello world"), "hello-world-2");
+        assert_eq!(slugger.slug("Ünïcode  & snake_case"), "ünïcode---snake_case");
+        assert_eq!(slugger.slug("A-1"), "a-1");
+        assert_eq!(slugger.slug("a"), "a");
+        assert_eq!(slugger.slug("a"), "a-2");
+    }
+
+    struct Asciidoc;
+
+    impl Slugger for Asciidoc {
+        fn slug(&self, text: &str) -> String {
+            let words: Vec<String> = text.split_whitespace().map(str::to_lowercase).collect();
+            format!("_{}", words.join("_"))
+        }
+
+        fn dedupe(&self, slug: &str, count: usize) -> String {
+            format!("{}_{}", slug, count + 1)
+        }
+    }
+
+    #[test]
+    fn test_heading_ids() {
+        let markdown = "# Getting Started\n\n## Setup\n\n## Setup";
+        let ast = parse(markdown, &TranspileOptions::builder().heading_ids(true).build());
+        let ids: Vec<_> = outline(&ast).into_iter().map(|h| h.slug).collect();
+        assert_eq!(ids, ["getting-started", "setup", "setup-1"]);
+        assert_eq!(ast[0], Node::element("h1").prop("id", "getting-started").child(Node::text("Getting Started")));
+
+        let options = TranspileOptions::builder().heading_ids(true).slugger(Asciidoc).build();
+        let ids: Vec<_> = outline(&parse(markdown, &options)).into_iter().map(|h| h.slug).collect();
+        assert_eq!(ids, ["_getting_started", "_setup", "_setup_2"]);
+    }
+}
@@ -0,0 +1,104 @@
+//! Document statistics for blog frontends: word count, reading time and
+//! counts of code blocks, images and links.
+
+use serde::Serialize;
+use crate::markdown::BLOCK_TAGS;
+use crate::visitor::{visit, Flow, Visitor};
+use crate::Node;
+
+/// Reading speed used for `reading_minutes`.
+const WORDS_PER_MINUTE: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+#[serde(rename_all = "camelCase")]
+pub struct DocStats {
+    /// Whitespace-separated words in all text, code included.
+    pub words: usize,
+    /// Characters (not bytes) in all text.
+    pub characters: usize,
+    pub code_blocks: usize,
+    pub images: usize,
+    /// Links with an `href`, not counting footnote references.
+    pub links: usize,
+    /// `words` at 200 words per minute, rounded up.
+    pub reading_minutes: usize,
+}
+
+/// Computes statistics for `nodes` in one walk.
+pub fn stats(nodes: &[Node]) -> DocStats {
+    let mut counter = Counter::default();
+    visit(nodes, &mut counter);
+    let mut stats = counter.stats;
+    stats.reading_minutes = stats.words.div_ceil(WORDS_PER_MINUTE);
+    stats
+}
+
+#[derive(Default)]
+struct Counter {
+    stats: DocStats,
+    /// Whether the last text seen ended inside a word, so a word split
+    /// across inline elements (`**bold**er`) is counted once.
+    in_word: bool,
+}
+
+impl Visitor for Counter {
+    fn enter(&mut self, node: &Node) -> Flow {
+        match node {
+            Node::Element { tag, props, .. } => {
+                if BLOCK_TAGS.contains(&tag.as_str()) || tag == "br" {
+                    self.in_word = false;
+                }
+                match tag.as_str() {
+                    "pre" => self.stats.code_blocks += 1,
+                    "img" => self.stats.images += 1,
+                    "a" if props.contains_key("href")
+                        && props.get("className").and_then(|v| v.as_str()) != Some("footnote-ref") =>
+                    {
+                        self.stats.links += 1
+                    }
+                    _ => {}
+                }
+            }
+            Node::Text { content, .. } => {
+                self.stats.characters += content.chars().count();
+                for c in content.chars() {
+                    let word = !c.is_whitespace();
+                    if word && !self.in_word {
+                        self.stats.words += 1;
+                    }
+                    self.in_word = word;
+                }
+            }
+            Node::Fragment { .. } | Node::Comment { .. } | Node::Raw { .. } => {}
+        }
+        Flow::Continue
+    }
+
+    fn exit(&mut self, node: &Node) {
+        if matches!(node, Node::Element { tag, .. } if BLOCK_TAGS.contains(&tag.as_str())) {
+            self.in_word = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_stats() {
+        let markdown = "# Two words\n\nSome **bold**er text, a [link](/x) and ![img](/i.png) here.[^1]\n\n```\nlet x = 1;\n```\n\n[^1]: Note.";
+        let ast = parse(markdown, &TranspileOptions::default());
+
+        let doc = stats(&ast);
+        assert_eq!(doc.words, 2 + 7 + 4 + 1);
+        assert_eq!(doc.characters, "Two words".len() + "Some bolder text, a link and  here.1".len() + "let x = 1;\n".len() + "Note.".len());
+        assert_eq!(doc.code_blocks, 1);
+        assert_eq!(doc.images, 1);
+        assert_eq!(doc.links, 1);
+        assert_eq!(doc.reading_minutes, 1);
+        assert_eq!(stats(&[]), DocStats::default());
+    }
+}
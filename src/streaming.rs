@@ -0,0 +1,87 @@
+//! Parsing a document as it arrives, handing out top-level blocks once
+//! later input can no longer change them.
+
+use crate::{IncrementalParser, Node, TranspileOptions};
+
+/// Parses markdown pushed in chunks. Each `push` returns the top-level
+/// nodes completed so far; the last block is held back, since the next
+/// chunk may extend it, until `finish`.
+///
+/// Built on `IncrementalParser`, so the same options (`footnotes`,
+/// `heading_ids`, ...) are not applied. A push reparses the chunk with the
+/// last blocks before it, costing about their size plus the number of
+/// definitions so far; a chunk that adds a link reference or footnote
+/// definition reparses the whole document once, and with `keys` set every
+/// push reassigns keys across the tree. Definitions that arrive after the
+/// blocks using them have been returned cannot change those blocks.
+pub struct StreamingParser {
+    parser: IncrementalParser,
+    /// Top-level nodes already returned.
+    emitted: usize,
+}
+
+impl StreamingParser {
+    pub fn new(options: TranspileOptions) -> Self {
+        StreamingParser { parser: IncrementalParser::new("", options), emitted: 0 }
+    }
+
+    /// Appends `chunk` and returns the top-level nodes it completed.
+    pub fn push(&mut self, chunk: &str) -> Vec<Node> {
+        let end = self.parser.source().len();
+        self.parser.edit(end..end, chunk);
+        let complete = self.parser.nodes().len().saturating_sub(1);
+        self.take(complete)
+    }
+
+    /// Returns the remaining nodes, the last block included.
+    pub fn finish(mut self) -> Vec<Node> {
+        let all = self.parser.nodes().len();
+        self.take(all)
+    }
+
+    fn take(&mut self, until: usize) -> Vec<Node> {
+        if until <= self.emitted {
+            return Vec::new();
+        }
+        let nodes = self.parser.nodes()[self.emitted..until].to_vec();
+        self.emitted = until;
+        nodes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_streaming_parser() {
+        let source = "# Title\n\nA *long* para\ngraph.\n\n- a\n- b\n\n```\ncode\n\n```\n\nLast.\n";
+        let options = TranspileOptions { source_spans: true, ..Default::default() };
+        let mut parser = StreamingParser::new(options.clone());
+
+        let mut nodes = Vec::new();
+        let mut pushes = Vec::new();
+        for chunk in source.as_bytes().chunks(5) {
+            let emitted = parser.push(std::str::from_utf8(chunk).unwrap());
+            pushes.push(emitted.len());
+            nodes.extend(emitted);
+        }
+        assert_eq!(pushes[..3], [0, 1, 0]);
+        nodes.extend(parser.finish());
+        assert_eq!(nodes, parse(source, &options));
+    }
+
+    #[test]
+    fn test_streaming_parser_definitions() {
+        let source = "[x]: /url\n\n[^n]: Note.\n\nSee [x][^n].\n\nMore [x].\n\nLast [x].\n";
+        let mut parser = StreamingParser::new(TranspileOptions::default());
+
+        let mut nodes = Vec::new();
+        for chunk in source.as_bytes().chunks(7) {
+            nodes.extend(parser.push(std::str::from_utf8(chunk).unwrap()));
+        }
+        nodes.extend(parser.finish());
+        assert_eq!(nodes, parse(source, &TranspileOptions::default()));
+    }
+}
@@ -0,0 +1,225 @@
+//! The AST flattened to one string with style ranges, the shape native
+//! text APIs (Compose `AnnotatedString`, `NSAttributedString`) build from.
+
+use serde_json::Value;
+use crate::markdown::BLOCK_TAGS;
+use crate::{text_content, Node};
+
+/// Character-level styles.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum InlineStyle {
+    Bold,
+    Italic,
+    Strikethrough,
+    Underline,
+    Code,
+    Superscript,
+    Subscript,
+    Link(String),
+}
+
+/// A style over `start..end`, in UTF-16 code units as Kotlin and Swift
+/// strings index them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct StyleRange {
+    pub start: usize,
+    pub end: usize,
+    pub style: InlineStyle,
+}
+
+/// A paragraph (a leaf block) over `start..end`, without the newline that
+/// ends it.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub(crate) struct Paragraph {
+    pub start: usize,
+    pub end: usize,
+    pub heading: Option<u8>,
+    /// Blockquote nesting.
+    pub quote: usize,
+    /// List nesting.
+    pub list: usize,
+    pub code: bool,
+}
+
+/// Text with blocks separated by newlines, list markers (`• `, `1. `)
+/// written into the text, soft breaks as spaces and `br` as a newline
+/// within its paragraph. Images contribute their `alt` text; comments and
+/// raw content are dropped.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub(crate) struct StyledText {
+    pub text: String,
+    pub spans: Vec<StyleRange>,
+    pub paragraphs: Vec<Paragraph>,
+}
+
+pub(crate) fn styled_text(nodes: &[Node]) -> StyledText {
+    let mut builder = Builder::default();
+    builder.blocks(nodes);
+    builder.out
+}
+
+fn is_block(node: &Node) -> bool {
+    match node {
+        Node::Element { tag, children, .. } => BLOCK_TAGS.contains(&tag.as_str()) || children.iter().any(is_block),
+        Node::Fragment { children } => children.iter().any(is_block),
+        _ => false,
+    }
+}
+
+#[derive(Default)]
+struct Builder {
+    out: StyledText,
+    /// Length of the text so far in UTF-16 code units.
+    len: usize,
+    quote: usize,
+    list: usize,
+    /// A list marker for the next paragraph.
+    marker: Option<String>,
+}
+
+impl Builder {
+    fn push(&mut self, text: &str) {
+        self.out.text.push_str(text);
+        self.len += text.encode_utf16().count();
+    }
+
+    fn blocks(&mut self, nodes: &[Node]) {
+        let mut run: Vec<&Node> = Vec::new();
+        for node in nodes {
+            if is_block(node) {
+                self.paragraph(&std::mem::take(&mut run), Paragraph::default());
+                self.block(node);
+            } else {
+                run.push(node);
+            }
+        }
+        self.paragraph(&run, Paragraph::default());
+    }
+
+    /// Writes a paragraph of inline `nodes`, unless they are blank.
+    fn paragraph<N: std::borrow::Borrow<Node>>(&mut self, nodes: &[N], style: Paragraph) {
+        let blank = nodes.iter().all(|node| matches!(node.borrow(), Node::Text { content, .. } if content.trim().is_empty()));
+        if blank && !style.code {
+            return;
+        }
+        if !self.out.text.is_empty() {
+            self.push("\n");
+        }
+        let start = self.len;
+        if let Some(marker) = self.marker.take() {
+            self.push(&marker);
+        }
+        for node in nodes {
+            self.inline(node.borrow());
+        }
+        let end = self.len;
+        self.out.paragraphs.push(Paragraph { start, end, quote: self.quote, list: self.list, ..style });
+    }
+
+    fn block(&mut self, node: &Node) {
+        let Node::Element { tag, props, children, .. } = node else {
+            if let Node::Fragment { children } = node {
+                self.blocks(children);
+            }
+            return;
+        };
+        match tag.as_str() {
+            "p" => self.paragraph(children, Paragraph::default()),
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.paragraph(children, Paragraph { heading: tag[1..].parse().ok(), ..Default::default() });
+            }
+            "blockquote" => {
+                self.quote += 1;
+                self.blocks(children);
+                self.quote -= 1;
+            }
+            "ul" | "ol" => {
+                let start = props.get("start").and_then(Value::as_u64).unwrap_or(1);
+                self.list += 1;
+                for (number, item) in (start..).zip(children.iter().filter(|item| item.tag() == Some("li"))) {
+                    self.marker = Some(match tag.as_str() {
+                        "ol" => format!("{}. ", number),
+                        _ => "\u{2022} ".to_string(),
+                    });
+                    self.blocks(item.children().map_or(&[][..], Vec::as_slice));
+                    self.marker = None;
+                }
+                self.list -= 1;
+            }
+            "pre" => {
+                let code = text_content(children);
+                let code = Node::text(code.strip_suffix('\n').unwrap_or(&code));
+                self.paragraph(&[code], Paragraph { code: true, ..Default::default() });
+            }
+            "hr" => self.paragraph(&[Node::text("\u{2014}".repeat(12))], Paragraph::default()),
+            "tr" => {
+                let mut cells = Vec::new();
+                for cell in children.iter().filter(|cell| matches!(cell.tag(), Some("td" | "th"))) {
+                    if !cells.is_empty() {
+                        cells.push(Node::text("\t"));
+                    }
+                    cells.push(cell.clone());
+                }
+                self.paragraph(&cells, Paragraph::default());
+            }
+            _ => self.blocks(children),
+        }
+    }
+
+    fn inline(&mut self, node: &Node) {
+        match node {
+            Node::Text { content, .. } => self.push(&content.replace('\n', " ")),
+            Node::Element { tag, props, children, .. } => {
+                let string = |key: &str| props.get(key).and_then(Value::as_str).unwrap_or_default();
+                let style = match tag.as_str() {
+                    "strong" | "b" | "th" => Some(InlineStyle::Bold),
+                    "em" | "i" => Some(InlineStyle::Italic),
+                    "del" | "s" => Some(InlineStyle::Strikethrough),
+                    "u" => Some(InlineStyle::Underline),
+                    "code" | "kbd" => Some(InlineStyle::Code),
+                    "sup" => Some(InlineStyle::Superscript),
+                    "sub" => Some(InlineStyle::Subscript),
+                    "a" if !string("href").is_empty() => Some(InlineStyle::Link(string("href").to_string())),
+                    "br" => return self.push("\n"),
+                    "img" => return self.push(string("alt")),
+                    "input" if string("type") == "checkbox" => {
+                        let checked = props.get("checked").and_then(Value::as_bool).unwrap_or(false);
+                        return self.push(if checked { "\u{2611} " } else { "\u{2610} " });
+                    }
+                    _ => None,
+                };
+                let start = self.len;
+                for child in children {
+                    self.inline(child);
+                }
+                if let Some(style) = style.filter(|_| self.len > start) {
+                    self.out.spans.push(StyleRange { start, end: self.len, style });
+                }
+            }
+            Node::Fragment { children } => {
+                for child in children {
+                    self.inline(child);
+                }
+            }
+            Node::Comment { .. } | Node::Raw { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_styled_text() {
+        let styled = styled_text(&parse("# Hé\n\n> 😀 **b**\n\n- x", &TranspileOptions::default()));
+        assert_eq!(styled.text, "Hé\n😀 b\n\u{2022} x");
+        assert_eq!(styled.spans, [StyleRange { start: 6, end: 7, style: InlineStyle::Bold }]);
+        assert_eq!(styled.paragraphs, [
+            Paragraph { start: 0, end: 2, heading: Some(1), ..Default::default() },
+            Paragraph { start: 3, end: 7, quote: 1, ..Default::default() },
+            Paragraph { start: 8, end: 11, list: 1, ..Default::default() },
+        ]);
+    }
+}
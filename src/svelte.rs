@@ -0,0 +1,153 @@
+//! Svelte output: the AST as `.svelte` component markup.
+
+use std::collections::BTreeSet;
+use serde_json::Value;
+use crate::elements::html_attribute;
+use crate::render_html::{self, VOID_TAGS};
+use crate::render_jsx::{js_string, js_value};
+use crate::Node;
+
+/// Renders `nodes` as the markup of a `.svelte` component. Capitalized
+/// tags are custom components, imported in a leading `<script>` from
+/// `{component_dir}/{Tag}.svelte` (`Foo.Bar` imports `Foo`). `raw` HTML is
+/// written as `{@html "..."}`; other raw nodes as text. Props use HTML
+/// names, with non-string values as `{expressions}`, and braces in text
+/// and attribute values are escaped so Svelte does not evaluate them.
+pub fn render_svelte(nodes: &[Node], component_dir: &str) -> String {
+    let mut components = BTreeSet::new();
+    collect_components(nodes, &mut components);
+
+    let mut out = String::new();
+    if !components.is_empty() {
+        out.push_str("<script>\n");
+        for component in &components {
+            let path = format!("{}/{}.svelte", component_dir.trim_end_matches('/'), component);
+            out.push_str(&format!("  import {} from {};\n", component, js_string(&path)));
+        }
+        out.push_str("</script>\n\n");
+    }
+    for (i, node) in nodes.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        write_node(node, &mut out);
+    }
+    out
+}
+
+fn collect_components<'n>(nodes: &'n [Node], components: &mut BTreeSet<&'n str>) {
+    for node in nodes {
+        if let Node::Element { tag, .. } = node {
+            let name = tag.split('.').next().unwrap_or_default();
+            let identifier = name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+            if name.starts_with(|c: char| c.is_ascii_uppercase()) && identifier {
+                components.insert(name);
+            }
+        }
+        if let Some(children) = node.children() {
+            collect_components(children, components);
+        }
+    }
+}
+
+fn write_node(node: &Node, out: &mut String) {
+    match node {
+        Node::Element { tag, props, children, .. } => {
+            out.push('<');
+            out.push_str(tag);
+            for (key, value) in props {
+                write_attribute(key, value, out);
+            }
+            out.push('>');
+            for child in children {
+                write_node(child, out);
+            }
+            if VOID_TAGS.contains(&tag.as_str()) {
+                return;
+            }
+            out.push_str("</");
+            out.push_str(tag);
+            out.push('>');
+        }
+        Node::Text { content, .. } => escape(content, false, out),
+        Node::Fragment { children } => {
+            for child in children {
+                write_node(child, out);
+            }
+        }
+        Node::Comment { content, .. } => {
+            out.push_str("<!--");
+            out.push_str(&content.replace("--", "- -"));
+            out.push_str("-->");
+        }
+        Node::Raw { content, format, .. } if format == "html" => {
+            out.push_str("{@html ");
+            out.push_str(&js_string(content));
+            out.push('}');
+        }
+        Node::Raw { content, .. } => escape(content, false, out),
+    }
+}
+
+fn write_attribute(key: &str, value: &Value, out: &mut String) {
+    let name = html_attribute(key);
+    if key == "key" || name.is_empty() || name.contains(|c: char| c.is_whitespace() || "\"'<>/={}".contains(c)) {
+        return;
+    }
+    out.push(' ');
+    out.push_str(name);
+    match value {
+        Value::String(value) => {
+            out.push_str("=\"");
+            escape(value, true, out);
+            out.push('"');
+        }
+        Value::Null => out.push_str("={null}"),
+        Value::Bool(true) => {}
+        value => {
+            out.push_str("={");
+            out.push_str(&js_value(value));
+            out.push('}');
+        }
+    }
+}
+
+/// HTML escaping plus `{` and `}`, which start Svelte expressions.
+fn escape(text: &str, attribute: bool, out: &mut String) {
+    for part in text.split_inclusive(['{', '}']) {
+        match part.strip_suffix('{') {
+            Some(part) => {
+                render_html::escape(part, attribute, out);
+                out.push_str("&#123;");
+            }
+            None => match part.strip_suffix('}') {
+                Some(part) => {
+                    render_html::escape(part, attribute, out);
+                    out.push_str("&#125;");
+                }
+                None => render_html::escape(part, attribute, out),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_render_svelte() {
+        let markdown = "# {title}\n\n<Alert kind=\"{x}\">Hi</Alert>";
+        let options = TranspileOptions::builder().allowed_tags(["Alert"]).build();
+        assert_eq!(render_svelte(&parse(markdown, &options), "$lib/components/"), "<script>\n  \
+            import Alert from \"$lib/components/Alert.svelte\";\n</script>\n\n\
+            <h1>&#123;title&#125;</h1>\n<p><Alert kind=\"&#123;x&#125;\">Hi</Alert></p>");
+
+        let nodes = [
+            Node::element("input").prop("disabled", true).prop("maxLength", 3),
+            Node::Raw { content: "<b>\"</b>".into(), format: "html".into(), span: None },
+        ];
+        assert_eq!(render_svelte(&nodes, "."), "<input disabled maxlength={3}>\n{@html \"<b>\\\"<\\/b>\"}");
+    }
+}
@@ -0,0 +1,153 @@
+//! Post-parse AST transforms, run by `parse` in registration order, and
+//! the lighter text hooks run around parsing.
+
+use std::fmt;
+use crate::visitor::{walk, Flow, VisitorMut};
+use crate::Node;
+
+/// Elements whose text `text_hook` leaves alone.
+const VERBATIM_TAGS: &[&str] = &["pre", "code", "script", "style"];
+
+/// A rewrite applied to the whole document after parsing (slug injection,
+/// link rewriting, class mapping, ...). Any `Fn(&mut Vec<Node>)` closure
+/// is a transform; types wrapping a `VisitorMut` can implement it directly.
+pub trait Transform: Send + Sync {
+    fn apply(&self, nodes: &mut Vec<Node>);
+}
+
+impl<F> Transform for F
+where
+    F: Fn(&mut Vec<Node>) + Send + Sync,
+{
+    fn apply(&self, nodes: &mut Vec<Node>) {
+        self(nodes)
+    }
+}
+
+impl fmt::Debug for dyn Transform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Transform")
+    }
+}
+
+/// Rewrites the markdown source before parsing (shortcode expansion,
+/// legacy syntax fixes, ...). Spans then refer to the rewritten source. Any
+/// `Fn(&str) -> String` closure is a preprocessor.
+pub trait Preprocess: Send + Sync {
+    fn preprocess(&self, markdown: &str) -> String;
+}
+
+impl<F> Preprocess for F
+where
+    F: Fn(&str) -> String + Send + Sync,
+{
+    fn preprocess(&self, markdown: &str) -> String {
+        self(markdown)
+    }
+}
+
+impl fmt::Debug for dyn Preprocess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Preprocess")
+    }
+}
+
+/// Rewrites the content of each text node after parsing (typography,
+/// emoji, masking), skipping text in `pre`, `code`, `script` and `style`.
+/// Any `Fn(&str) -> String` closure is a hook.
+pub trait TextHook: Send + Sync {
+    fn rewrite(&self, text: &str) -> String;
+}
+
+impl<F> TextHook for F
+where
+    F: Fn(&str) -> String + Send + Sync,
+{
+    fn rewrite(&self, text: &str) -> String {
+        self(text)
+    }
+}
+
+impl fmt::Debug for dyn TextHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TextHook")
+    }
+}
+
+struct TextRewriter<'h>(&'h dyn TextHook);
+
+impl VisitorMut for TextRewriter<'_> {
+    fn enter(&mut self, node: &mut Node) -> Flow {
+        match node {
+            Node::Element { tag, .. } if VERBATIM_TAGS.contains(&tag.as_str()) => Flow::SkipChildren,
+            Node::Text { content, .. } => {
+                *content = self.0.rewrite(content);
+                Flow::Continue
+            }
+            _ => Flow::Continue,
+        }
+    }
+}
+
+pub(crate) fn rewrite_text(nodes: &mut [Node], hook: Option<&dyn TextHook>) {
+    if let Some(hook) = hook {
+        walk(nodes, &mut TextRewriter(hook));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::{parse, walk, Flow, TranspileOptions, VisitorMut};
+
+    struct ClassMapper;
+
+    impl VisitorMut for ClassMapper {
+        fn enter(&mut self, node: &mut Node) -> Flow {
+            if let Node::Element { tag, props, .. } = node {
+                if tag == "p" {
+                    props.insert("className".to_string(), "md-p".into());
+                }
+            }
+            Flow::Continue
+        }
+    }
+
+    impl Transform for ClassMapper {
+        fn apply(&self, nodes: &mut Vec<Node>) {
+            walk(nodes, &mut ClassMapper);
+        }
+    }
+
+    #[test]
+    fn test_transforms_run_in_order() {
+        let options = TranspileOptions {
+            transforms: vec![
+                Arc::new(ClassMapper),
+                Arc::new(|nodes: &mut Vec<Node>| nodes.retain(|n| !matches!(n, Node::Element { tag, .. } if tag == "h1"))),
+            ],
+            ..Default::default()
+        };
+        let ast = parse("# Dropped\n\nKept", &options);
+
+        assert_eq!(ast.len(), 1);
+        if let Node::Element { tag, props, .. } = &ast[0] {
+            assert_eq!(tag, "p");
+            assert_eq!(props.get("className").unwrap(), "md-p");
+        }
+    }
+
+    #[test]
+    fn test_text_hooks() {
+        let options = TranspileOptions::builder()
+            .preprocess(|markdown: &str| markdown.replace(":wave:", "\u{1f44b}"))
+            .text_hook(|text: &str| text.replace("darn", "d**n"))
+            .build();
+        let ast = parse("darn :wave: `darn`", &options);
+        assert_eq!(ast, [Node::element("p").with_children([
+            Node::text("d**n \u{1f44b} "),
+            Node::element("code").child(Node::text("darn")),
+        ])]);
+    }
+}
@@ -0,0 +1,68 @@
+//! TypeScript declarations for the AST and options, generated from the
+//! Rust types so the published typings cannot drift from them.
+
+use ts_rs::TS;
+use crate::{CodeHandler, Construct, DocStats, Extensions, FootnoteMode, FootnoteNames, KeyStrategy, Node, PropStyle, Quotes, RawTextPolicy, SchemeAction, SoftBreak, Span, TranspileOptions, UrlSchemes, Whitespace};
+
+/// Per-variant aliases, so consumers can name one kind of node.
+const NODE_ALIASES: &[(&str, &str)] = &[
+    ("ElementNode", "element"),
+    ("TextNode", "text"),
+    ("FragmentNode", "fragment"),
+    ("CommentNode", "comment"),
+    ("RawNode", "raw"),
+];
+
+/// Contents of a `.d.ts` file declaring `Node` (with `ElementNode`,
+/// `TextNode`, ... aliases), `Span`, `TranspileOptions` and `DocStats`.
+pub fn typescript_definitions() -> String {
+    let decls = [
+        serde_json::Value::decl(),
+        Span::decl(),
+        <Node>::decl(),
+        RawTextPolicy::decl(),
+        KeyStrategy::decl(),
+        Extensions::decl(),
+        Construct::decl(),
+        CodeHandler::decl(),
+        SoftBreak::decl(),
+        Quotes::decl(),
+        Whitespace::decl(),
+        PropStyle::decl(),
+        FootnoteNames::decl(),
+        FootnoteMode::decl(),
+        SchemeAction::decl(),
+        UrlSchemes::decl(),
+        TranspileOptions::decl(),
+        DocStats::decl(),
+    ];
+    let mut out = String::from("// Generated by md2jsx. Do not edit.\n");
+    for decl in decls {
+        out.push_str("\nexport ");
+        out.push_str(&decl);
+        out.push('\n');
+    }
+    out.push('\n');
+    for (name, tag) in NODE_ALIASES {
+        out.push_str(&format!("export type {} = Extract<Node, {{ type: \"{}\" }}>;\n", name, tag));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typescript_definitions() {
+        let ts = typescript_definitions();
+
+        assert!(ts.contains("export type Node = "));
+        assert!(ts.contains("export type ElementNode = Extract<Node, { type: \"element\" }>;"));
+        assert!(ts.contains("export type TranspileOptions = "));
+        assert!(ts.contains("allowed_tags: Array<string>"));
+        assert!(ts.contains("span?: Span"));
+        assert!(!ts.contains("transforms:"));
+        assert!(ts.contains("export type DocStats = {") && ts.contains("readingMinutes: number"));
+    }
+}
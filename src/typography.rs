@@ -0,0 +1,98 @@
+//! Typographic replacements on top of smart punctuation, from
+//! `TranspileOptions::typography`.
+
+use crate::visitor::{walk, Flow, VisitorMut};
+use crate::{Node, TranspileOptions};
+
+/// markdown-it's typographer replacements, plus arrows.
+pub const TYPOGRAPHER: &[(&str, &str)] = &[
+    ("(c)", "\u{a9}"),
+    ("(C)", "\u{a9}"),
+    ("(r)", "\u{ae}"),
+    ("(R)", "\u{ae}"),
+    ("(tm)", "\u{2122}"),
+    ("(TM)", "\u{2122}"),
+    ("+-", "\u{b1}"),
+    ("->", "\u{2192}"),
+    ("<-", "\u{2190}"),
+    ("<->", "\u{2194}"),
+    ("=>", "\u{21d2}"),
+    ("<=>", "\u{21d4}"),
+];
+
+/// Text in these elements is left alone.
+const VERBATIM_TAGS: &[&str] = &["pre", "code", "script", "style"];
+
+struct Replacer<'t> {
+    /// Patterns, longest first so `<->` wins over `<-`.
+    patterns: Vec<(&'t str, &'t str)>,
+}
+
+impl VisitorMut for Replacer<'_> {
+    fn enter(&mut self, node: &mut Node) -> Flow {
+        match node {
+            Node::Element { tag, .. } if VERBATIM_TAGS.contains(&tag.as_str()) => Flow::SkipChildren,
+            Node::Text { content, .. } => {
+                if let Some(replaced) = self.replace(content) {
+                    *content = replaced;
+                }
+                Flow::Continue
+            }
+            _ => Flow::Continue,
+        }
+    }
+}
+
+impl Replacer<'_> {
+    fn replace(&self, text: &str) -> Option<String> {
+        if !self.patterns.iter().any(|(from, _)| text.contains(from)) {
+            return None;
+        }
+        let mut replaced = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(c) = rest.chars().next() {
+            match self.patterns.iter().find(|(from, _)| rest.starts_with(from)) {
+                Some((from, to)) => {
+                    replaced.push_str(to);
+                    rest = &rest[from.len()..];
+                }
+                None => {
+                    replaced.push(c);
+                    rest = &rest[c.len_utf8()..];
+                }
+            }
+        }
+        Some(replaced)
+    }
+}
+
+pub(crate) fn apply_typography(nodes: &mut [Node], options: &TranspileOptions) {
+    if options.typography.is_empty() || !options.extensions.smart_punctuation {
+        return;
+    }
+    let mut patterns: Vec<(&str, &str)> = options.typography.iter()
+        .filter(|(from, _)| !from.is_empty())
+        .map(|(from, to)| (from.as_str(), to.as_str()))
+        .collect();
+    patterns.sort_by_key(|(from, _)| std::cmp::Reverse(from.len()));
+    walk(nodes, &mut Replacer { patterns });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, text_content, Extensions, TranspileOptions};
+
+    #[test]
+    fn test_typography() {
+        let markdown = "(c) a -> b <-> c -- d `->`";
+        let options = TranspileOptions::builder().normalize(true).typographer().typography("\u{2013}", "\u{2014}").build();
+        assert_eq!(text_content(&parse(markdown, &options)), "\u{a9} a \u{2192} b \u{2194} c \u{2014} d ->");
+
+        let options = TranspileOptions::builder()
+            .normalize(true)
+            .typographer()
+            .extensions(Extensions { smart_punctuation: false, ..Default::default() })
+            .build();
+        assert_eq!(text_content(&parse(markdown, &options)), "(c) a -> b <-> c -- d ->");
+    }
+}
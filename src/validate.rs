@@ -0,0 +1,109 @@
+//! Structural checks for trees that React or the DOM would reject at
+//! hydration, typically produced by malformed embedded HTML.
+
+use serde::Serialize;
+use crate::markdown::BLOCK_TAGS;
+use crate::Node;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ViolationKind {
+    /// A block element inside a `p`, which the HTML parser would close.
+    BlockInParagraph,
+    /// An `li` outside `ul`/`ol`.
+    ListItemOutsideList,
+    /// A `td`/`th` outside `tr`.
+    CellOutsideRow,
+    /// A `tr` outside `table`/`thead`/`tbody`/`tfoot`.
+    RowOutsideTable,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Violation {
+    /// Index path of the offending element, as in `Patch`.
+    pub path: Vec<usize>,
+    pub kind: ViolationKind,
+    /// Tag of the offending element.
+    pub tag: String,
+    /// Tag of its parent element, if any. Fragments are transparent.
+    pub parent: Option<String>,
+}
+
+/// Checks `nodes` for elements nested where HTML does not allow them.
+pub fn validate(nodes: &[Node]) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    check(nodes, None, false, &mut Vec::new(), &mut violations);
+    violations
+}
+
+fn check(nodes: &[Node], parent: Option<&str>, in_paragraph: bool, path: &mut Vec<usize>, out: &mut Vec<Violation>) {
+    for (i, node) in nodes.iter().enumerate() {
+        path.push(i);
+        match node {
+            Node::Element { tag, children, .. } => {
+                let kind = match tag.as_str() {
+                    "li" if !matches!(parent, Some("ul" | "ol")) => Some(ViolationKind::ListItemOutsideList),
+                    "td" | "th" if parent != Some("tr") => Some(ViolationKind::CellOutsideRow),
+                    "tr" if !matches!(parent, Some("table" | "thead" | "tbody" | "tfoot")) => {
+                        Some(ViolationKind::RowOutsideTable)
+                    }
+                    tag if in_paragraph && BLOCK_TAGS.contains(&tag) => Some(ViolationKind::BlockInParagraph),
+                    _ => None,
+                };
+                if let Some(kind) = kind {
+                    out.push(Violation {
+                        path: path.clone(),
+                        kind,
+                        tag: tag.clone(),
+                        parent: parent.map(String::from),
+                    });
+                }
+                check(children, Some(tag), in_paragraph || tag == "p", path, out);
+            }
+            Node::Fragment { children } => check(children, parent, in_paragraph, path, out),
+            Node::Text { .. } | Node::Comment { .. } | Node::Raw { .. } => {}
+        }
+        path.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_validate_markdown_is_valid() {
+        let ast = parse("# A\n\n- b\n  1. c\n\n| d |\n| - |\n| e |\n\n> f", &TranspileOptions::default());
+        assert_eq!(validate(&ast), []);
+    }
+
+    #[test]
+    fn test_validate() {
+        let options = TranspileOptions {
+            allowed_tags: vec!["div".to_string(), "li".to_string(), "span".to_string()],
+            ..Default::default()
+        };
+        let ast = parse("text <span><div>x</div></span>\n\n<li>y</li>", &options);
+        let violations = validate(&ast);
+
+        assert_eq!(violations, [
+            Violation {
+                path: vec![0, 1, 0],
+                kind: ViolationKind::BlockInParagraph,
+                tag: "div".to_string(),
+                parent: Some("span".to_string()),
+            },
+            Violation {
+                path: vec![1],
+                kind: ViolationKind::ListItemOutsideList,
+                tag: "li".to_string(),
+                parent: None,
+            },
+        ]);
+
+        let row = Node::fragment([Node::element("tr").child(Node::element("td"))]);
+        let kinds: Vec<_> = validate(&[row]).into_iter().map(|v| v.kind).collect();
+        assert_eq!(kinds, [ViolationKind::RowOutsideTable]);
+    }
+}
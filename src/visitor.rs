@@ -0,0 +1,150 @@
+//! Depth-first traversal of the AST with enter/exit hooks.
+
+use crate::Node;
+
+/// Returned from `enter` to steer the traversal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Flow {
+    /// Descend into the node's children.
+    #[default]
+    Continue,
+    /// Skip the node's children; `exit` is still called for the node.
+    SkipChildren,
+    /// End the traversal immediately.
+    Stop,
+}
+
+/// Read-only AST visitor. Both hooks default to no-ops.
+pub trait Visitor {
+    fn enter(&mut self, _node: &Node) -> Flow {
+        Flow::Continue
+    }
+
+    fn exit(&mut self, _node: &Node) {}
+}
+
+/// AST visitor that may modify nodes in place. `enter` runs before the
+/// node's children are walked, so changes to `children` are visible to
+/// the rest of the traversal.
+pub trait VisitorMut {
+    fn enter(&mut self, _node: &mut Node) -> Flow {
+        Flow::Continue
+    }
+
+    fn exit(&mut self, _node: &mut Node) {}
+}
+
+/// Walks `nodes` depth-first, calling the visitor's hooks on every node.
+pub fn visit(nodes: &[Node], visitor: &mut impl Visitor) {
+    visit_nodes(nodes, visitor);
+}
+
+/// Walks `nodes` depth-first, letting the visitor modify every node.
+pub fn walk(nodes: &mut [Node], visitor: &mut impl VisitorMut) {
+    walk_nodes(nodes, visitor);
+}
+
+/// Returns false once the traversal has been stopped.
+fn visit_nodes(nodes: &[Node], visitor: &mut impl Visitor) -> bool {
+    for node in nodes {
+        match visitor.enter(node) {
+            Flow::Stop => return false,
+            Flow::SkipChildren => {}
+            Flow::Continue => {
+                if let Some(children) = node.children() {
+                    if !visit_nodes(children, visitor) {
+                        return false;
+                    }
+                }
+            }
+        }
+        visitor.exit(node);
+    }
+    true
+}
+
+fn walk_nodes(nodes: &mut [Node], visitor: &mut impl VisitorMut) -> bool {
+    for node in nodes {
+        match visitor.enter(node) {
+            Flow::Stop => return false,
+            Flow::SkipChildren => {}
+            Flow::Continue => {
+                if let Some(children) = node.children_mut() {
+                    if !walk_nodes(children, visitor) {
+                        return false;
+                    }
+                }
+            }
+        }
+        visitor.exit(node);
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    struct LinkRewriter;
+
+    impl VisitorMut for LinkRewriter {
+        fn enter(&mut self, node: &mut Node) -> Flow {
+            if let Node::Element { tag, props, .. } = node {
+                if tag == "a" {
+                    if let Some(serde_json::Value::String(href)) = props.get_mut("href") {
+                        *href = href.replace(".md", ".html");
+                    }
+                }
+            }
+            Flow::Continue
+        }
+    }
+
+    #[derive(Default)]
+    struct TagCollector {
+        tags: Vec<String>,
+        depth: usize,
+        max_depth: usize,
+    }
+
+    impl Visitor for TagCollector {
+        fn enter(&mut self, node: &Node) -> Flow {
+            if let Node::Element { tag, .. } = node {
+                self.tags.push(tag.clone());
+                self.depth += 1;
+                self.max_depth = self.max_depth.max(self.depth);
+                if tag == "h1" {
+                    return Flow::SkipChildren;
+                }
+            }
+            Flow::Continue
+        }
+
+        fn exit(&mut self, node: &Node) {
+            if let Node::Element { .. } = node {
+                self.depth -= 1;
+            }
+        }
+    }
+
+    #[test]
+    fn test_walk_rewrites_links() {
+        let mut ast = parse("See [the guide](guide.md).", &TranspileOptions::default());
+        walk(&mut ast, &mut LinkRewriter);
+
+        let json = serde_json::to_string(&ast).unwrap();
+        assert!(json.contains("guide.html"));
+    }
+
+    #[test]
+    fn test_visit_enter_exit() {
+        let ast = parse("# *Title*\n\n- **a**", &TranspileOptions::default());
+        let mut collector = TagCollector::default();
+        visit(&ast, &mut collector);
+
+        assert_eq!(collector.tags, vec!["h1", "ul", "li", "strong"]);
+        assert_eq!(collector.max_depth, 3);
+        assert_eq!(collector.depth, 0);
+    }
+}
@@ -0,0 +1,105 @@
+//! Vue 3 output: a module exporting a component whose render function
+//! builds the AST with `h()`.
+
+use std::collections::BTreeMap;
+use serde_json::{Map, Value};
+use crate::elements::html_attribute;
+use crate::render_jsx::{js_string, js_value};
+use crate::Node;
+
+/// Generates an ES module exporting a Vue 3 component named
+/// `component_name` that renders `nodes`. Props use HTML names (`class`,
+/// `for`) as Vue expects; attributes such as `v-if` or `:title` from
+/// allowed HTML are passed through unchanged, as `h()` props rather than
+/// directives. Capitalized tags are custom components, looked up with
+/// `resolveComponent`. `raw` HTML is set through `innerHTML` and comments
+/// are left out.
+pub fn render_vue(nodes: &[Node], component_name: &str) -> String {
+    let mut components = BTreeMap::new();
+    collect_components(nodes, &mut components);
+
+    let mut out = String::new();
+    match components.is_empty() {
+        true => out.push_str("import { h } from \"vue\";\n\n"),
+        false => out.push_str("import { h, resolveComponent } from \"vue\";\n\n"),
+    }
+    out.push_str("export default {\n");
+    out.push_str(&format!("  name: {},\n", js_string(component_name)));
+    out.push_str("  render() {\n");
+    for (tag, variable) in &components {
+        out.push_str(&format!("    const {} = resolveComponent({});\n", variable, js_string(tag)));
+    }
+    out.push_str(&format!("    return {};\n", children(nodes, &components)));
+    out.push_str("  },\n};\n");
+    out
+}
+
+fn is_component(tag: &str) -> bool {
+    tag.starts_with(|c: char| c.is_ascii_uppercase())
+}
+
+/// Custom component tags and the variables holding them.
+fn collect_components(nodes: &[Node], components: &mut BTreeMap<String, String>) {
+    for node in nodes {
+        if let Node::Element { tag, .. } = node {
+            if is_component(tag) && !components.contains_key(tag) {
+                let variable: String = tag.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+                components.insert(tag.clone(), format!("_component_{}", variable));
+            }
+        }
+        if let Some(children) = node.children() {
+            collect_components(children, components);
+        }
+    }
+}
+
+fn children(nodes: &[Node], components: &BTreeMap<String, String>) -> String {
+    let mut items = Vec::with_capacity(nodes.len());
+    push_vnodes(nodes, components, &mut items);
+    format!("[{}]", items.join(", "))
+}
+
+fn push_vnodes(nodes: &[Node], components: &BTreeMap<String, String>, items: &mut Vec<String>) {
+    for node in nodes {
+        match node {
+            Node::Element { tag, props, children: kids, .. } => {
+                let kind = components.get(tag).cloned().unwrap_or_else(|| js_string(tag));
+                items.push(format!("h({}, {}, {})", kind, js_value(&vue_props(props)), children(kids, components)));
+            }
+            Node::Text { content, .. } => items.push(js_string(content)),
+            Node::Fragment { children } => push_vnodes(children, components, items),
+            Node::Comment { .. } => {}
+            Node::Raw { content, format, .. } if format == "html" => {
+                items.push(format!("h(\"div\", {{ innerHTML: {} }})", js_string(content)));
+            }
+            Node::Raw { content, .. } => items.push(js_string(content)),
+        }
+    }
+}
+
+/// Props under their HTML names, which Vue uses for both attributes and
+/// DOM props.
+fn vue_props(props: &indexmap::IndexMap<String, Value>) -> Value {
+    let props: Map<String, Value> = props.iter()
+        .filter(|(_, value)| !value.is_null())
+        .map(|(key, value)| (html_attribute(key).to_string(), value.clone()))
+        .collect();
+    Value::Object(props)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_render_vue() {
+        let markdown = "<Alert v-if=\"shown\" :title=\"t\">Hi</Alert>\n\n```js\nx\n```";
+        let options = TranspileOptions::builder().allowed_tags(["Alert"]).build();
+        assert_eq!(render_vue(&parse(markdown, &options), "Doc"), "import { h, resolveComponent } from \"vue\";\n\n\
+            export default {\n  name: \"Doc\",\n  render() {\n    \
+            const _component_Alert = resolveComponent(\"Alert\");\n    \
+            return [h(\"p\", {}, [h(_component_Alert, {\"v-if\":\"shown\",\":title\":\"t\"}, [\"Hi\"])]), \
+            h(\"pre\", {}, [h(\"code\", {\"class\":\"language-js\"}, [\"x\\n\"])])];\n  },\n};\n");
+    }
+}
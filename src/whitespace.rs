@@ -0,0 +1,117 @@
+//! Whitespace cleanup of text nodes, from `TranspileOptions::whitespace`.
+
+use serde::{Deserialize, Serialize};
+use crate::Node;
+
+/// Elements whose whitespace is significant.
+const PRESERVING_TAGS: &[&str] = &["pre", "code", "textarea", "script", "style"];
+
+/// Elements that React does not allow text children in.
+const STRUCTURAL_TAGS: &[&str] = &[
+    "table", "thead", "tbody", "tfoot", "tr", "colgroup", "ul", "ol", "dl", "select", "optgroup",
+];
+
+/// Whitespace cleanup applied after `normalize`. Text inside `pre`,
+/// `code`, `textarea`, `script` and `style` is left alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "typescript", derive(ts_rs::TS))]
+#[serde(default)]
+pub struct Whitespace {
+    /// Trim whitespace at the start and end of each element's content.
+    pub trim: bool,
+    /// Collapse runs of whitespace (line breaks included) to one space.
+    pub collapse: bool,
+    /// Remove whitespace-only text children of `table`, `tr`, `ul` and
+    /// other elements that cannot hold text.
+    pub strip_structural: bool,
+}
+
+impl Whitespace {
+    pub(crate) fn is_noop(&self) -> bool {
+        !self.trim && !self.collapse && !self.strip_structural
+    }
+}
+
+pub(crate) fn clean_whitespace(nodes: &mut [Node], whitespace: &Whitespace) {
+    if whitespace.is_noop() {
+        return;
+    }
+    for node in nodes.iter_mut() {
+        match node {
+            Node::Text { content, .. } if whitespace.collapse => collapse(content),
+            Node::Element { tag, children, .. } if !PRESERVING_TAGS.contains(&tag.as_str()) => {
+                clean_whitespace(children, whitespace);
+                if whitespace.strip_structural && STRUCTURAL_TAGS.contains(&tag.as_str()) {
+                    children.retain(|child| !matches!(child, Node::Text { content, .. } if content.trim().is_empty()));
+                }
+                if whitespace.trim {
+                    trim(children);
+                }
+            }
+            Node::Fragment { children } => clean_whitespace(children, whitespace),
+            _ => {}
+        }
+    }
+}
+
+fn collapse(content: &mut String) {
+    if !content.chars().any(|c| c.is_whitespace() && c != ' ') && !content.contains("  ") {
+        return;
+    }
+    let mut collapsed = String::with_capacity(content.len());
+    let mut in_space = false;
+    for c in content.chars() {
+        if c.is_whitespace() {
+            if !in_space {
+                collapsed.push(' ');
+            }
+            in_space = true;
+        } else {
+            collapsed.push(c);
+            in_space = false;
+        }
+    }
+    *content = collapsed;
+}
+
+/// Trims the first and last text children, dropping them if nothing is
+/// left.
+fn trim(children: &mut Vec<Node>) {
+    if let Some(Node::Text { content, .. }) = children.first_mut() {
+        let trimmed = content.trim_start();
+        if trimmed.len() != content.len() {
+            *content = trimmed.to_string();
+        }
+        if content.is_empty() {
+            children.remove(0);
+        }
+    }
+    if let Some(Node::Text { content, .. }) = children.last_mut() {
+        content.truncate(content.trim_end().len());
+        if content.is_empty() {
+            children.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, Node, TranspileOptions, Whitespace};
+
+    #[test]
+    fn test_whitespace() {
+        let markdown = "<table>\n  <tr> <td>  a   b  </td> </tr>\n</table>\n\nsome   *words*\nhere\n\n    keep   this";
+        let whitespace = Whitespace { trim: true, collapse: true, strip_structural: true };
+        let options = TranspileOptions::builder().allowed_tags(["table", "tr", "td"]).normalize(true).whitespace(whitespace).build();
+        let ast = parse(markdown, &options);
+        assert_eq!(ast, [
+            Node::element("table").child(Node::element("tr").child(Node::element("td").child(Node::text("a b")))),
+            Node::element("p").with_children([
+                Node::text("some "),
+                Node::element("em").child(Node::text("words")),
+                Node::text(" here"),
+            ]),
+            Node::element("pre").child(Node::element("code").child(Node::text("keep   this"))),
+        ]);
+    }
+}
@@ -0,0 +1,125 @@
+//! Renders the AST as well-formed XHTML, for XSLT pipelines and EPUB
+//! packagers.
+
+use serde_json::Value;
+use crate::elements::html_attribute;
+use crate::render_html::{escape, VOID_TAGS};
+use crate::Node;
+
+/// Renders `nodes` as an XHTML fragment. Like `render_html`, but void
+/// elements are self-closed (`<br/>`), `true` props are written as
+/// `checked="checked"`, raw nodes of any format are escaped as text and
+/// characters XML does not allow are dropped.
+pub fn render_xhtml(nodes: &[Node]) -> String {
+    let mut out = String::new();
+    for (i, node) in nodes.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        write_node(node, &mut out);
+    }
+    out
+}
+
+/// Renders `nodes` as a standalone XHTML document, with the XML
+/// declaration and the XHTML namespace, as EPUB content documents need.
+pub fn render_xhtml_document(nodes: &[Node], title: &str) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE html>\n");
+    out.push_str("<html xmlns=\"http://www.w3.org/1999/xhtml\">\n<head><title>");
+    escape_xml(title, false, &mut out);
+    out.push_str("</title></head>\n<body>\n");
+    out.push_str(&render_xhtml(nodes));
+    out.push_str("\n</body>\n</html>\n");
+    out
+}
+
+fn write_node(node: &Node, out: &mut String) {
+    match node {
+        Node::Element { tag, props, children, .. } => {
+            out.push('<');
+            out.push_str(tag);
+            for (key, value) in props {
+                write_attribute(key, value, out);
+            }
+            if VOID_TAGS.contains(&tag.as_str()) {
+                // An unclosed `<br>` in allowed HTML holds what followed
+                // it; that content goes after the tag.
+                out.push_str("/>");
+                for child in children {
+                    write_node(child, out);
+                }
+                return;
+            }
+            out.push('>');
+            for child in children {
+                write_node(child, out);
+            }
+            out.push_str("</");
+            out.push_str(tag);
+            out.push('>');
+        }
+        Node::Text { content, .. } | Node::Raw { content, .. } => escape_xml(content, false, out),
+        Node::Fragment { children } => {
+            for child in children {
+                write_node(child, out);
+            }
+        }
+        Node::Comment { content, .. } => {
+            out.push_str("<!--");
+            // `--` cannot appear inside a comment, nor `-` at its end.
+            let content = content.replace("--", "- -");
+            escape_comment(content.strip_suffix('-').unwrap_or(&content), out);
+            out.push_str("-->");
+        }
+    }
+}
+
+fn write_attribute(key: &str, value: &Value, out: &mut String) {
+    let name = html_attribute(key);
+    if key == "key" || name.is_empty() || name.contains(|c: char| c.is_whitespace() || "\"'<>/=".contains(c)) {
+        return;
+    }
+    let value = match value {
+        Value::Null | Value::Bool(false) => return,
+        Value::Bool(true) => name.to_string(),
+        Value::String(value) => value.clone(),
+        other => other.to_string(),
+    };
+    out.push(' ');
+    out.push_str(name);
+    out.push_str("=\"");
+    escape_xml(&value, true, out);
+    out.push('"');
+}
+
+/// Characters allowed in XML 1.0 documents.
+fn is_xml_char(c: char) -> bool {
+    matches!(c, '\t' | '\n' | '\r' | '\u{20}'..='\u{D7FF}' | '\u{E000}'..='\u{FFFD}' | '\u{10000}'..)
+}
+
+fn escape_xml(text: &str, attribute: bool, out: &mut String) {
+    let text: String = text.chars().filter(|&c| is_xml_char(c)).collect();
+    escape(&text, attribute, out);
+}
+
+fn escape_comment(text: &str, out: &mut String) {
+    out.extend(text.chars().filter(|&c| is_xml_char(c)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, TranspileOptions};
+
+    #[test]
+    fn test_render_xhtml() {
+        let markdown = "A\u{1}<br>b & c\n\n- [x] done\n\n<!-- x- -->";
+        let options = TranspileOptions::builder().allowed_tags(["br"]).build();
+        assert_eq!(render_xhtml(&parse(markdown, &options)), "<p>A<br/>b &amp; c</p>\n\
+            <ul><li><input type=\"checkbox\" checked=\"checked\" disabled=\"disabled\"/>done</li></ul>\n\
+            <!-- x- -->");
+
+        let raw = Node::Raw { content: "<b>".into(), format: "html".into(), span: None };
+        assert!(render_xhtml_document(&[raw], "T & U").contains("<head><title>T &amp; U</title></head>\n<body>\n&lt;b&gt;\n</body>"));
+    }
+}